@@ -1,24 +1,81 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use crate::audio_device::output_devices;
 use crate::audio_player::{AudioPlayerTrait, PlayerCommand};
 use crate::keyboard_controls::{KeyboardControls, CooldownHandler, Cooldown};
-use crate::episodes::Episode;
-use std::io;
+use crate::config::InputMode;
+use crate::episodes::{format_duration, Episode};
+use crate::key_input::KeyEventInput;
+use crate::line_input::read_line_timeout;
+use crate::subscriptions::FeedSettings;
+use std::io::{self, IsTerminal};
+use std::time::Duration;
 
-pub fn play_episode<T: AudioPlayerTrait>(
+const LINE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Starts `episode` at its feed's speed, or `default_speed`, past the feed's intro.
+pub fn start_episode<T: AudioPlayerTrait>(
     player: &mut T,
     episode: &Episode,
+    settings: &FeedSettings,
+    default_speed: f32,
 ) -> Result<()> {
+    player.set_speed(settings.speed.unwrap_or(default_speed))?;
     player.play(episode)?;
-    KeyboardControls::print_help();
-    
-    let stdin = io::stdin();
-    let mut stdin_locked = stdin.lock();
+    if let Some(intro) = settings.skip_intro() {
+        // An episode shorter than the intro plays from the start
+        if player.duration().is_none_or(|duration| intro < duration) {
+            player.seek(intro)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn play_episode<T: AudioPlayerTrait>(
+    player: &mut T,
+    episode: &mut Episode,
+    settings: &FeedSettings,
+    default_speed: f32,
+    controls: &KeyboardControls,
+    input_mode: InputMode,
+) -> Result<()> {
+    start_episode(player, episode, settings, default_speed)?;
+    // The player measured the audio itself, which beats whatever the feed claimed
+    if let Some(duration) = player.duration() {
+        episode.duration = Some(duration);
+    }
+
+    if input_mode == InputMode::Keys && io::stdin().is_terminal() {
+        controls.print_key_help();
+        let mut input = KeyEventInput::new(controls);
+        run(player, |_cooldown_handler: &mut CooldownHandler| input.next_command())?;
+        return finish(player);
+    }
+
+    controls.print_help();
+
+    // Stop waiting for Enter now and then, so the run loop notices a device going away
     let get_stdin_command = |cooldown_handler: &mut CooldownHandler| {
         let mut handler = cooldown_handler.clone();
-        get_next_command(&mut handler, &mut stdin_locked)
+        match read_line_timeout(LINE_POLL_INTERVAL) {
+            Some(line) => controls.read_command(&mut handler, &mut line.as_bytes()),
+            None => PlayerCommand::Ignore,
+        }
     };
     
-    run(player, get_stdin_command)
+    run(player, get_stdin_command)?;
+    finish(player)
+}
+
+/// Stops playback and reports what silence trimming saved.
+fn finish<T: AudioPlayerTrait>(player: &mut T) -> Result<()> {
+    if let Some(saved) = player.time_saved() {
+        println!(
+            "✂️  Trimmed silence saved {} in this episode, {} overall",
+            format_duration(saved.episode),
+            format_duration(saved.overall)
+        );
+    }
+    player.stop()
 }
 
 pub fn run<T: AudioPlayerTrait>(
@@ -28,11 +85,22 @@ pub fn run<T: AudioPlayerTrait>(
     let mut cooldown_handler: CooldownHandler = CooldownHandler::new();
 
     loop {
+        match player.check_output_device() {
+            Ok(Some(device)) => println!("🎧 Output device went away, now playing to {}", device),
+            Ok(None) => {}
+            Err(e) => println!("⚠️  {}", e),
+        }
+
         let command = get_command(&mut cooldown_handler);
         match command {
             PlayerCommand::Quit => break,
             PlayerCommand::Ignore => continue,
-            _ => process_command(player, command)?,
+            _ => {
+                // A failed command should not end playback
+                if let Err(e) = process_command(player, command) {
+                    println!("⚠️  {}", e);
+                }
+            }
         }
     }
     Ok(())
@@ -50,14 +118,89 @@ pub fn process_command<T: AudioPlayerTrait>(
     command: PlayerCommand,
 ) -> Result<()> {
     match command {
-        PlayerCommand::Pause => player.pause()?,
+        PlayerCommand::Pause => {
+            player.pause()?;
+            println!("{}", if player.is_paused() { "⏸️ Paused playback" } else { "▶️ Resumed playback" });
+        },
         PlayerCommand::Play => player.resume()?,
         PlayerCommand::Stop => player.stop()?,
-        PlayerCommand::SkipForward(seconds) => player.skip(seconds)?,
-        PlayerCommand::SkipBackward(seconds) => player.skip(-seconds)?,
-        PlayerCommand::VolumeUp(step) => player.adjust_volume(step)?,
-        PlayerCommand::VolumeDown(step) => player.adjust_volume(-step)?,
+        PlayerCommand::SkipForward(seconds) => {
+            player.skip(seconds)?;
+            println!("⏩ Skipped forward {} seconds. New position: {}", seconds, format_duration(player.position()));
+        },
+        PlayerCommand::SkipBackward(seconds) => {
+            player.skip(-seconds)?;
+            println!("⏪ Skipped backward {} seconds. New position: {}", seconds, format_duration(player.position()));
+        },
+        PlayerCommand::VolumeUp(step) => {
+            player.adjust_volume(step)?;
+            println!("🔊 Volume increased to {:.1}", player.volume());
+        },
+        PlayerCommand::VolumeDown(step) => {
+            player.adjust_volume(-step)?;
+            println!("🔉 Volume decreased to {:.1}", player.volume());
+        },
+        PlayerCommand::Seek(position) => {
+            player.seek(position)?;
+            println!("🎯 Jumped to {}", format_duration(player.position()));
+        },
+        PlayerCommand::SeekPercent(percent) => {
+            player.seek(percent_of_duration(player, percent)?)?;
+            println!("🎯 Jumped to {}", format_duration(player.position()));
+        },
+        PlayerCommand::SetVolume(volume) => {
+            player.set_volume(volume)?;
+            println!("🔊 Volume set to {:.1}", player.volume());
+        },
+        PlayerCommand::ToggleMute => {
+            player.toggle_mute()?;
+            if player.is_muted() {
+                println!("🔇 Muted");
+            } else {
+                println!("🔊 Unmuted, volume {:.1}", player.volume());
+            }
+        },
+        PlayerCommand::Sleep(mode) => {
+            player.set_sleep_timer(mode)?;
+            print_sleep_remaining(player, "😴 Sleep timer set");
+        },
+        PlayerCommand::ExtendSleep(by) => {
+            player.extend_sleep_timer(by)?;
+            print_sleep_remaining(player, "😴 Sleep timer extended");
+        },
+        PlayerCommand::CancelSleep => {
+            player.cancel_sleep_timer();
+            println!("⏰ Sleep timer cancelled");
+        },
+        PlayerCommand::SetOutputDevice(device) => {
+            player.set_output_device(device.as_deref())?;
+            println!("🎧 Playing to {}", player.output_device());
+        },
+        PlayerCommand::ListOutputDevices => {
+            let current = player.output_device();
+            for device in output_devices()? {
+                let marker = if device == current { "*" } else { " " };
+                println!("{} {}", marker, device);
+            }
+        },
+        PlayerCommand::SetSpeed(speed) => {
+            player.set_speed(speed)?;
+            println!("⏱️ Speed set to {:.2}x", speed);
+        },
         _ => (),
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+fn print_sleep_remaining<T: AudioPlayerTrait>(player: &T, message: &str) {
+    if let Some(remaining) = player.sleep_remaining() {
+        println!("{}, stopping in {}", message, format_duration(remaining));
+    }
+}
+
+/// The position `percent` of the way into the episode, clamped to the episode's duration.
+pub fn percent_of_duration<T: AudioPlayerTrait>(player: &T, percent: f32) -> Result<Duration> {
+    let total = player.duration()
+        .ok_or_else(|| anyhow!("Cannot seek to {}%: episode duration is unknown", percent))?;
+    Ok(total.mul_f32(percent.clamp(0.0, 100.0) / 100.0))
+}
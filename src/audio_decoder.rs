@@ -0,0 +1,255 @@
+//! Decodes downloaded audio with symphonia into a source rodio can play and seek to the exact
+//! sample, which rodio's own decoder does not do for every format. It also has the Opus
+//! decoder, which symphonia lacks.
+
+use crate::opus::OpusDecoder;
+use log::warn;
+use rodio::source::SeekError;
+use rodio::Source;
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::Duration;
+use symphonia::core::audio::{AudioBufferRef, Channels, SampleBuffer, SignalSpec};
+use symphonia::core::codecs::{CodecRegistry, Decoder, DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS};
+use symphonia::core::errors::{Error, Result};
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::{Time, TimeBase};
+
+/// Decode errors in a row after which the stream counts as broken rather than glitched.
+const MAX_DECODE_RETRIES: usize = 3;
+
+/// Some formats cannot seek to their very end, so seeks there land this far before it.
+const END_MARGIN: Duration = Duration::from_millis(1);
+
+/// How much audio the Opus decoder needs after a seek before its output is right (RFC 7845).
+const OPUS_PRE_ROLL: Duration = Duration::from_millis(80);
+
+/// How far before the target a seek starts over when the decoder primed past the target.
+const RETRY_PRE_ROLL: Duration = Duration::from_millis(100);
+
+lazy_static::lazy_static! {
+    static ref CODECS: CodecRegistry = {
+        let mut codecs = CodecRegistry::new();
+        symphonia::default::register_enabled_codecs(&mut codecs);
+        codecs.register_all::<OpusDecoder>();
+        codecs
+    };
+}
+
+/// Every codec the player can decode: symphonia's and ours.
+pub fn codecs() -> &'static CodecRegistry {
+    &CODECS
+}
+
+/// A decoded stream of interleaved samples from the first audio track of a file.
+pub struct DecodedAudio {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    time_base: Option<TimeBase>,
+    total_duration: Option<Duration>,
+    /// Audio decoded and dropped before the seek target, for codecs that need to settle.
+    pre_roll: Duration,
+    /// The timestamp the audio starts at: Ogg Opus timestamps count the pre-skip too.
+    start_ts: u64,
+    spec: SignalSpec,
+    buffer: SampleBuffer<i16>,
+    /// The timestamp of the first frame in `buffer`.
+    buffer_ts: u64,
+    /// The next sample of `buffer` to play.
+    offset: usize,
+}
+
+impl DecodedAudio {
+    /// Opens the audio and decodes its first packet, so a codec we cannot decode or data that
+    /// is broken from the start fail here rather than play as silence.
+    pub fn new(bytes: Arc<[u8]>, mime_type: Option<&str>) -> Result<Self> {
+        let mut hint = Hint::new();
+        if let Some(mime_type) = mime_type {
+            hint.mime_type(mime_type);
+        }
+        let stream = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+        let options = FormatOptions { enable_gapless: true, ..Default::default() };
+        let format = symphonia::default::get_probe().format(&hint, stream, &options, &MetadataOptions::default())?.format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or(Error::Unsupported("no audio track"))?;
+        let params = &track.codec_params;
+        let decoder = codecs().make(params, &DecoderOptions::default())?;
+        let time_base = params.time_base.or_else(|| params.sample_rate.map(|rate| TimeBase::new(1, rate)));
+        let total_duration = time_base.zip(params.n_frames).map(|(base, frames)| to_duration(base.calc_time(frames)));
+        let (pre_roll, start_ts) = if params.codec == CODEC_TYPE_OPUS {
+            (OPUS_PRE_ROLL, params.delay.map_or(0, u64::from))
+        } else {
+            (Duration::ZERO, 0)
+        };
+        let spec = SignalSpec::new(params.sample_rate.unwrap_or(44100), params.channels.unwrap_or(Channels::FRONT_LEFT | Channels::FRONT_RIGHT));
+
+        let mut audio = Self {
+            track_id: track.id,
+            format,
+            decoder,
+            time_base,
+            total_duration,
+            pre_roll,
+            start_ts,
+            spec,
+            buffer: SampleBuffer::new(0, spec),
+            buffer_ts: 0,
+            offset: 0,
+        };
+        audio.fill_buffer()?;
+        Ok(audio)
+    }
+
+    /// Decodes until there are samples to play. At the end of the stream the buffer is left
+    /// used up.
+    fn fill_buffer(&mut self) -> Result<()> {
+        while self.decode_packet()? {
+            if !self.buffer.is_empty() {
+                self.offset = 0;
+                return Ok(());
+            }
+        }
+        self.offset = self.buffer.len();
+        Ok(())
+    }
+
+    /// Decodes the next packet of the track into the buffer, skipping up to a few packets that
+    /// fail to decode. Returns false at the end of the stream.
+    fn decode_packet(&mut self) -> Result<bool> {
+        let mut errors = 0;
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(Error::IoError(_)) => return Ok(false),
+                Err(e) => return Err(e),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            match self.decoder.decode(&packet) {
+                Ok(decoded) => {
+                    // A decoder that is still priming, e.g. after a seek, holds back the start
+                    self.buffer_ts = (packet.ts() + packet.dur()).saturating_sub(decoded.frames() as u64);
+                    copy_samples(decoded, &mut self.buffer, &mut self.spec);
+                    return Ok(true);
+                }
+                Err(Error::DecodeError(e)) if errors < MAX_DECODE_RETRIES => {
+                    warn!("Skipping audio packet that failed to decode: {}", e);
+                    errors += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Decodes and drops the audio before `target`, leaving the next sample on `channel`.
+    /// Returns false when the decoded audio only starts after `target`.
+    fn decode_to(&mut self, target: u64, channel: usize) -> Result<bool> {
+        loop {
+            self.fill_buffer()?;
+            let frames = ((self.buffer.len() - self.offset) / self.channel_count()) as u64;
+            if frames == 0 {
+                return Ok(true);
+            }
+            if self.buffer_ts > target {
+                return Ok(false);
+            }
+            if self.buffer_ts + frames > target {
+                self.offset = (target - self.buffer_ts) as usize * self.channel_count() + channel;
+                return Ok(true);
+            }
+        }
+    }
+
+    fn channel_count(&self) -> usize {
+        self.spec.channels.count().max(1)
+    }
+}
+
+fn copy_samples(decoded: AudioBufferRef, buffer: &mut SampleBuffer<i16>, spec: &mut SignalSpec) {
+    let needed = decoded.frames() * decoded.spec().channels.count();
+    if *decoded.spec() != *spec || buffer.capacity() < needed {
+        *spec = *decoded.spec();
+        *buffer = SampleBuffer::new(decoded.capacity() as u64, *spec);
+    }
+    buffer.copy_interleaved_ref(decoded);
+}
+
+fn to_duration(time: Time) -> Duration {
+    Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac)
+}
+
+fn seek_error(error: Error) -> SeekError {
+    SeekError::Other(Box::new(error))
+}
+
+impl Iterator for DecodedAudio {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let sample = *self.buffer.samples().get(self.offset)?;
+        self.offset += 1;
+        if self.offset == self.buffer.len() {
+            if let Err(e) = self.fill_buffer() {
+                warn!("Stopped decoding audio: {}", e);
+            }
+        }
+        Some(sample)
+    }
+}
+
+impl Source for DecodedAudio {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.buffer.len() - self.offset)
+    }
+
+    fn channels(&self) -> u16 {
+        self.channel_count() as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.spec.rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+
+    /// Seeks to the exact sample: the format reader lands on a packet at or before `pos` (and
+    /// the pre-roll), and the audio up to it is decoded and dropped. Decoders that need a packet
+    /// or two to prime may start past `pos`; then the seek starts over further back.
+    fn try_seek(&mut self, pos: Duration) -> std::result::Result<(), SeekError> {
+        let pos = match self.total_duration {
+            Some(total) if pos + END_MARGIN >= total => total.saturating_sub(END_MARGIN),
+            _ => pos,
+        };
+        // Whoever reads the samples counts channels; keep the next sample on the same one.
+        let channel = self.offset % self.channel_count();
+
+        let mut pre_roll = self.pre_roll;
+        loop {
+            let start = pos.saturating_sub(pre_roll);
+            let seeked = self
+                .format
+                .seek(SeekMode::Accurate, SeekTo::Time { time: Time::from(start.as_secs_f64()), track_id: Some(self.track_id) })
+                .map_err(seek_error)?;
+            self.decoder.reset();
+
+            let target = self.time_base.map_or(seeked.required_ts, |base| {
+                base.calc_timestamp(Time::from(pos.as_secs_f64())) + self.start_ts
+            });
+            if self.decode_to(target, channel).map_err(seek_error)? || start.is_zero() {
+                return Ok(());
+            }
+            pre_roll = (pre_roll * 2).max(RETRY_PRE_ROLL);
+        }
+    }
+}
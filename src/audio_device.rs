@@ -0,0 +1,52 @@
+use anyhow::{anyhow, bail, Context, Result};
+use rodio::cpal::traits::HostTrait;
+use rodio::{cpal, DeviceTrait, OutputStream, OutputStreamHandle};
+
+/// Names of the output devices the audio system offers.
+pub fn output_devices() -> Result<Vec<String>> {
+    let devices = cpal::default_host().output_devices().context("Failed to list output devices")?;
+    Ok(devices.filter_map(|device| device.name().ok()).collect())
+}
+
+/// Name of the device the audio system currently plays to by default.
+pub fn default_output_device() -> Option<String> {
+    cpal::default_host().default_output_device().and_then(|device| device.name().ok())
+}
+
+/// Finds `wanted` among `names`: an exact match, or else the only name containing it, ignoring case.
+pub fn find_device<'a>(names: &'a [String], wanted: &str) -> Result<&'a str> {
+    if let Some(name) = names.iter().find(|name| name.as_str() == wanted) {
+        return Ok(name);
+    }
+    let lowercase = wanted.to_lowercase();
+    let matches: Vec<&String> = names.iter().filter(|name| name.to_lowercase().contains(&lowercase)).collect();
+    match matches[..] {
+        [name] => Ok(name),
+        [] => bail!("No output device matches '{}' (available: {})", wanted, names.join(", ")),
+        _ => bail!(
+            "'{}' matches several output devices: {}",
+            wanted,
+            matches.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+/// Opens an output stream on the named device, or on the default device for `None`.
+/// Returns the stream with the name of the device it plays to.
+pub fn open_output(device: Option<&str>) -> Result<(OutputStream, OutputStreamHandle, String)> {
+    let Some(wanted) = device else {
+        let (stream, handle) = OutputStream::try_default().context("Failed to open the default output device")?;
+        return Ok((stream, handle, default_output_device().unwrap_or_else(|| "default".to_string())));
+    };
+
+    let names = output_devices()?;
+    let name = find_device(&names, wanted)?.to_string();
+    let device = cpal::default_host()
+        .output_devices()
+        .context("Failed to list output devices")?
+        .find(|device| device.name().is_ok_and(|n| n == name))
+        .ok_or_else(|| anyhow!("Output device '{}' disappeared", name))?;
+    let (stream, handle) = OutputStream::try_from_device(&device)
+        .with_context(|| format!("Failed to open output device '{}'", name))?;
+    Ok((stream, handle, name))
+}
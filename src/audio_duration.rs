@@ -0,0 +1,248 @@
+use crate::episodes::Episode;
+use std::time::Duration;
+
+/// Where a playback duration came from, from most to least trustworthy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationSource {
+    Decoder,
+    XingHeader,
+    VbriHeader,
+    FrameScan,
+    Feed,
+    EnclosureLength,
+}
+
+/// Picks the best available duration for an episode whose audio is in `bytes`.
+///
+/// The decoder's own value wins, then the MP3 headers or a frame scan, then the feed's
+/// `itunes:duration`, and finally an estimate from the enclosure length and bitrate.
+/// `bytes` may be just the start of the file; frames are only counted when it is complete.
+pub fn resolve_duration(
+    decoded: Option<Duration>,
+    bytes: &[u8],
+    episode: &Episode,
+) -> Option<(Duration, DurationSource)> {
+    if let Some(duration) = decoded.filter(|d| !d.is_zero()) {
+        return Some((duration, DurationSource::Decoder));
+    }
+    let complete = episode.enclosure_length.is_none_or(|length| bytes.len() as u64 >= length);
+    let found = if complete { mp3_duration(bytes) } else { mp3_header_duration(bytes) };
+    if found.is_some() {
+        return found;
+    }
+    if let Some(duration) = episode.duration.filter(|d| !d.is_zero()) {
+        return Some((duration, DurationSource::Feed));
+    }
+    let length = episode.enclosure_length.filter(|&l| l > 0)?;
+    let (_, header) = first_frame(bytes)?;
+    estimate_from_length(length, header.bitrate).map(|d| (d, DurationSource::EnclosureLength))
+}
+
+/// Reads the duration of an MP3 from its Xing/Info or VBRI header, or by walking every frame.
+pub fn mp3_duration(bytes: &[u8]) -> Option<(Duration, DurationSource)> {
+    mp3_header_duration(bytes).or_else(|| {
+        let (offset, header) = first_frame(bytes)?;
+        scan_frames(bytes, offset, &header).map(|d| (d, DurationSource::FrameScan))
+    })
+}
+
+/// Reads the frame count that VBR encoders store in the first frame.
+fn mp3_header_duration(bytes: &[u8]) -> Option<(Duration, DurationSource)> {
+    let (offset, header) = first_frame(bytes)?;
+    let frame = &bytes[offset..];
+
+    if let Some(frames) = xing_frames(frame, &header) {
+        return Some((header.duration_of(frames), DurationSource::XingHeader));
+    }
+    vbri_frames(frame).map(|frames| (header.duration_of(frames), DurationSource::VbriHeader))
+}
+
+/// Estimates the duration of `length` bytes of audio at `bits_per_second`.
+pub fn estimate_from_length(length: u64, bits_per_second: u32) -> Option<Duration> {
+    if bits_per_second == 0 {
+        return None;
+    }
+    Some(Duration::from_secs_f64(length as f64 * 8.0 / bits_per_second as f64))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Version {
+    Mpeg1,
+    Mpeg2,
+    Mpeg25,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FrameHeader {
+    version: Version,
+    layer: u8,
+    /// Bits per second.
+    bitrate: u32,
+    sample_rate: u32,
+    padding: bool,
+    mono: bool,
+}
+
+const BITRATES_V1: [[u32; 14]; 3] = [
+    [32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448],
+    [32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384],
+    [32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+];
+const BITRATES_V2: [[u32; 14]; 2] = [
+    [32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256],
+    [8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+const SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+impl FrameHeader {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let &[b0, b1, b2, b3] = bytes.get(..4)? else {
+            return None;
+        };
+        if b0 != 0xFF || b1 & 0xE0 != 0xE0 {
+            return None;
+        }
+
+        let version = match (b1 >> 3) & 0b11 {
+            0 => Version::Mpeg25,
+            2 => Version::Mpeg2,
+            3 => Version::Mpeg1,
+            _ => return None,
+        };
+        let layer = match (b1 >> 1) & 0b11 {
+            1 => 3,
+            2 => 2,
+            3 => 1,
+            _ => return None,
+        };
+
+        // Index 0 is "free format" and 15 is invalid; neither can be measured
+        let bitrate_index = (b2 >> 4) as usize;
+        if bitrate_index == 0 || bitrate_index == 15 {
+            return None;
+        }
+        let kbps = match version {
+            Version::Mpeg1 => BITRATES_V1[layer as usize - 1][bitrate_index - 1],
+            _ => BITRATES_V2[if layer == 1 { 0 } else { 1 }][bitrate_index - 1],
+        };
+
+        let base_rate = *SAMPLE_RATES.get(((b2 >> 2) & 0b11) as usize)?;
+        let sample_rate = match version {
+            Version::Mpeg1 => base_rate,
+            Version::Mpeg2 => base_rate / 2,
+            Version::Mpeg25 => base_rate / 4,
+        };
+
+        Some(Self {
+            version,
+            layer,
+            bitrate: kbps * 1000,
+            sample_rate,
+            padding: (b2 >> 1) & 1 == 1,
+            mono: b3 >> 6 == 0b11,
+        })
+    }
+
+    fn samples_per_frame(&self) -> u32 {
+        match (self.layer, self.version) {
+            (1, _) => 384,
+            (3, Version::Mpeg2 | Version::Mpeg25) => 576,
+            _ => 1152,
+        }
+    }
+
+    fn frame_length(&self) -> usize {
+        let length = if self.layer == 1 {
+            (12 * self.bitrate / self.sample_rate + self.padding as u32) * 4
+        } else {
+            self.samples_per_frame() / 8 * self.bitrate / self.sample_rate + self.padding as u32
+        };
+        length as usize
+    }
+
+    /// Offset of the Xing/Info tag: it follows the side information of the first frame.
+    fn xing_offset(&self) -> usize {
+        match (self.version, self.mono) {
+            (Version::Mpeg1, false) => 4 + 32,
+            (Version::Mpeg1, true) => 4 + 17,
+            (_, false) => 4 + 17,
+            (_, true) => 4 + 9,
+        }
+    }
+
+    fn duration_of(&self, frames: u32) -> Duration {
+        let samples = frames as u64 * self.samples_per_frame() as u64;
+        Duration::from_secs_f64(samples as f64 / self.sample_rate as f64)
+    }
+}
+
+/// Size of a leading ID3v2 tag, which sits in front of the first audio frame.
+fn id3v2_size(bytes: &[u8]) -> usize {
+    match bytes {
+        [b'I', b'D', b'3', _, _, flags, size @ ..] if size.len() >= 4 => {
+            let size = size[..4].iter().fold(0usize, |acc, &b| (acc << 7) | (b & 0x7F) as usize);
+            let footer = if flags & 0x10 != 0 { 10 } else { 0 };
+            10 + size + footer
+        }
+        _ => 0,
+    }
+}
+
+/// Finds the first frame whose successor also parses, so stray sync bits in tags are skipped.
+fn first_frame(bytes: &[u8]) -> Option<(usize, FrameHeader)> {
+    let start = id3v2_size(bytes).min(bytes.len());
+    (start..bytes.len().saturating_sub(3)).find_map(|offset| {
+        let header = FrameHeader::parse(&bytes[offset..])?;
+        let next = offset + header.frame_length();
+        let confirmed = match bytes.get(next..) {
+            Some(rest) if rest.len() >= 4 => FrameHeader::parse(rest)
+                .is_some_and(|h| h.version == header.version && h.layer == header.layer),
+            // A single frame at the very end of the data is still a frame
+            _ => next <= bytes.len(),
+        };
+        confirmed.then_some((offset, header))
+    })
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn xing_frames(frame: &[u8], header: &FrameHeader) -> Option<u32> {
+    let offset = header.xing_offset();
+    let tag = frame.get(offset..offset + 4)?;
+    if tag != b"Xing" && tag != b"Info" {
+        return None;
+    }
+    let flags = read_u32(frame, offset + 4)?;
+    if flags & 0x1 == 0 {
+        return None;
+    }
+    read_u32(frame, offset + 8).filter(|&frames| frames > 0)
+}
+
+fn vbri_frames(frame: &[u8]) -> Option<u32> {
+    const OFFSET: usize = 4 + 32;
+    if frame.get(OFFSET..OFFSET + 4)? != b"VBRI" {
+        return None;
+    }
+    read_u32(frame, OFFSET + 14).filter(|&frames| frames > 0)
+}
+
+/// Adds up the samples of every frame until the data ends or stops looking like MP3.
+fn scan_frames(bytes: &[u8], start: usize, first: &FrameHeader) -> Option<Duration> {
+    let mut offset = start;
+    let mut samples: u64 = 0;
+    while let Some(header) = bytes.get(offset..).and_then(FrameHeader::parse) {
+        let length = header.frame_length();
+        if length == 0 || offset + length > bytes.len() {
+            break;
+        }
+        samples += header.samples_per_frame() as u64;
+        offset += length;
+    }
+    if samples == 0 {
+        return None;
+    }
+    Some(Duration::from_secs_f64(samples as f64 / first.sample_rate as f64))
+}
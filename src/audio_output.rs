@@ -0,0 +1,260 @@
+use crate::audio_device::open_output;
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use rodio::queue::SourcesQueueOutput;
+use rodio::source::UniformSourceIterator;
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Headless outputs mix everything to this format.
+const HEADLESS_CHANNELS: u16 = 2;
+const HEADLESS_SAMPLE_RATE: u32 = 44100;
+
+/// Audio is consumed in chunks of this length, then the consumer waits to keep pace.
+const CHUNK: Duration = Duration::from_millis(10);
+
+/// Where the player sends its audio.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum OutputBackend {
+    /// A sound card: the named one, or the system default for `None`.
+    Device(Option<String>),
+    /// No sound card; the audio is discarded at `speed` times real time.
+    Null { speed: f32 },
+    /// No sound card; the audio is written to a 16-bit stereo WAV file at `speed` times real time.
+    Wav { path: PathBuf, speed: f32 },
+}
+
+impl Default for OutputBackend {
+    fn default() -> Self {
+        OutputBackend::Device(None)
+    }
+}
+
+impl FromStr for OutputBackend {
+    type Err = anyhow::Error;
+
+    /// Parses `device`, `device:NAME`, `null`, `null:SPEED` or `wav:PATH`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, argument) = match s.split_once(':') {
+            Some((kind, argument)) => (kind, Some(argument)),
+            None => (s, None),
+        };
+        match (kind.to_ascii_lowercase().as_str(), argument) {
+            ("device", None) => Ok(OutputBackend::Device(None)),
+            ("device", Some(name)) => Ok(OutputBackend::Device(Some(name.to_string()))),
+            ("null", None) => Ok(OutputBackend::Null { speed: 1.0 }),
+            ("null", Some(speed)) => {
+                let speed: f32 = speed.parse().map_err(|_| anyhow!("Invalid output speed '{}'", speed))?;
+                if !(speed.is_finite() && speed > 0.0) {
+                    bail!("Output speed must be positive, got {}", speed);
+                }
+                Ok(OutputBackend::Null { speed })
+            }
+            ("wav", Some(path)) if !path.is_empty() => Ok(OutputBackend::Wav { path: PathBuf::from(path), speed: 1.0 }),
+            ("wav", _) => bail!("The wav output needs a file, e.g. wav:/tmp/out.wav"),
+            _ => bail!("Unknown output '{}' (expected device, device:NAME, null, null:SPEED or wav:PATH)", s),
+        }
+    }
+}
+
+impl fmt::Display for OutputBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputBackend::Device(None) => f.write_str("device"),
+            OutputBackend::Device(Some(name)) => write!(f, "device:{}", name),
+            OutputBackend::Null { speed } if *speed == 1.0 => f.write_str("null"),
+            OutputBackend::Null { speed } => write!(f, "null:{}", speed),
+            OutputBackend::Wav { path, .. } => write!(f, "wav:{}", path.display()),
+        }
+    }
+}
+
+impl TryFrom<String> for OutputBackend {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<OutputBackend> for String {
+    fn from(backend: OutputBackend) -> Self {
+        backend.to_string()
+    }
+}
+
+type WavWriter = hound::WavWriter<BufWriter<File>>;
+
+enum Target {
+    Device { _stream: OutputStream, handle: OutputStreamHandle },
+    Null { speed: f32 },
+    Wav { writer: Arc<Mutex<WavWriter>>, speed: f32 },
+}
+
+/// An opened output backend, which the player creates a sink on for every episode.
+pub struct AudioOutput {
+    target: Target,
+    name: String,
+}
+
+impl AudioOutput {
+    pub fn open(backend: &OutputBackend) -> Result<Self> {
+        let (target, name) = match backend {
+            OutputBackend::Device(device) => {
+                let (_stream, handle, name) = open_output(device.as_deref())?;
+                (Target::Device { _stream, handle }, name)
+            }
+            OutputBackend::Null { speed } => (Target::Null { speed: *speed }, "null".to_string()),
+            OutputBackend::Wav { path, speed } => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let spec = hound::WavSpec {
+                    channels: HEADLESS_CHANNELS,
+                    sample_rate: HEADLESS_SAMPLE_RATE,
+                    bits_per_sample: 16,
+                    sample_format: hound::SampleFormat::Int,
+                };
+                let writer = hound::WavWriter::create(path, spec)
+                    .with_context(|| format!("Failed to create {}", path.display()))?;
+                (Target::Wav { writer: Arc::new(Mutex::new(writer)), speed: *speed }, backend.to_string())
+            }
+        };
+        Ok(Self { target, name })
+    }
+
+    /// The device name, or a description of the headless output.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the audio goes to a sound card, which can go away while playing.
+    pub fn is_device(&self) -> bool {
+        matches!(self.target, Target::Device { .. })
+    }
+
+    /// A sink playing `source` to this output. Headless sinks are drained by a thread of
+    /// their own, which ends when the sink is dropped.
+    pub fn play<S: Source<Item = f32> + Send + 'static>(&self, source: S) -> Result<Sink> {
+        let (wav, speed) = match &self.target {
+            Target::Device { handle, .. } => {
+                let sink = Sink::try_new(handle)?;
+                sink.append(source);
+                return Ok(sink);
+            }
+            Target::Null { speed } => (None, *speed),
+            Target::Wav { writer, speed } => (Some(writer.clone()), *speed),
+        };
+
+        let (sink, queue) = Sink::new_idle();
+        let played = Arc::new(AtomicU64::new(0));
+        // Converted up front: converting the queue drops audio wherever its frames end
+        let source = UniformSourceIterator::new(source, HEADLESS_CHANNELS, HEADLESS_SAMPLE_RATE);
+        sink.append(Counted { source, played: played.clone() });
+        std::thread::spawn(move || {
+            drain(queue, played, speed, |chunk| {
+                let Some(writer) = &wav else {
+                    return Ok(());
+                };
+                let mut writer = writer.lock().unwrap();
+                for &sample in chunk {
+                    writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+                }
+                // Keeps the header valid, so the file can be read while it grows
+                writer.flush()?;
+                Ok(())
+            })
+        });
+        Ok(sink)
+    }
+}
+
+/// Counts the samples the episode has produced. A paused or finished sink fills in silence
+/// without touching its source, so a count that stands still means nothing is playing.
+struct Counted<S> {
+    source: S,
+    played: Arc<AtomicU64>,
+}
+
+impl<S: Source<Item = f32>> Iterator for Counted<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.source.next()?;
+        self.played.fetch_add(1, Ordering::Relaxed);
+        Some(sample)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.source.size_hint()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Counted<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
+        self.source.try_seek(pos)
+    }
+}
+
+/// Pulls the mixed audio of a sink in chunks, keeping pace with `speed` times real time,
+/// until the sink is dropped or `write` fails.
+///
+/// The sink is pulled while paused or finished too, since that is when it handles seeks and
+/// other controls, but the silence it fills in then is not written.
+fn drain(
+    queue: SourcesQueueOutput<f32>,
+    played: Arc<AtomicU64>,
+    speed: f32,
+    mut write: impl FnMut(&[f32]) -> Result<(), hound::Error>,
+) {
+    let mut samples = UniformSourceIterator::<_, f32>::new(queue, HEADLESS_CHANNELS, HEADLESS_SAMPLE_RATE);
+    let chunk_len = (CHUNK.as_secs_f64() * HEADLESS_SAMPLE_RATE as f64) as usize * HEADLESS_CHANNELS as usize;
+    let mut chunk = Vec::with_capacity(chunk_len);
+    let started = Instant::now();
+    let mut consumed = Duration::ZERO;
+
+    loop {
+        let played_before = played.load(Ordering::Relaxed);
+        chunk.clear();
+        chunk.extend(samples.by_ref().take(chunk_len));
+        if chunk.is_empty() {
+            break;
+        }
+        if played.load(Ordering::Relaxed) != played_before {
+            if let Err(e) = write(&chunk) {
+                warn!("Headless output failed: {}", e);
+                break;
+            }
+        }
+
+        consumed += CHUNK;
+        if let Some(ahead) = consumed.div_f32(speed).checked_sub(started.elapsed()) {
+            std::thread::sleep(ahead);
+        }
+    }
+}
@@ -1,11 +1,33 @@
-use anyhow::{Result, anyhow};
-use crate::episodes::Episode;
-use rodio::{Decoder, OutputStream, Sink, Source};
-use std::io::{BufReader, Cursor};
+use anyhow::{Result, anyhow, bail};
+use crate::audio_decoder::{codecs, DecodedAudio};
+use crate::audio_device::{default_output_device, output_devices};
+use crate::audio_output::{AudioOutput, OutputBackend};
+use crate::audio_duration::resolve_duration;
+use crate::chapters::{parse_id3_chapters, Chapter};
+use crate::credentials::redact;
+use crate::episodes::{format_duration, Episode};
+use crate::http::HttpClient;
+use crate::loudness::{measure, normalization_gain, Normalization, Normalized, SharedGain};
+use crate::silence::{SilenceTrimming, TimeSaved, TrimCounter, TrimSilence};
+use crate::sleep_timer::{fade_gain, SleepMode, SleepTimer};
+use crate::volume::{Volume, VolumeControl};
+use log::{info, warn};
+use rodio::{Sink, Source};
+use std::fmt;
+use std::io::Cursor;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use reqwest;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use symphonia::core::codecs::{CodecType, DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS, CODEC_TYPE_SPEEX, CODEC_TYPE_WMA};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// The slowest and fastest speeds episodes can play at.
+pub const MIN_SPEED: f32 = 0.25;
+pub const MAX_SPEED: f32 = 4.0;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum PlayerCommand {
@@ -16,25 +38,81 @@ pub enum PlayerCommand {
     SkipBackward(i64),
     VolumeUp(f32),
     VolumeDown(f32),
+    Seek(Duration),
+    SeekPercent(f32),
+    SetVolume(f32),
+    SetSpeed(f32),
+    ToggleMute,
+    Sleep(SleepMode),
+    /// Adds time to the sleep timer, or starts one when none is running.
+    ExtendSleep(Duration),
+    CancelSleep,
+    /// Moves playback to the named output device, or to the default device for `None`.
+    SetOutputDevice(Option<String>),
+    ListOutputDevices,
     Quit,
     Ignore,
 }
 
 pub struct AudioPlayer {
-    _stream: OutputStream,
-    stream_handle: rodio::OutputStreamHandle,
+    /// Downloads episode audio; shared with the feed source so both use the same settings.
+    client: HttpClient,
+    output: AudioOutput,
+    /// The device the user chose; `None` follows the system default.
+    device: Option<String>,
+    last_device_check: Instant,
     sink: Arc<Mutex<Option<Sink>>>,
+    /// The downloaded episode, kept so playback can be rebuilt on another device.
+    current_audio: Option<CurrentAudio>,
     current_file: Arc<Mutex<Option<PathBuf>>>,
     current_position: Arc<Mutex<Duration>>,
     duration: Arc<Mutex<Option<Duration>>>,
+    speed: f32,
+    normalization: Option<Normalization>,
+    silence_trimming: Option<SilenceTrimming>,
+    trim_counter: TrimCounter,
+    volume: VolumeControl,
+    /// The amplitude for `volume`, shared with the playing source.
+    volume_gain: SharedGain,
+    /// The normalization gain of the current episode.
+    gain: SharedGain,
+    chapters: Vec<Chapter>,
+    sleep_timer: Arc<Mutex<Option<SleepTimer>>>,
+    /// The sleep timer's fade-out, shared with the playing source.
+    fade_gain: SharedGain,
 }
 pub trait AudioPlayerTrait {
     fn play(&mut self, episode: &Episode) -> Result<()>;
     fn resume(&mut self) -> Result<()>;
     fn pause(&mut self) -> Result<()>;
     fn stop(&mut self) -> Result<()>;
+    /// Fails, leaving the position unchanged, when the audio cannot seek.
     fn skip(&mut self, seconds: i64) -> Result<()>;
+    fn seek(&mut self, position: Duration) -> Result<()>;
     fn adjust_volume(&mut self, step: f32) -> Result<()>;
+    /// Sets the perceptual volume level, clamped to the configured maximum.
+    fn set_volume(&mut self, volume: f32) -> Result<()>;
+    fn toggle_mute(&mut self) -> Result<()>;
+    fn set_speed(&mut self, speed: f32) -> Result<()>;
+    fn duration(&self) -> Option<Duration>;
+    fn position(&self) -> Duration;
+    fn is_paused(&self) -> bool;
+    fn volume(&self) -> f32;
+    fn is_muted(&self) -> bool;
+    /// Time saved by silence trimming, or `None` when trimming is off.
+    fn time_saved(&self) -> Option<TimeSaved>;
+    /// Starts a sleep timer, replacing any running one.
+    fn set_sleep_timer(&mut self, mode: SleepMode) -> Result<()>;
+    fn extend_sleep_timer(&mut self, by: Duration) -> Result<()>;
+    fn cancel_sleep_timer(&mut self);
+    /// Listening time until the sleep timer stops playback, or `None` when no timer is running.
+    fn sleep_remaining(&self) -> Option<Duration>;
+    /// Moves playback, keeping its position, to the named device or to the default one for `None`.
+    fn set_output_device(&mut self, device: Option<&str>) -> Result<()>;
+    /// The name of the device playing the audio.
+    fn output_device(&self) -> String;
+    /// Moves playback to another device when the current one has gone away, and returns its name.
+    fn check_output_device(&mut self) -> Result<Option<String>>;
 }
 
 impl AudioPlayerTrait for AudioPlayer {
@@ -58,22 +136,130 @@ impl AudioPlayerTrait for AudioPlayer {
         AudioPlayer::skip(self, seconds)
     }
 
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        AudioPlayer::play_from_position(self, position)
+    }
+
     fn adjust_volume(&mut self, step: f32) -> Result<()> {
         AudioPlayer::adjust_volume(self, step)
     }
+
+    fn set_volume(&mut self, volume: f32) -> Result<()> {
+        AudioPlayer::set_volume(self, volume)
+    }
+
+    fn set_speed(&mut self, speed: f32) -> Result<()> {
+        AudioPlayer::set_speed(self, speed)
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        AudioPlayer::duration(self)
+    }
+
+    fn position(&self) -> Duration {
+        AudioPlayer::current_position(self)
+    }
+
+    fn is_paused(&self) -> bool {
+        AudioPlayer::is_paused(self)
+    }
+
+    fn volume(&self) -> f32 {
+        AudioPlayer::volume(self)
+    }
+
+    fn toggle_mute(&mut self) -> Result<()> {
+        AudioPlayer::toggle_mute(self)
+    }
+
+    fn is_muted(&self) -> bool {
+        AudioPlayer::is_muted(self)
+    }
+
+    fn time_saved(&self) -> Option<TimeSaved> {
+        AudioPlayer::time_saved(self)
+    }
+
+    fn set_sleep_timer(&mut self, mode: SleepMode) -> Result<()> {
+        AudioPlayer::set_sleep_timer(self, mode)
+    }
+
+    fn extend_sleep_timer(&mut self, by: Duration) -> Result<()> {
+        AudioPlayer::extend_sleep_timer(self, by)
+    }
+
+    fn cancel_sleep_timer(&mut self) {
+        AudioPlayer::cancel_sleep_timer(self)
+    }
+
+    fn sleep_remaining(&self) -> Option<Duration> {
+        AudioPlayer::sleep_remaining(self)
+    }
+
+    fn set_output_device(&mut self, device: Option<&str>) -> Result<()> {
+        AudioPlayer::set_output_device(self, device)
+    }
+
+    fn output_device(&self) -> String {
+        AudioPlayer::output_device(self)
+    }
+
+    fn check_output_device(&mut self) -> Result<Option<String>> {
+        AudioPlayer::check_output_device(self)
+    }
+}
+
+/// The downloaded audio of the current episode.
+struct CurrentAudio {
+    bytes: Arc<[u8]>,
+    mime_type: Option<String>,
 }
 
+/// How often the sleep timer counts down and updates its fade.
+const SLEEP_TICK: Duration = Duration::from_millis(100);
+
+/// How often the output device is checked for having gone away; listing devices is not free.
+const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
 impl AudioPlayer {
+    /// A player on the default output device.
     pub fn new() -> Result<Self> {
-        let (_stream, stream_handle) = OutputStream::try_default()?;
-        Ok(Self {
-            _stream,
-            stream_handle,
+        Self::with_device(None)
+    }
+
+    /// A player on the named output device, or on the default device for `None`.
+    pub fn with_device(device: Option<&str>) -> Result<Self> {
+        Self::with_output(OutputBackend::Device(device.map(str::to_string)))
+    }
+
+    /// A player on any output backend, including the headless ones that need no sound card.
+    pub fn with_output(backend: OutputBackend) -> Result<Self> {
+        let player = Self {
+            client: HttpClient::default(),
+            output: AudioOutput::open(&backend)?,
+            device: match backend {
+                OutputBackend::Device(device) => device,
+                _ => None,
+            },
+            last_device_check: Instant::now(),
             sink: Arc::new(Mutex::new(None)),
+            current_audio: None,
             current_file: Arc::new(Mutex::new(None)),
             current_position: Arc::new(Mutex::new(Duration::default())),
             duration: Arc::new(Mutex::new(None)),
-        })
+            speed: 1.0,
+            normalization: None,
+            silence_trimming: None,
+            trim_counter: TrimCounter::new(),
+            volume: VolumeControl::default(),
+            volume_gain: SharedGain::new(1.0),
+            gain: SharedGain::new(1.0),
+            chapters: Vec::new(),
+            sleep_timer: Arc::new(Mutex::new(None)),
+            fade_gain: SharedGain::new(1.0),
+        };
+        player.spawn_sleep_ticker();
+        Ok(player)
     }
 
     pub fn play(&mut self, episode: &Episode) -> Result<()> {
@@ -83,60 +269,81 @@ impl AudioPlayer {
         let audio_url = episode.audio_url.as_ref().ok_or_else(|| anyhow!("Episode has no audio URL"))?;
 
         // Download and decode audio
-        let audio_bytes = reqwest::blocking::get(audio_url)?.bytes()?.to_vec();
-        let source = Decoder::new(BufReader::new(Cursor::new(audio_bytes.clone())))?;
+        let download = self.client.get_enclosure(audio_url)?;
+        // The feed's type is what the publisher meant; the server's is the fallback
+        let mime_type = episode.mime_type.clone().or(download.content_type);
+        let audio_bytes: Arc<[u8]> = download.bytes.into();
+        self.play_audio(episode, audio_bytes, mime_type)
+    }
+
+    /// Plays audio that is already downloaded, as if it were the enclosure of `episode`.
+    pub fn play_audio(&mut self, episode: &Episode, audio_bytes: Arc<[u8]>, mime_type: Option<String>) -> Result<()> {
+        self.stop()?;
+        let audio_url = episode.audio_url.as_deref().unwrap_or(&episode.title);
+        let source = decode(audio_bytes.clone(), mime_type.as_deref())?;
+        let duration = resolve_duration(source.total_duration(), &audio_bytes, episode);
+        match duration {
+            Some((duration, from)) => info!("Episode duration {:?} (from {:?})", duration, from),
+            None => warn!("Could not determine the episode duration"),
+        }
+
+        self.chapters = parse_id3_chapters(&audio_bytes);
+        if !self.chapters.is_empty() {
+            info!("Episode has {} chapters", self.chapters.len());
+        }
 
         // Setup playback and store state
-        let sink = Sink::try_new(&self.stream_handle)?;
-        *self.duration.lock().unwrap() = source.total_duration();
+        *self.duration.lock().unwrap() = duration.map(|(duration, _)| duration);
         *self.current_file.lock().unwrap() = Some(PathBuf::from(audio_url));
         *self.current_position.lock().unwrap() = Duration::default();
 
         // Start playback
-        sink.append(source);
+        self.gain = self.normalization_gain(audio_url, &audio_bytes, mime_type.clone());
+        let sink = self.new_sink(&self.output, source)?;
         *self.sink.lock().unwrap() = Some(sink);
+        self.current_audio = Some(CurrentAudio { bytes: audio_bytes, mime_type });
         Ok(())
     }
 
+    /// A sink on `output` playing `source` through the normalization, volume,
+    /// fade and silence trimming of the player.
+    fn new_sink(&self, output: &AudioOutput, source: DecodedAudio) -> Result<Sink> {
+        let trim_settings = self.silence_trimming.as_ref().map(|trimming| trimming.settings);
+        let trimmed = TrimSilence::new(Normalized::new(source, self.gain.clone()), trim_settings, self.trim_counter.clone());
+        let sink = output.play(Volume::new(trimmed, self.volume_gain.clone()).with_fade(self.fade_gain.clone()))?;
+        sink.set_speed(self.speed);
+        Ok(sink)
+    }
+
+    /// Seeks the current episode; on failure the position is left where the audio really is.
     pub fn play_from_position(&mut self, position: Duration) -> Result<()> {
-        // Ensure position is within total duration
-        let total_duration = self.duration()
-            .ok_or_else(|| anyhow!("Failed to get duration"))?;
-        let adjusted_position = position.min(total_duration);
-
-        // Try to seek in the existing sink
-        if let Some(sink) = self.sink.lock().unwrap().as_mut() {
-            if sink.try_seek(adjusted_position).is_err() {
-                // If seeking fails, log a warning but continue
-                println!("Warning: Seeking not supported or failed");
-            }
+        // Ensure position is within total duration, when it is known
+        let adjusted_position = match self.duration() {
+            Some(total_duration) => position.min(total_duration),
+            None => position,
+        };
+
+        let sink = self.sink.lock().unwrap();
+        let sink = sink.as_ref().ok_or_else(|| anyhow!("Nothing is playing"))?;
+        if let Err(e) = sink.try_seek(adjusted_position) {
+            bail!("Cannot seek to {}: {}", format_duration(adjusted_position), e);
         }
 
-        // Update current position
         *self.current_position.lock().unwrap() = adjusted_position;
         Ok(())
     }
 
     pub fn skip(&mut self, seconds: i64) -> Result<()> {
         let current_pos = self.current_position();
-        let total_duration = self.duration().map_or(Duration::from_secs(0), |d| d);
+        let forward = current_pos + Duration::from_secs(seconds.unsigned_abs());
         let new_pos = match seconds.is_positive() {
-            true => (current_pos + Duration::from_secs(seconds as u64)).min(total_duration),
-            false => current_pos.saturating_sub(Duration::from_secs(-seconds as u64)),
+            true => self.duration().map_or(forward, |total| forward.min(total)),
+            false => current_pos.saturating_sub(Duration::from_secs(seconds.unsigned_abs())),
         };
     
         // Use play_from_position for seeking
-        self.play_from_position(new_pos)?;
-    
-        // Print skip message
-        println!(
-            "{} {} seconds. New position: {:?}",
-            if seconds >= 0 { "⏩ Skipped forward" } else { "⏪ Skipped backward" },
-            seconds.abs(),
-            new_pos
-        );
-        Ok(())
-    }    
+        self.play_from_position(new_pos)
+    }
 
     pub fn resume(&mut self) -> Result<()> {
         if let Some(sink) = self.sink.lock().unwrap().as_ref() {
@@ -150,10 +357,8 @@ impl AudioPlayer {
         if let Some(sink) = sink.as_ref() {
             if sink.is_paused() {
                 sink.play();
-                println!("▶️ Resumed playback");
             } else {
                 sink.pause();
-                println!("⏸️ Paused playback");
             }
         }
         Ok(())
@@ -163,27 +368,366 @@ impl AudioPlayer {
         if let Some(sink) = self.sink.lock().unwrap().take() {
             sink.stop();
         }
+        self.current_audio = None;
+
+        // A timer waiting for the end of this episode or chapter has nothing left to wait for
+        let mut sleep_timer = self.sleep_timer.lock().unwrap();
+        if sleep_timer.as_ref().is_some_and(SleepTimer::is_episode_bound) {
+            *sleep_timer = None;
+            self.fade_gain.set(1.0);
+        }
+        drop(sleep_timer);
+
+        // Add what this episode saved to the overall total, once
+        let saved = self.trim_counter.episode();
+        self.trim_counter.reset();
+        if let Some(trimming) = &self.silence_trimming {
+            if let Err(e) = trimming.log.lock().unwrap().add(saved) {
+                warn!("Failed to record time saved: {}", e);
+            }
+        }
         Ok(())
     }
 
     pub fn adjust_volume(&mut self, step: f32) -> Result<()> {
-        if let Some(sink) = self.sink.lock().unwrap().as_mut() {
-            sink.set_volume((sink.volume() + step).max(0.0));
-            println!(
-                "🔊 Volume {} to {:.1}",
-                if step > 0.0 { "increased" } else { "decreased" },
-                sink.volume()
-            );
+        self.volume.adjust(step);
+        self.volume_gain.set(self.volume.amplitude());
+        Ok(())
+    }
+
+    pub fn set_volume(&mut self, volume: f32) -> Result<()> {
+        self.volume.set(volume);
+        self.volume_gain.set(self.volume.amplitude());
+        Ok(())
+    }
+
+    pub fn toggle_mute(&mut self) -> Result<()> {
+        self.volume.toggle_mute();
+        self.volume_gain.set(self.volume.amplitude());
+        Ok(())
+    }
+
+    /// Replaces the volume state, e.g. with one restored from the last session.
+    pub fn set_volume_control(&mut self, volume: VolumeControl) {
+        self.volume_gain.set(volume.amplitude());
+        self.volume = volume;
+    }
+
+    /// Sets the playback speed for the current and all following episodes.
+    pub fn set_speed(&mut self, speed: f32) -> Result<()> {
+        if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+            return Err(anyhow!("Playback speed must be between {} and {}, got {}", MIN_SPEED, MAX_SPEED, speed));
+        }
+        self.speed = speed;
+        if let Some(sink) = self.sink.lock().unwrap().as_ref() {
+            sink.set_speed(speed);
+        }
+        Ok(())
+    }
+
+    /// Enables loudness normalization for the episodes played from now on, or turns it off.
+    pub fn set_normalization(&mut self, normalization: Option<Normalization>) {
+        self.normalization = normalization;
+    }
+
+    /// Downloads episodes with `client` from now on, e.g. the one the feed source uses.
+    pub fn set_http_client(&mut self, client: HttpClient) {
+        self.client = client;
+    }
+
+    /// Enables shortening of pauses for the episodes played from now on, or turns it off.
+    pub fn set_silence_trimming(&mut self, trimming: Option<SilenceTrimming>) {
+        self.silence_trimming = trimming;
+    }
+
+    pub fn time_saved(&self) -> Option<TimeSaved> {
+        let trimming = self.silence_trimming.as_ref()?;
+        let episode = self.trim_counter.episode();
+        Some(TimeSaved { episode, overall: trimming.log.lock().unwrap().total() + episode })
+    }
+
+    pub fn set_sleep_timer(&mut self, mode: SleepMode) -> Result<()> {
+        let timer = SleepTimer::new(mode, self.current_position(), self.duration(), &self.chapters)?;
+        *self.sleep_timer.lock().unwrap() = Some(timer);
+        Ok(())
+    }
+
+    pub fn extend_sleep_timer(&mut self, by: Duration) -> Result<()> {
+        let position = self.current_position();
+        let mut sleep_timer = self.sleep_timer.lock().unwrap();
+        match sleep_timer.as_mut() {
+            Some(timer) => timer.extend(by, position, self.speed),
+            None => *sleep_timer = Some(SleepTimer::new(SleepMode::After(by), position, None, &[])?),
         }
         Ok(())
     }
 
+    pub fn cancel_sleep_timer(&mut self) {
+        *self.sleep_timer.lock().unwrap() = None;
+        self.fade_gain.set(1.0);
+    }
+
+    pub fn sleep_remaining(&self) -> Option<Duration> {
+        let position = self.current_position();
+        let sleep_timer = self.sleep_timer.lock().unwrap();
+        sleep_timer.as_ref().map(|timer| timer.remaining(position, self.speed))
+    }
+
+    pub fn set_output_device(&mut self, device: Option<&str>) -> Result<()> {
+        let output = AudioOutput::open(&OutputBackend::Device(device.map(str::to_string)))?;
+        self.device = device.map(str::to_string);
+        self.move_playback(output)
+    }
+
+    pub fn output_device(&self) -> String {
+        self.output.name().to_string()
+    }
+
+    /// Follows the audio when its device goes away, e.g. when a headset is unplugged: a chosen
+    /// device falls back to the default one, and the default follows the system's new default.
+    pub fn check_output_device(&mut self) -> Result<Option<String>> {
+        if !self.output.is_device() || self.last_device_check.elapsed() < DEVICE_CHECK_INTERVAL {
+            return Ok(None);
+        }
+        self.last_device_check = Instant::now();
+
+        let current = self.output.name();
+        let gone = match &self.device {
+            Some(_) => !output_devices()?.iter().any(|name| name == current),
+            None => default_output_device().is_some_and(|name| name != current),
+        };
+        if !gone {
+            return Ok(None);
+        }
+        warn!("Output device '{}' went away, switching to the default device", current);
+        let output = AudioOutput::open(&OutputBackend::Device(None))?;
+        self.device = None;
+        self.move_playback(output)?;
+        Ok(Some(self.output_device()))
+    }
+
+    /// Replaces the output and rebuilds the current episode on it at the same position.
+    fn move_playback(&mut self, output: AudioOutput) -> Result<()> {
+        let position = self.current_position();
+        let paused = self.is_paused();
+        let playing = self.sink.lock().unwrap().as_ref().is_some_and(|sink| !sink.empty());
+        // Set up the new sink first, so a failure leaves the episode playing on the old output
+        let sink = match (playing, &self.current_audio) {
+            (true, Some(audio)) => Some(self.new_sink(&output, decode(audio.bytes.clone(), audio.mime_type.as_deref())?)?),
+            _ => None,
+        };
+
+        let old_output = std::mem::replace(&mut self.output, output);
+        info!("Playing to output device '{}'", self.output.name());
+
+        if let Some(sink) = sink {
+            if paused {
+                sink.pause();
+            }
+            if let Err(e) = sink.try_seek(position) {
+                warn!("Could not resume at {}: {}", format_duration(position), e);
+            }
+            if let Some(old_sink) = self.sink.lock().unwrap().replace(sink) {
+                old_sink.stop();
+            }
+        }
+        drop(old_output);
+        Ok(())
+    }
+
+    /// The chapters embedded in the current episode's audio.
+    pub fn chapters(&self) -> &[Chapter] {
+        &self.chapters
+    }
+
+    /// Counts the sleep timer down while audio plays, fades out at the end and then pauses.
+    ///
+    /// This runs on its own thread so the timer also fires while the line prompt waits for input.
+    /// The thread ends with the player.
+    fn spawn_sleep_ticker(&self) {
+        let sleep_timer: Weak<Mutex<Option<SleepTimer>>> = Arc::downgrade(&self.sleep_timer);
+        let sink = self.sink.clone();
+        let trim_counter = self.trim_counter.clone();
+        let fade = self.fade_gain.clone();
+        std::thread::spawn(move || {
+            let mut last_tick = Instant::now();
+            loop {
+                std::thread::sleep(SLEEP_TICK);
+                let played = last_tick.elapsed();
+                last_tick = Instant::now();
+
+                let Some(sleep_timer) = sleep_timer.upgrade() else {
+                    break;
+                };
+                let mut sleep_timer = sleep_timer.lock().unwrap();
+                let Some(timer) = sleep_timer.as_mut() else {
+                    continue;
+                };
+                let sink = sink.lock().unwrap();
+                // Time only counts while something is audible
+                let Some(sink) = sink.as_ref().filter(|sink| !sink.is_paused()) else {
+                    continue;
+                };
+                if sink.empty() && !timer.is_episode_bound() {
+                    continue;
+                }
+
+                timer.elapse(played);
+                let position = sink.get_pos() + trim_counter.since_seek();
+                let remaining = if sink.empty() { Duration::ZERO } else { timer.remaining(position, sink.speed()) };
+                if remaining.is_zero() {
+                    sink.pause();
+                    *sleep_timer = None;
+                    fade.set(1.0);
+                    info!("Sleep timer finished, playback paused");
+                } else {
+                    fade.set(fade_gain(remaining));
+                }
+            }
+        });
+    }
+
+    /// The gain for an episode: from the cache when it was measured before, otherwise unity
+    /// until a background analysis of the downloaded audio has finished.
+    fn normalization_gain(&self, audio_url: &str, audio_bytes: &Arc<[u8]>, mime_type: Option<String>) -> SharedGain {
+        let gain = SharedGain::new(1.0);
+        let Some(normalization) = self.normalization.clone() else {
+            return gain;
+        };
+
+        // Keyed without credentials, which would otherwise end up in the cache file
+        let audio_url = redact(audio_url);
+        if let Some(lufs) = normalization.cache.lock().unwrap().get(&audio_url) {
+            gain.set(normalization_gain(lufs, normalization.target_lufs));
+            return gain;
+        }
+
+        let shared = gain.clone();
+        let audio_bytes = audio_bytes.clone();
+        std::thread::spawn(move || {
+            let lufs = match decode(audio_bytes, mime_type.as_deref()).map(measure) {
+                Ok(Some(lufs)) => lufs,
+                Ok(None) => return info!("Episode is silent, leaving its volume alone"),
+                Err(e) => return warn!("Loudness analysis failed: {}", e),
+            };
+            info!("Measured {:.1} LUFS, normalizing to {:.1} LUFS", lufs, normalization.target_lufs);
+            shared.set(normalization_gain(lufs, normalization.target_lufs));
+            if let Err(e) = normalization.cache.lock().unwrap().insert(&audio_url, lufs) {
+                warn!("Failed to cache loudness: {}", e);
+            }
+        });
+        gain
+    }
+
     // Helpers
     pub fn current_position(&self) -> Duration {
-        *self.current_position.lock().unwrap()
+        match self.sink.lock().unwrap().as_ref() {
+            // Trimmed pauses never reach the sink, so its position lags behind the audio
+            Some(sink) => sink.get_pos() + self.trim_counter.since_seek(),
+            None => *self.current_position.lock().unwrap(),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.sink.lock().unwrap().as_ref().is_some_and(|sink| sink.is_paused())
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume.level()
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.volume.is_muted()
     }
 
     pub fn duration(&self) -> Option<Duration> {
         *self.duration.lock().unwrap()
     }
-}
\ No newline at end of file
+}
+
+/// The volume is written once, when the player goes away, however the session ends.
+impl Drop for AudioPlayer {
+    fn drop(&mut self) {
+        if let Err(e) = self.volume.save() {
+            warn!("Failed to save the volume: {}", e);
+        }
+    }
+}
+
+/// Why an enclosure could not be played.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaybackError {
+    /// The container was recognized, but there is no decoder for the codec inside it.
+    UnsupportedCodec { mime_type: String, codec: String },
+    /// The data is not in any audio container we know.
+    UnrecognizedFormat { mime_type: String },
+}
+
+impl fmt::Display for PlaybackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlaybackError::UnsupportedCodec { mime_type, codec } => {
+                write!(f, "Cannot play {} enclosure: the {} codec is not supported", mime_type, codec)
+            }
+            PlaybackError::UnrecognizedFormat { mime_type } => {
+                write!(f, "Cannot play {} enclosure: unrecognized audio format", mime_type)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PlaybackError {}
+
+/// Decodes a whole downloaded episode. Every format goes through symphonia, so the result can seek.
+///
+/// When the audio cannot be decoded at all, the error is a [`PlaybackError`] naming the MIME type
+/// and, if the container could be read, the codec.
+pub fn decode(bytes: impl Into<Arc<[u8]>>, mime_type: Option<&str>) -> Result<DecodedAudio> {
+    let bytes = bytes.into();
+    DecodedAudio::new(bytes.clone(), mime_type).map_err(|e| match diagnose(bytes, mime_type) {
+        Some(error) => anyhow::Error::new(error),
+        None => anyhow!("Failed to decode audio: {}", e),
+    })
+}
+
+/// Finds out whether undecodable audio is an unknown container or a codec we cannot decode.
+/// Returns `None` when both are supported, i.e. the data itself is broken.
+fn diagnose(bytes: Arc<[u8]>, mime_type: Option<&str>) -> Option<PlaybackError> {
+    let mut hint = Hint::new();
+    if let Some(mime_type) = mime_type {
+        hint.mime_type(mime_type);
+    }
+    let stream = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let mime_type = mime_type.unwrap_or("unknown").to_string();
+
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        stream,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    );
+    let Ok(probed) = probed else {
+        return Some(PlaybackError::UnrecognizedFormat { mime_type });
+    };
+
+    let Some(track) = probed.format.default_track() else {
+        return Some(PlaybackError::UnsupportedCodec { mime_type, codec: codec_name(CODEC_TYPE_NULL) });
+    };
+    // A decoder may exist for the codec and still refuse the stream, e.g. surround Opus
+    match codecs().make(&track.codec_params, &DecoderOptions::default()) {
+        Err(SymphoniaError::Unsupported(_)) => {
+            Some(PlaybackError::UnsupportedCodec { mime_type, codec: codec_name(track.codec_params.codec) })
+        }
+        _ => None,
+    }
+}
+
+fn codec_name(codec: CodecType) -> String {
+    match codec {
+        CODEC_TYPE_OPUS => "Opus".to_string(),
+        CODEC_TYPE_SPEEX => "Speex".to_string(),
+        CODEC_TYPE_WMA => "WMA".to_string(),
+        CODEC_TYPE_NULL => "unknown".to_string(),
+        other => format!("unknown ({})", other),
+    }
+}
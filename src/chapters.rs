@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+/// A chapter marker embedded in the episode audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub title: Option<String>,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// The chapter playing at `position`, if any.
+pub fn chapter_at(chapters: &[Chapter], position: Duration) -> Option<&Chapter> {
+    chapters.iter().find(|chapter| chapter.start <= position && position < chapter.end)
+}
+
+/// Reads the `CHAP` frames of a leading ID3v2.3 or ID3v2.4 tag, ordered by start time.
+pub fn parse_id3_chapters(bytes: &[u8]) -> Vec<Chapter> {
+    let Some((version, body)) = id3_body(bytes) else {
+        return Vec::new();
+    };
+
+    let mut chapters: Vec<Chapter> = frames(body, version)
+        .filter(|(id, _)| id == b"CHAP")
+        .filter_map(|(_, data)| parse_chap(data, version))
+        .collect();
+    chapters.sort_by_key(|chapter| chapter.start);
+    chapters
+}
+
+/// The tag version and the bytes holding its frames.
+fn id3_body(bytes: &[u8]) -> Option<(u8, &[u8])> {
+    let [b'I', b'D', b'3', version, _, flags, s0, s1, s2, s3, ..] = *bytes else {
+        return None;
+    };
+    if version != 3 && version != 4 {
+        return None;
+    }
+    let size = syncsafe([s0, s1, s2, s3]);
+    let mut body = bytes.get(10..10 + size).unwrap_or(&bytes[10..]);
+
+    // Skip the extended header; its size includes itself in v2.4 but not in v2.3
+    if flags & 0x40 != 0 {
+        let raw: [u8; 4] = body.get(..4)?.try_into().ok()?;
+        let skip = if version == 4 { syncsafe(raw) } else { u32::from_be_bytes(raw) as usize + 4 };
+        body = body.get(skip..)?;
+    }
+    Some((version, body))
+}
+
+fn syncsafe(bytes: [u8; 4]) -> usize {
+    bytes.iter().fold(0, |acc, &b| (acc << 7) | (b & 0x7F) as usize)
+}
+
+/// Iterates over `(frame id, frame data)` until padding or the end of the data.
+fn frames(mut data: &[u8], version: u8) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    std::iter::from_fn(move || {
+        let id: [u8; 4] = data.get(..4)?.try_into().ok()?;
+        if id[0] == 0 {
+            return None;
+        }
+        let raw: [u8; 4] = data.get(4..8)?.try_into().ok()?;
+        let size = if version == 4 { syncsafe(raw) } else { u32::from_be_bytes(raw) as usize };
+        let frame = data.get(10..10 + size)?;
+        data = &data[10 + size..];
+        Some((id, frame))
+    })
+}
+
+fn parse_chap(data: &[u8], version: u8) -> Option<Chapter> {
+    // Element ID, then start and end in milliseconds, then byte offsets we do not need
+    let id_end = data.iter().position(|&b| b == 0)?;
+    let times = data.get(id_end + 1..id_end + 17)?;
+    let start = u32::from_be_bytes(times[0..4].try_into().ok()?);
+    let end = u32::from_be_bytes(times[4..8].try_into().ok()?);
+    if end <= start {
+        return None;
+    }
+
+    let title = frames(&data[id_end + 17..], version)
+        .find(|(id, _)| id == b"TIT2")
+        .and_then(|(_, text)| decode_text(text));
+
+    Some(Chapter {
+        title,
+        start: Duration::from_millis(start as u64),
+        end: Duration::from_millis(end as u64),
+    })
+}
+
+/// Decodes an ID3 text frame: an encoding byte followed by the text.
+fn decode_text(data: &[u8]) -> Option<String> {
+    let (&encoding, text) = data.split_first()?;
+    let text = match encoding {
+        0 => text.iter().map(|&b| b as char).collect(),
+        1 | 2 => {
+            let (big_endian, text) = match text {
+                [0xFE, 0xFF, rest @ ..] => (true, rest),
+                [0xFF, 0xFE, rest @ ..] => (false, rest),
+                _ => (encoding == 2, text),
+            };
+            let units: Vec<u16> = text
+                .chunks_exact(2)
+                .map(|pair| if big_endian { u16::from_be_bytes([pair[0], pair[1]]) } else { u16::from_le_bytes([pair[0], pair[1]]) })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(text).into_owned(),
+    };
+    let text = text.trim_end_matches('\0').trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
@@ -0,0 +1,165 @@
+use clap::{Parser, Subcommand};
+use rss_reader::config::{ConfigOverrides, DirectoryService, InputMode, Theme};
+use rss_reader::audio_output::OutputBackend;
+use rss_reader::OutputFormat;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "rss_reader", about = "Podcast player for the terminal")]
+pub struct Cli {
+    /// Config file (default: $XDG_CONFIG_HOME/rss_reader/config.toml)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Subscription file: one feed URL per line, or TOML with a [[feed]] table per feed
+    #[arg(long, global = true)]
+    pub feeds: Option<PathBuf>,
+
+    /// Directory for persistent application data
+    #[arg(long, global = true)]
+    pub data_dir: Option<PathBuf>,
+
+    /// Directory for cached data
+    #[arg(long, global = true)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Directory for downloaded episodes
+    #[arg(long, global = true)]
+    pub download_dir: Option<PathBuf>,
+
+    /// Default playback speed
+    #[arg(long, global = true)]
+    pub speed: Option<f32>,
+
+    /// Loudness every episode is normalized to, in LUFS
+    #[arg(long, global = true, allow_negative_numbers = true)]
+    pub target_lufs: Option<f64>,
+
+    /// Shorten long pauses while playing
+    #[arg(long, global = true)]
+    pub trim_silence: bool,
+
+    /// Audio output device, by name or part of it (see the devices command)
+    #[arg(long, global = true)]
+    pub output_device: Option<String>,
+
+    /// Audio output: device, or headless null, null:SPEED or wav:PATH
+    #[arg(long, global = true)]
+    pub output: Option<OutputBackend>,
+
+    /// Seconds to wait for a stalled server before giving up
+    #[arg(long, global = true)]
+    pub timeout: Option<u64>,
+
+    /// Extra attempts after a transient network failure
+    #[arg(long, global = true)]
+    pub retries: Option<u32>,
+
+    /// User-Agent header sent with every request
+    #[arg(long, global = true)]
+    pub user_agent: Option<String>,
+
+    /// Podcast directory to search: itunes or podcastindex
+    #[arg(long, global = true)]
+    pub directory: Option<DirectoryService>,
+
+    /// Playback controls: keys (single key presses) or line (commands confirmed with Enter)
+    #[arg(long, global = true)]
+    pub input: Option<InputMode>,
+
+    /// UI theme: default, light or mono
+    #[arg(long, global = true)]
+    pub theme: Option<Theme>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List all subscribed podcasts
+    Podcasts {
+        /// Output format: text, json or ndjson
+        #[arg(long, short, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        /// Only podcasts with this tag or in this folder
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// List the episodes of a feed (defaults to the first subscription)
+    Episodes {
+        feed_url: Option<String>,
+
+        /// Output format: text, json or ndjson
+        #[arg(long, short, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Pick an episode of a feed at a prompt and play it (defaults to the first subscription)
+    Play {
+        feed_url: Option<String>,
+    },
+    /// Subscribe to a feed, given its URL or the URL of a website that links to it
+    Add {
+        url: String,
+    },
+    /// Unsubscribe from a feed
+    Remove {
+        url: String,
+    },
+    /// Give a podcast a title of your own; without one it uses the feed's title again
+    Rename {
+        url: String,
+        title: Option<String>,
+    },
+    /// Tag a podcast, e.g. to list it with `podcasts --tag`
+    Tag {
+        url: String,
+
+        #[arg(required = true)]
+        tags: Vec<String>,
+
+        /// Take the tags off instead
+        #[arg(long, short)]
+        remove: bool,
+    },
+    /// Search the podcast directory for shows to subscribe to
+    SearchDirectory {
+        #[arg(required = true)]
+        query: Vec<String>,
+
+        /// Most results to list
+        #[arg(long, short)]
+        limit: Option<usize>,
+
+        /// Output format: text, json or ndjson
+        #[arg(long, short, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// List the audio output devices
+    Devices,
+}
+
+impl Cli {
+    pub fn overrides(&self) -> ConfigOverrides {
+        ConfigOverrides {
+            config_file: self.config.clone(),
+            subscriptions: self.feeds.clone(),
+            data_dir: self.data_dir.clone(),
+            cache_dir: self.cache_dir.clone(),
+            download_dir: self.download_dir.clone(),
+            default_speed: self.speed,
+            input_mode: self.input,
+            target_lufs: self.target_lufs,
+            trim_silence: self.trim_silence.then_some(true),
+            output_device: self.output_device.clone(),
+            output: self.output.clone(),
+            timeout_secs: self.timeout,
+            retries: self.retries,
+            user_agent: self.user_agent.clone(),
+            directory_provider: self.directory,
+            theme: self.theme,
+            ..Default::default()
+        }
+    }
+}
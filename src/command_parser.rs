@@ -0,0 +1,172 @@
+use anyhow::{anyhow, bail, Result};
+use std::time::Duration;
+use crate::audio_player::{PlayerCommand, MAX_SPEED, MIN_SPEED};
+use crate::episodes::parse_duration;
+use crate::sleep_timer::SleepMode;
+
+/// Usage examples shown in the help for commands that take an argument.
+pub const ARGUMENT_EXAMPLES: &[(&str, &str)] = &[
+    ("f 45, b 2m", "Skip forward or backward by a custom amount"),
+    ("seek 1:02:30", "Jump to an absolute position"),
+    ("seek 40%", "Jump to a percentage of the episode"),
+    ("vol 0.6", "Set the volume (1.0 is the original level)"),
+    ("speed 1.5", "Set the playback speed"),
+    ("sleep 30, sleep end", "Stop in 30 minutes, or at the end of the episode"),
+    ("sleep chapter", "Stop at the end of the current chapter"),
+    ("sleep +10, sleep off", "Extend the sleep timer, or cancel it"),
+    ("device list", "List the audio output devices"),
+    ("device USB, device default", "Play to another output device"),
+];
+
+/// The keyword commands that are not tied to a key binding.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Keyword {
+    Seek,
+    Volume,
+    Speed,
+    Sleep,
+    Device,
+}
+
+impl Keyword {
+    pub fn parse(word: &str) -> Option<Self> {
+        match word.to_ascii_lowercase().as_str() {
+            "seek" | "goto" => Some(Keyword::Seek),
+            "vol" | "volume" => Some(Keyword::Volume),
+            "speed" => Some(Keyword::Speed),
+            "sleep" => Some(Keyword::Sleep),
+            "device" | "output" => Some(Keyword::Device),
+            _ => None,
+        }
+    }
+
+    pub fn command(&self, argument: &str) -> Result<PlayerCommand> {
+        match self {
+            Keyword::Seek => parse_seek_target(argument),
+            Keyword::Volume => {
+                let volume = parse_number(argument, "volume")?;
+                if volume < 0.0 {
+                    bail!("Volume cannot be negative, got {}", argument);
+                }
+                Ok(PlayerCommand::SetVolume(volume))
+            }
+            Keyword::Speed => {
+                let speed = parse_number(argument.trim_end_matches('x'), "speed")?;
+                if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+                    bail!("Speed must be between {} and {}, got {}", MIN_SPEED, MAX_SPEED, argument);
+                }
+                Ok(PlayerCommand::SetSpeed(speed))
+            }
+            Keyword::Sleep => parse_sleep(argument),
+            Keyword::Device => Ok(match argument.trim() {
+                "list" => PlayerCommand::ListOutputDevices,
+                "default" => PlayerCommand::SetOutputDevice(None),
+                name => PlayerCommand::SetOutputDevice(Some(name.to_string())),
+            }),
+        }
+    }
+}
+
+/// Splits `"seek 1:02:30"` into the command word and its optional argument.
+pub fn split_command(input: &str) -> (&str, Option<&str>) {
+    let input = input.trim();
+    match input.split_once(char::is_whitespace) {
+        Some((word, argument)) => (word, Some(argument.trim())),
+        None => (input, None),
+    }
+}
+
+/// Parses an absolute position (`1:02:30`, `90s`, `2m`) or a percentage (`40%`).
+pub fn parse_seek_target(argument: &str) -> Result<PlayerCommand> {
+    match argument.trim().strip_suffix('%') {
+        Some(percent) => {
+            let percent = parse_number(percent, "percentage")?;
+            if !(0.0..=100.0).contains(&percent) {
+                bail!("Percentage must be between 0 and 100, got {}%", percent);
+            }
+            Ok(PlayerCommand::SeekPercent(percent))
+        }
+        None => Ok(PlayerCommand::Seek(parse_time(argument)?)),
+    }
+}
+
+/// Parses a sleep timer: minutes (`30`, `1h`), `end`, `chapter`, an extension (`+10`) or `off`.
+pub fn parse_sleep(argument: &str) -> Result<PlayerCommand> {
+    let argument = argument.trim();
+    match argument.to_ascii_lowercase().as_str() {
+        "end" | "episode" => return Ok(PlayerCommand::Sleep(SleepMode::EndOfEpisode)),
+        "chapter" => return Ok(PlayerCommand::Sleep(SleepMode::EndOfChapter)),
+        "off" | "cancel" => return Ok(PlayerCommand::CancelSleep),
+        _ => {}
+    }
+    let (extend, time) = match argument.strip_prefix('+') {
+        Some(time) => (true, time.trim()),
+        None => (false, argument),
+    };
+    // A plain number is minutes here, unlike for skipping and seeking
+    let time = if !time.is_empty() && time.chars().all(|c| c.is_ascii_digit()) {
+        let minutes: u64 = time.parse().map_err(|_| anyhow!("Invalid sleep time '{}'", time))?;
+        Duration::from_secs(minutes.saturating_mul(60))
+    } else {
+        parse_time(time)?
+    };
+    if time.is_zero() {
+        bail!("Sleep time must be greater than zero, got {}", argument);
+    }
+    if extend {
+        Ok(PlayerCommand::ExtendSleep(time))
+    } else {
+        Ok(PlayerCommand::Sleep(SleepMode::After(time)))
+    }
+}
+
+/// Parses a time span: plain seconds (`45`), `H:MM:SS` / `M:SS`, or unit suffixes (`2m`, `1h30m`, `90s`).
+pub fn parse_time(argument: &str) -> Result<Duration> {
+    let argument = argument.trim();
+    if argument.contains(':') || argument.chars().all(|c| c.is_ascii_digit()) {
+        return parse_duration(argument).ok_or_else(|| anyhow!("Invalid time '{}'", argument));
+    }
+
+    let mut total: u64 = 0;
+    let mut digits = String::new();
+    for c in argument.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let multiplier = match c.to_ascii_lowercase() {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => bail!("Invalid time '{}' (use e.g. 45, 2m, 1h30m or 1:02:30)", argument),
+        };
+        let value: u64 = digits
+            .parse()
+            .map_err(|_| anyhow!("Invalid time '{}' (missing number before '{}')", argument, c))?;
+        total = value
+            .checked_mul(multiplier)
+            .and_then(|seconds| seconds.checked_add(total))
+            .ok_or_else(|| anyhow!("Invalid time '{}' (too long)", argument))?;
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        bail!("Invalid time '{}' (missing unit after {})", argument, digits);
+    }
+    Ok(Duration::from_secs(total))
+}
+
+fn parse_number(argument: &str, what: &str) -> Result<f32> {
+    let value: f32 = argument
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Invalid {} '{}'", what, argument.trim()))?;
+    if !value.is_finite() {
+        bail!("Invalid {} '{}'", what, argument.trim());
+    }
+    Ok(value)
+}
+
+/// Parses the step for a volume command, e.g. the `0.2` in `+ 0.2`.
+pub fn parse_volume_step(argument: &str) -> Result<f32> {
+    parse_number(argument, "volume step")
+}
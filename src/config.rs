@@ -0,0 +1,669 @@
+use crate::audio_player::{MAX_SPEED, MIN_SPEED};
+use anyhow::{anyhow, bail, Context, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use crate::audio_output::OutputBackend;
+use crate::directory::{DirectoryProvider, ItunesDirectory, PodcastIndexDirectory, ITUNES_URL, PODCAST_INDEX_URL};
+use crate::http::HttpClient;
+use crate::http::HttpSettings;
+use crate::loudness::DEFAULT_TARGET_LUFS;
+use crate::refresh_scheduler::ScheduleSettings;
+use crate::silence::{self, TrimSettings};
+use crate::volume::DEFAULT_MAX_VOLUME;
+
+pub const APP_NAME: &str = "rss_reader";
+const CONFIG_FILE_NAME: &str = "config.toml";
+const SUBSCRIPTIONS_FILE_NAME: &str = "rss-db.txt";
+const ENV_PREFIX: &str = "RSS_READER_";
+const MB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Config {
+    pub paths: PathsConfig,
+    pub playback: PlaybackConfig,
+    pub refresh: RefreshConfig,
+    pub network: NetworkConfig,
+    pub directory: DirectoryConfig,
+    pub ui: UiConfig,
+    pub keys: KeysConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathsConfig {
+    pub subscriptions: PathBuf,
+    pub data_dir: PathBuf,
+    pub cache_dir: PathBuf,
+    pub download_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaybackConfig {
+    pub skip_forward_secs: i64,
+    pub skip_backward_secs: i64,
+    pub volume_step: f32,
+    /// The highest volume level; 1.0 plays episodes at their (normalized) level.
+    pub max_volume: f32,
+    pub default_speed: f32,
+    pub input_cooldown_ms: u64,
+    pub input_mode: InputMode,
+    /// Bring every episode to `target_lufs`, measured with EBU R128.
+    pub normalize: bool,
+    pub target_lufs: f64,
+    /// Shorten pauses quieter than `silence_threshold_db` to `max_pause_ms`.
+    pub trim_silence: bool,
+    pub silence_threshold_db: f32,
+    pub max_pause_ms: u64,
+    /// Output device name, or part of it; the system default when unset.
+    pub output_device: Option<String>,
+    /// Where audio goes: `device`, or the headless `null`, `null:SPEED` and `wav:PATH`.
+    pub output: OutputBackend,
+}
+
+/// How playback controls are read from the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum InputMode {
+    /// Single key presses in raw mode; falls back to `Line` when stdin is not a terminal.
+    #[default]
+    Keys,
+    /// Whole lines confirmed with Enter, which allows commands with arguments.
+    Line,
+}
+
+impl FromStr for InputMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "keys" => Ok(InputMode::Keys),
+            "line" => Ok(InputMode::Line),
+            other => Err(anyhow!("Unknown input mode '{}' (expected keys or line)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefreshConfig {
+    /// How often feeds are refreshed while the TUI is open.
+    pub interval_mins: u64,
+    /// Up to this share of each wait is added or taken off at random, so feeds spread out.
+    pub jitter_percent: u8,
+    /// The longest wait between refreshes, however rarely a feed says it changes or however
+    /// often it failed.
+    pub max_interval_mins: u64,
+}
+
+/// How feeds and episodes are downloaded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    pub connect_timeout_secs: u64,
+    /// How long to wait for a stalled server before giving up on a request.
+    pub timeout_secs: u64,
+    /// Extra attempts after a timeout, a dropped connection, 408, 429 or a server error.
+    pub retries: u32,
+    /// The wait before the first retry, doubling for each one after.
+    pub retry_backoff_ms: u64,
+    pub user_agent: String,
+    pub max_feed_mb: u64,
+    pub max_enclosure_mb: u64,
+    /// Accept gzip and brotli compressed responses.
+    pub compression: bool,
+    /// Proxy for all requests, e.g. `http://proxy.example.com:3128`; the `HTTPS_PROXY`
+    /// and related variables apply when unset.
+    pub proxy: Option<String>,
+    /// Comma-separated hosts reached without the proxy, e.g. `localhost,.internal`.
+    pub no_proxy: Option<String>,
+    /// PEM files with extra root certificates, e.g. a company CA.
+    pub ca_certificates: Vec<PathBuf>,
+}
+
+/// Where `search-directory` and the TUI's "Add podcast" dialog look for shows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirectoryConfig {
+    pub provider: DirectoryService,
+    /// Most results per search.
+    pub limit: usize,
+    pub itunes_url: String,
+    pub podcastindex_url: String,
+    /// Credentials from podcastindex.org, needed for the Podcast Index.
+    pub podcastindex_key: Option<String>,
+    pub podcastindex_secret: Option<String>,
+}
+
+/// A podcast directory to search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DirectoryService {
+    #[default]
+    Itunes,
+    PodcastIndex,
+}
+
+impl FromStr for DirectoryService {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "itunes" => Ok(DirectoryService::Itunes),
+            "podcastindex" | "podcast-index" => Ok(DirectoryService::PodcastIndex),
+            other => Err(anyhow!("Unknown directory '{}' (expected itunes or podcastindex)", other)),
+        }
+    }
+}
+
+impl fmt::Display for DirectoryService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DirectoryService::Itunes => "itunes",
+            DirectoryService::PodcastIndex => "podcastindex",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct UiConfig {
+    pub theme: Theme,
+}
+
+/// Key binding overrides per action name, e.g. `pause = ["p", "space"]` under `[keys.player]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct KeysConfig {
+    pub player: HashMap<String, KeyList>,
+    pub tui: HashMap<String, KeyList>,
+}
+
+/// One key binding or a list of alternatives.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    pub fn as_slice(&self) -> &[String] {
+        match self {
+            KeyList::One(key) => std::slice::from_ref(key),
+            KeyList::Many(keys) => keys,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Default,
+    Light,
+    Mono,
+}
+
+impl FromStr for Theme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "default" => Ok(Theme::Default),
+            "light" => Ok(Theme::Light),
+            "mono" => Ok(Theme::Mono),
+            other => Err(anyhow!("Unknown theme '{}' (expected default, light or mono)", other)),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Theme::Default => "default",
+            Theme::Light => "light",
+            Theme::Mono => "mono",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        let data_dir = app_dir(dirs::data_dir());
+        Self {
+            subscriptions: default_subscriptions_path(),
+            download_dir: data_dir.join("downloads"),
+            cache_dir: app_dir(dirs::cache_dir()),
+            data_dir,
+        }
+    }
+}
+
+impl Default for PlaybackConfig {
+    fn default() -> Self {
+        Self {
+            skip_forward_secs: 10,
+            skip_backward_secs: 10,
+            volume_step: 0.1,
+            max_volume: DEFAULT_MAX_VOLUME,
+            default_speed: 1.0,
+            input_cooldown_ms: 250,
+            input_mode: InputMode::default(),
+            normalize: true,
+            target_lufs: DEFAULT_TARGET_LUFS,
+            trim_silence: false,
+            silence_threshold_db: silence::DEFAULT_THRESHOLD_DB,
+            max_pause_ms: silence::DEFAULT_MAX_PAUSE.as_millis() as u64,
+            output_device: None,
+            output: OutputBackend::default(),
+        }
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        let settings = HttpSettings::default();
+        Self {
+            connect_timeout_secs: settings.connect_timeout.as_secs(),
+            timeout_secs: settings.read_timeout.as_secs(),
+            retries: settings.retries,
+            retry_backoff_ms: settings.retry_backoff.as_millis() as u64,
+            user_agent: settings.user_agent,
+            max_feed_mb: settings.max_feed_bytes / MB,
+            max_enclosure_mb: settings.max_enclosure_bytes / MB,
+            compression: settings.compression,
+            proxy: settings.proxy,
+            no_proxy: settings.no_proxy,
+            ca_certificates: settings.ca_certificates,
+        }
+    }
+}
+
+impl Default for DirectoryConfig {
+    fn default() -> Self {
+        Self {
+            provider: DirectoryService::default(),
+            limit: 20,
+            itunes_url: ITUNES_URL.to_string(),
+            podcastindex_url: PODCAST_INDEX_URL.to_string(),
+            podcastindex_key: None,
+            podcastindex_secret: None,
+        }
+    }
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        Self { interval_mins: 60, jitter_percent: 10, max_interval_mins: 24 * 60 }
+    }
+}
+
+impl PlaybackConfig {
+    pub fn input_cooldown(&self) -> Duration {
+        Duration::from_millis(self.input_cooldown_ms)
+    }
+
+    /// The output backend, with a bare `device` resolved to the configured `output_device`.
+    pub fn output_backend(&self) -> OutputBackend {
+        match &self.output {
+            OutputBackend::Device(None) => OutputBackend::Device(self.output_device.clone()),
+            other => other.clone(),
+        }
+    }
+
+    pub fn trim_settings(&self) -> TrimSettings {
+        TrimSettings {
+            threshold_db: self.silence_threshold_db,
+            max_pause: Duration::from_millis(self.max_pause_ms),
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub fn http_settings(&self) -> HttpSettings {
+        HttpSettings {
+            connect_timeout: Duration::from_secs(self.connect_timeout_secs),
+            read_timeout: Duration::from_secs(self.timeout_secs),
+            retries: self.retries,
+            retry_backoff: Duration::from_millis(self.retry_backoff_ms),
+            user_agent: self.user_agent.clone(),
+            max_feed_bytes: self.max_feed_mb.saturating_mul(MB),
+            max_enclosure_bytes: self.max_enclosure_mb.saturating_mul(MB),
+            compression: self.compression,
+            proxy: self.proxy.clone(),
+            no_proxy: self.no_proxy.clone(),
+            ca_certificates: self.ca_certificates.iter().map(|path| expand_tilde(path)).collect(),
+        }
+    }
+}
+
+impl DirectoryConfig {
+    /// The configured directory, searched with `client`.
+    pub fn provider(&self, client: HttpClient) -> Box<dyn DirectoryProvider> {
+        match self.provider {
+            DirectoryService::Itunes => Box::new(ItunesDirectory::with_base_url(client, &self.itunes_url)),
+            DirectoryService::PodcastIndex => Box::new(PodcastIndexDirectory::with_base_url(
+                client,
+                &self.podcastindex_url,
+                self.podcastindex_key.clone().unwrap_or_default(),
+                self.podcastindex_secret.clone().unwrap_or_default(),
+            )),
+        }
+    }
+}
+
+impl RefreshConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_mins * 60)
+    }
+
+    /// The background refresh schedule, or `None` for a zero interval.
+    pub fn schedule_settings(&self) -> Option<ScheduleSettings> {
+        (self.interval_mins > 0).then(|| ScheduleSettings {
+            interval: self.interval(),
+            jitter: f64::from(self.jitter_percent.min(100)) / 100.0,
+            max_interval: Duration::from_secs(self.max_interval_mins * 60).max(self.interval()),
+        })
+    }
+}
+
+/// Values that take precedence over the config file, collected from the environment or the command line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigOverrides {
+    pub config_file: Option<PathBuf>,
+    pub subscriptions: Option<PathBuf>,
+    pub data_dir: Option<PathBuf>,
+    pub cache_dir: Option<PathBuf>,
+    pub download_dir: Option<PathBuf>,
+    pub skip_forward_secs: Option<i64>,
+    pub skip_backward_secs: Option<i64>,
+    pub volume_step: Option<f32>,
+    pub default_speed: Option<f32>,
+    pub input_mode: Option<InputMode>,
+    pub target_lufs: Option<f64>,
+    pub trim_silence: Option<bool>,
+    pub output_device: Option<String>,
+    pub output: Option<OutputBackend>,
+    pub refresh_interval_mins: Option<u64>,
+    pub timeout_secs: Option<u64>,
+    pub retries: Option<u32>,
+    pub user_agent: Option<String>,
+    pub directory_provider: Option<DirectoryService>,
+    pub podcastindex_key: Option<String>,
+    pub podcastindex_secret: Option<String>,
+    pub theme: Option<Theme>,
+}
+
+impl ConfigOverrides {
+    /// Reads `RSS_READER_*` variables from the process environment.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Builds overrides from a variable lookup, e.g. `RSS_READER_DATA_DIR` or `RSS_READER_SPEED`.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        Ok(Self {
+            config_file: env_var(&lookup, "CONFIG")?,
+            subscriptions: env_var(&lookup, "SUBSCRIPTIONS")?,
+            data_dir: env_var(&lookup, "DATA_DIR")?,
+            cache_dir: env_var(&lookup, "CACHE_DIR")?,
+            download_dir: env_var(&lookup, "DOWNLOAD_DIR")?,
+            skip_forward_secs: env_var(&lookup, "SKIP_FORWARD")?,
+            skip_backward_secs: env_var(&lookup, "SKIP_BACKWARD")?,
+            volume_step: env_var(&lookup, "VOLUME_STEP")?,
+            default_speed: env_var(&lookup, "SPEED")?,
+            input_mode: env_var(&lookup, "INPUT_MODE")?,
+            target_lufs: env_var(&lookup, "TARGET_LUFS")?,
+            trim_silence: env_var(&lookup, "TRIM_SILENCE")?,
+            output_device: env_var(&lookup, "OUTPUT_DEVICE")?,
+            output: env_var(&lookup, "OUTPUT")?,
+            refresh_interval_mins: env_var(&lookup, "REFRESH_INTERVAL")?,
+            timeout_secs: env_var(&lookup, "TIMEOUT")?,
+            retries: env_var(&lookup, "RETRIES")?,
+            user_agent: env_var(&lookup, "USER_AGENT")?,
+            directory_provider: env_var(&lookup, "DIRECTORY")?,
+            podcastindex_key: env_var(&lookup, "PODCASTINDEX_KEY")?,
+            podcastindex_secret: env_var(&lookup, "PODCASTINDEX_SECRET")?,
+            theme: env_var(&lookup, "THEME")?,
+        })
+    }
+
+    /// Combines two sets of overrides, preferring values from `other`.
+    pub fn merge(self, other: ConfigOverrides) -> Self {
+        Self {
+            config_file: other.config_file.or(self.config_file),
+            subscriptions: other.subscriptions.or(self.subscriptions),
+            data_dir: other.data_dir.or(self.data_dir),
+            cache_dir: other.cache_dir.or(self.cache_dir),
+            download_dir: other.download_dir.or(self.download_dir),
+            skip_forward_secs: other.skip_forward_secs.or(self.skip_forward_secs),
+            skip_backward_secs: other.skip_backward_secs.or(self.skip_backward_secs),
+            volume_step: other.volume_step.or(self.volume_step),
+            default_speed: other.default_speed.or(self.default_speed),
+            input_mode: other.input_mode.or(self.input_mode),
+            target_lufs: other.target_lufs.or(self.target_lufs),
+            trim_silence: other.trim_silence.or(self.trim_silence),
+            output_device: other.output_device.or(self.output_device),
+            output: other.output.or(self.output),
+            refresh_interval_mins: other.refresh_interval_mins.or(self.refresh_interval_mins),
+            timeout_secs: other.timeout_secs.or(self.timeout_secs),
+            retries: other.retries.or(self.retries),
+            user_agent: other.user_agent.or(self.user_agent),
+            directory_provider: other.directory_provider.or(self.directory_provider),
+            podcastindex_key: other.podcastindex_key.or(self.podcastindex_key),
+            podcastindex_secret: other.podcastindex_secret.or(self.podcastindex_secret),
+            theme: other.theme.or(self.theme),
+        }
+    }
+}
+
+fn env_var<T: FromStr>(lookup: &impl Fn(&str) -> Option<String>, name: &str) -> Result<Option<T>>
+where
+    T::Err: fmt::Display,
+{
+    let name = format!("{}{}", ENV_PREFIX, name);
+    match lookup(&name).filter(|value| !value.trim().is_empty()) {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| anyhow!("Invalid value '{}' for {}: {}", value, name, e)),
+        None => Ok(None),
+    }
+}
+
+impl Config {
+    /// Loads the configuration with the precedence defaults < config file < `overrides`.
+    ///
+    /// A missing file at the default location is not an error; a missing file that was
+    /// explicitly requested is.
+    pub fn load(overrides: &ConfigOverrides) -> Result<Self> {
+        let mut config = match &overrides.config_file {
+            Some(path) => Self::from_file(path)?,
+            None => {
+                let path = default_config_path();
+                if path.exists() {
+                    Self::from_file(&path)?
+                } else {
+                    Self::default()
+                }
+            }
+        };
+        config.apply(overrides);
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Rejects values that would silently misbehave, naming the setting at fault.
+    pub fn validate(&self) -> Result<()> {
+        let playback = &self.playback;
+        check(playback.skip_forward_secs > 0, "playback.skip_forward_secs", playback.skip_forward_secs, "positive")?;
+        check(playback.skip_backward_secs > 0, "playback.skip_backward_secs", playback.skip_backward_secs, "positive")?;
+        check(playback.volume_step > 0.0 && playback.volume_step <= 1.0, "playback.volume_step", playback.volume_step, "above 0 and at most 1")?;
+        check(
+            (MIN_SPEED..=MAX_SPEED).contains(&playback.default_speed),
+            "playback.default_speed",
+            playback.default_speed,
+            &format!("between {} and {}", MIN_SPEED, MAX_SPEED),
+        )?;
+        check((-70.0..=0.0).contains(&playback.target_lufs), "playback.target_lufs", playback.target_lufs, "between -70 and 0")?;
+        check(playback.silence_threshold_db < 0.0, "playback.silence_threshold_db", playback.silence_threshold_db, "negative")?;
+        check(playback.max_pause_ms > 0, "playback.max_pause_ms", playback.max_pause_ms, "positive")?;
+        check(playback.max_volume > 0.0 && playback.max_volume <= 2.0, "playback.max_volume", playback.max_volume, "above 0 and at most 2")?;
+        let refresh = &self.refresh;
+        check(refresh.interval_mins > 0, "refresh.interval_mins", refresh.interval_mins, "positive")?;
+        check(refresh.jitter_percent <= 100, "refresh.jitter_percent", refresh.jitter_percent, "at most 100")?;
+        check(
+            refresh.max_interval_mins >= refresh.interval_mins,
+            "refresh.max_interval_mins",
+            refresh.max_interval_mins,
+            "at least refresh.interval_mins",
+        )?;
+        let network = &self.network;
+        check(network.connect_timeout_secs > 0, "network.connect_timeout_secs", network.connect_timeout_secs, "positive")?;
+        check(network.timeout_secs > 0, "network.timeout_secs", network.timeout_secs, "positive")?;
+        check(network.retries <= 10, "network.retries", network.retries, "at most 10")?;
+        check(network.max_feed_mb > 0, "network.max_feed_mb", network.max_feed_mb, "positive")?;
+        check(network.max_enclosure_mb > 0, "network.max_enclosure_mb", network.max_enclosure_mb, "positive")?;
+        check(!network.user_agent.trim().is_empty(), "network.user_agent", format!("{:?}", network.user_agent), "not empty")?;
+        check((1..=200).contains(&self.directory.limit), "directory.limit", self.directory.limit, "between 1 and 200")?;
+        Ok(())
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        info!("Loaded configuration from {}", path.display());
+        Self::from_toml(&content).with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let mut config: Config = toml::from_str(content)?;
+        config.paths.expand_tilde();
+        Ok(config)
+    }
+
+    pub fn apply(&mut self, overrides: &ConfigOverrides) {
+        if let Some(path) = &overrides.subscriptions {
+            self.paths.subscriptions = path.clone();
+        }
+        if let Some(path) = &overrides.data_dir {
+            self.paths.data_dir = path.clone();
+        }
+        if let Some(path) = &overrides.cache_dir {
+            self.paths.cache_dir = path.clone();
+        }
+        if let Some(path) = &overrides.download_dir {
+            self.paths.download_dir = path.clone();
+        }
+        if let Some(secs) = overrides.skip_forward_secs {
+            self.playback.skip_forward_secs = secs;
+        }
+        if let Some(secs) = overrides.skip_backward_secs {
+            self.playback.skip_backward_secs = secs;
+        }
+        if let Some(step) = overrides.volume_step {
+            self.playback.volume_step = step;
+        }
+        if let Some(speed) = overrides.default_speed {
+            self.playback.default_speed = speed;
+        }
+        if let Some(mode) = overrides.input_mode {
+            self.playback.input_mode = mode;
+        }
+        if let Some(lufs) = overrides.target_lufs {
+            self.playback.target_lufs = lufs;
+        }
+        if let Some(trim) = overrides.trim_silence {
+            self.playback.trim_silence = trim;
+        }
+        if let Some(device) = &overrides.output_device {
+            self.playback.output_device = Some(device.clone());
+        }
+        if let Some(output) = &overrides.output {
+            self.playback.output = output.clone();
+        }
+        if let Some(mins) = overrides.refresh_interval_mins {
+            self.refresh.interval_mins = mins;
+        }
+        if let Some(secs) = overrides.timeout_secs {
+            self.network.timeout_secs = secs;
+        }
+        if let Some(retries) = overrides.retries {
+            self.network.retries = retries;
+        }
+        if let Some(user_agent) = &overrides.user_agent {
+            self.network.user_agent = user_agent.clone();
+        }
+        if let Some(provider) = overrides.directory_provider {
+            self.directory.provider = provider;
+        }
+        if let Some(key) = &overrides.podcastindex_key {
+            self.directory.podcastindex_key = Some(key.clone());
+        }
+        if let Some(secret) = &overrides.podcastindex_secret {
+            self.directory.podcastindex_secret = Some(secret.clone());
+        }
+        if let Some(theme) = overrides.theme {
+            self.ui.theme = theme;
+        }
+        self.paths.expand_tilde();
+    }
+}
+
+fn check(valid: bool, setting: &str, value: impl fmt::Display, expected: &str) -> Result<()> {
+    if !valid {
+        bail!("Invalid {} = {}: must be {}", setting, value, expected);
+    }
+    Ok(())
+}
+
+impl PathsConfig {
+    fn expand_tilde(&mut self) {
+        for path in [
+            &mut self.subscriptions,
+            &mut self.data_dir,
+            &mut self.cache_dir,
+            &mut self.download_dir,
+        ] {
+            *path = expand_tilde(path);
+        }
+    }
+}
+
+fn expand_tilde(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn app_dir(base: Option<PathBuf>) -> PathBuf {
+    base.unwrap_or_else(|| PathBuf::from(".")).join(APP_NAME)
+}
+
+/// `$XDG_CONFIG_HOME/rss_reader/config.toml`
+pub fn default_config_path() -> PathBuf {
+    app_dir(dirs::config_dir()).join(CONFIG_FILE_NAME)
+}
+
+/// The subscription file in the config directory, falling back to a legacy `rss-db.txt`
+/// in the working directory when only that one exists.
+fn default_subscriptions_path() -> PathBuf {
+    let path = app_dir(dirs::config_dir()).join(SUBSCRIPTIONS_FILE_NAME);
+    let legacy = PathBuf::from(SUBSCRIPTIONS_FILE_NAME);
+    if !path.exists() && legacy.exists() {
+        legacy
+    } else {
+        path
+    }
+}
@@ -0,0 +1,220 @@
+//! Podcast directories, searched by name to find the feed URL of a show.
+
+use crate::credentials::redact;
+use crate::http::HttpClient;
+use anyhow::{bail, Context, Result};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const ITUNES_URL: &str = "https://itunes.apple.com";
+pub const PODCAST_INDEX_URL: &str = "https://api.podcastindex.org/api/1.0";
+
+/// A show found in a directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DirectoryResult {
+    pub title: String,
+    pub author: Option<String>,
+    pub feed_url: String,
+    pub description: Option<String>,
+    pub episode_count: Option<u64>,
+}
+
+/// A searchable podcast directory. The TUI searches it from a worker thread.
+pub trait DirectoryProvider: Send + Sync {
+    /// The directory's name, for headings and messages.
+    fn name(&self) -> &'static str;
+
+    /// Shows matching `query`, best match first, at most `limit` of them.
+    /// Shows the directory lists without a feed are left out.
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<DirectoryResult>>;
+}
+
+impl<P: DirectoryProvider + ?Sized> DirectoryProvider for Box<P> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<DirectoryResult>> {
+        (**self).search(query, limit)
+    }
+}
+
+/// Apple's iTunes Search API, which needs no account.
+#[derive(Debug, Clone)]
+pub struct ItunesDirectory {
+    client: HttpClient,
+    base_url: String,
+}
+
+impl ItunesDirectory {
+    pub fn new(client: HttpClient) -> Self {
+        Self::with_base_url(client, ITUNES_URL)
+    }
+
+    /// Searches an API at another address, e.g. a local stand-in.
+    pub fn with_base_url(client: HttpClient, base_url: impl Into<String>) -> Self {
+        Self { client, base_url: base_url.into() }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItunesResponse {
+    #[serde(default)]
+    results: Vec<ItunesShow>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItunesShow {
+    collection_name: Option<String>,
+    artist_name: Option<String>,
+    feed_url: Option<String>,
+    track_count: Option<u64>,
+}
+
+impl DirectoryProvider for ItunesDirectory {
+    fn name(&self) -> &'static str {
+        "iTunes"
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<DirectoryResult>> {
+        let limit = limit.to_string();
+        let url = endpoint(&self.base_url, "search", &[
+            ("media", "podcast"),
+            ("entity", "podcast"),
+            ("term", query),
+            ("limit", &limit),
+        ])?;
+        let response: ItunesResponse = get_json(&self.client, &url, &[])?;
+        Ok(response
+            .results
+            .into_iter()
+            .filter_map(|show| {
+                Some(DirectoryResult {
+                    title: show.collection_name.unwrap_or_default(),
+                    author: show.artist_name,
+                    feed_url: show.feed_url.filter(|url| !url.trim().is_empty())?,
+                    description: None,
+                    episode_count: show.track_count,
+                })
+            })
+            .collect())
+    }
+}
+
+/// The Podcast Index API, which needs a free API key and secret from podcastindex.org.
+#[derive(Debug, Clone)]
+pub struct PodcastIndexDirectory {
+    client: HttpClient,
+    base_url: String,
+    api_key: String,
+    api_secret: String,
+}
+
+impl PodcastIndexDirectory {
+    pub fn new(client: HttpClient, api_key: impl Into<String>, api_secret: impl Into<String>) -> Self {
+        Self::with_base_url(client, PODCAST_INDEX_URL, api_key, api_secret)
+    }
+
+    /// Searches an API at another address, e.g. a local stand-in.
+    pub fn with_base_url(
+        client: HttpClient,
+        base_url: impl Into<String>,
+        api_key: impl Into<String>,
+        api_secret: impl Into<String>,
+    ) -> Self {
+        Self { client, base_url: base_url.into(), api_key: api_key.into(), api_secret: api_secret.into() }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PodcastIndexResponse {
+    #[serde(default)]
+    feeds: Vec<PodcastIndexFeed>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PodcastIndexFeed {
+    title: Option<String>,
+    author: Option<String>,
+    url: Option<String>,
+    description: Option<String>,
+    episode_count: Option<u64>,
+}
+
+impl DirectoryProvider for PodcastIndexDirectory {
+    fn name(&self) -> &'static str {
+        "Podcast Index"
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<DirectoryResult>> {
+        if self.api_key.is_empty() || self.api_secret.is_empty() {
+            bail!("Podcast Index needs an API key and secret; set podcastindex_key and podcastindex_secret under [directory]");
+        }
+        let max = limit.to_string();
+        let url = endpoint(&self.base_url, "search/byterm", &[("q", query), ("max", &max)])?;
+        let date = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs().to_string();
+        let authorization = podcast_index_signature(&self.api_key, &self.api_secret, &date);
+        let headers = [("X-Auth-Key", self.api_key.as_str()), ("X-Auth-Date", &date), ("Authorization", &authorization)];
+
+        let response: PodcastIndexResponse = get_json(&self.client, &url, &headers)?;
+        Ok(response
+            .feeds
+            .into_iter()
+            .filter_map(|feed| {
+                Some(DirectoryResult {
+                    title: feed.title.unwrap_or_default(),
+                    author: feed.author.filter(|author| !author.trim().is_empty()),
+                    feed_url: feed.url.filter(|url| !url.trim().is_empty())?,
+                    description: feed.description.filter(|description| !description.trim().is_empty()),
+                    episode_count: feed.episode_count,
+                })
+            })
+            .take(limit)
+            .collect())
+    }
+}
+
+/// The `Authorization` header Podcast Index expects: the hex SHA-1 of key, secret and date.
+pub fn podcast_index_signature(api_key: &str, api_secret: &str, date: &str) -> String {
+    sha1_smol::Sha1::from(format!("{}{}{}", api_key, api_secret, date)).digest().to_string()
+}
+
+fn endpoint(base_url: &str, path: &str, params: &[(&str, &str)]) -> Result<String> {
+    let url = format!("{}/{}", base_url.trim_end_matches('/'), path);
+    let url = Url::parse_with_params(&url, params).with_context(|| format!("Invalid directory URL {}", redact(base_url)))?;
+    Ok(url.into())
+}
+
+fn get_json<T: for<'de> Deserialize<'de>>(client: &HttpClient, url: &str, headers: &[(&str, &str)]) -> Result<T> {
+    let download = client.get_with_headers(url, headers, client.settings().max_feed_bytes)?;
+    serde_json::from_slice(&download.bytes).with_context(|| format!("Unexpected answer from {}", redact(url)))
+}
+
+pub fn pretty_print_result(result: &DirectoryResult) -> String {
+    let mut details = Vec::new();
+
+    details.push(format!("📻  Podcast: {}", result.title));
+
+    if let Some(author) = &result.author {
+        details.push(format!("👤  Author: {}", author));
+    }
+
+    details.push(format!("📡  Feed: {}", redact(&result.feed_url)));
+
+    if let Some(count) = result.episode_count {
+        details.push(format!("🎧  Episodes: {}", count));
+    }
+
+    if let Some(description) = &result.description {
+        let truncated: String = description.chars().take(200).collect();
+        let ellipsis = if truncated.len() < description.len() { "..." } else { "" };
+        details.push(format!("📝  Description: {}{}", truncated.trim_end(), ellipsis));
+    }
+
+    details.join("\n")
+}
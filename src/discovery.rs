@@ -0,0 +1,125 @@
+use crate::credentials::redact;
+use crate::episodes::parse_feed;
+use crate::http::{Download, HttpClient};
+use anyhow::{bail, Context, Result};
+use log::debug;
+use reqwest::Url;
+use std::collections::HashSet;
+
+/// Paths where sites commonly publish their feed, tried when a page links to none.
+pub const COMMON_FEED_PATHS: &[&str] = &["/feed", "/rss", "/feed.xml", "/rss.xml", "/podcast.xml", "/index.xml"];
+
+/// Link types that announce an RSS feed.
+const FEED_TYPES: &[&str] = &["application/rss+xml", "application/x-rss+xml"];
+
+/// A feed found for a URL the user gave.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedCandidate {
+    pub url: String,
+    /// The feed's own title, or the title the page gave its link.
+    pub title: Option<String>,
+}
+
+/// Finds the feeds behind `url`: the URL itself when it is a feed, otherwise the feeds an HTML
+/// page links to, otherwise the first feed found at one of the `COMMON_FEED_PATHS`.
+pub fn discover_feeds(client: &HttpClient, url: &str) -> Result<Vec<FeedCandidate>> {
+    let download = client.get_feed(url).with_context(|| format!("Failed to fetch {}", redact(url)))?;
+    if let Some(feed) = as_feed(&download) {
+        return Ok(vec![feed]);
+    }
+
+    let html = String::from_utf8_lossy(&download.bytes);
+    let mut seen = HashSet::new();
+    let mut candidates = feed_links(&html, &download.url);
+    candidates.retain(|candidate| seen.insert(candidate.url.clone()));
+    if !candidates.is_empty() {
+        return Ok(candidates);
+    }
+
+    for path in COMMON_FEED_PATHS {
+        let Some(guess) = Url::parse(&download.url).and_then(|base| base.join(path)).ok() else {
+            continue;
+        };
+        debug!("Trying {}", redact(guess.as_str()));
+        if let Some(feed) = client.get_feed(guess.as_str()).ok().as_ref().and_then(as_feed) {
+            return Ok(vec![feed]);
+        }
+    }
+    bail!("No feed found at {}", redact(url))
+}
+
+/// The download as a candidate when it parses as a feed.
+fn as_feed(download: &Download) -> Option<FeedCandidate> {
+    let feed = parse_feed(&download.bytes).ok()?;
+    let url = download.moved_to.clone().unwrap_or_else(|| download.url.clone());
+    Some(FeedCandidate { url, title: feed.title })
+}
+
+/// The RSS feeds an HTML page announces with `<link rel="alternate" type="application/rss+xml">`,
+/// in page order, with relative links resolved against `base_url`.
+pub fn feed_links(html: &str, base_url: &str) -> Vec<FeedCandidate> {
+    let base = Url::parse(base_url).ok();
+    let mut candidates = Vec::new();
+    let lowercase = html.to_ascii_lowercase();
+    let mut rest = 0;
+
+    while let Some(start) = lowercase[rest..].find("<link").map(|i| rest + i) {
+        let end = lowercase[start..].find('>').map_or(html.len(), |i| start + i);
+        rest = end;
+        let attributes = parse_attributes(&html[start + "<link".len()..end]);
+        let attribute = |name: &str| attributes.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+
+        let is_alternate = attribute("rel").is_some_and(|rel| rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("alternate")));
+        let is_feed = attribute("type").is_some_and(|t| FEED_TYPES.iter().any(|feed_type| t.trim().eq_ignore_ascii_case(feed_type)));
+        let Some(href) = attribute("href").filter(|_| is_alternate && is_feed) else {
+            continue;
+        };
+        let url = match &base {
+            Some(base) => base.join(href).map(String::from).unwrap_or_else(|_| href.to_string()),
+            None => href.to_string(),
+        };
+        let title = attribute("title").map(str::trim).filter(|title| !title.is_empty()).map(str::to_string);
+        candidates.push(FeedCandidate { url, title });
+    }
+    candidates
+}
+
+/// Attribute names (lowercased) and values of a tag, with entities in the values decoded.
+fn parse_attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut chars = tag.trim_end_matches('/').chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == '/').is_some() {}
+        let name: String = std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace() && *c != '=')).collect();
+        if name.is_empty() {
+            break;
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let value = if chars.next_if_eq(&'=').is_some() {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.next_if(|c| *c == '"' || *c == '\'') {
+                Some(quote) => {
+                    let value = std::iter::from_fn(|| chars.next_if(|c| *c != quote)).collect();
+                    chars.next();
+                    value
+                }
+                None => std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect(),
+            }
+        } else {
+            String::new()
+        };
+        attributes.push((name.to_ascii_lowercase(), decode_entities(&value)));
+    }
+    attributes
+}
+
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
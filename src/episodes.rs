@@ -1,18 +1,52 @@
-use anyhow::{Context, Result};
+use crate::credentials::redact;
+use crate::feed_source::{FeedSource, HttpSource};
+use crate::subscriptions::{add_subscription, move_subscription, read_subscriptions};
+use anyhow::Result;
+use log::info;
+use rss::extension::syndication::UpdatePeriod;
 use rss::Channel;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-#[derive(Debug, Clone, Default)]
+const HOUR: u64 = 60 * 60;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Episode {
     pub title: String,
     pub link: Option<String>,
     pub description: Option<String>,
     pub pub_date: Option<String>,
+    #[serde(with = "duration_secs")]
     pub duration: Option<Duration>,
     pub audio_url: Option<String>,
+    pub mime_type: Option<String>,
+    /// Size of the enclosure in bytes, as announced by the feed.
+    pub enclosure_length: Option<u64>,
+}
+
+/// (De)serializes an optional `Duration` as whole seconds, so JSON consumers see a plain number.
+pub(crate) mod duration_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration.as_secs()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+    }
 }
 
 impl Episode {
+    /// A copy with the credentials in its audio URL masked, for display and exports.
+    pub fn redacted(&self) -> Self {
+        Self { audio_url: self.audio_url.as_deref().map(redact), ..self.clone() }
+    }
+
     pub fn from_item(item: rss::Item) -> Option<Self> {
         let title = item.title()?;
         let enclosure = item.enclosure()?;
@@ -30,12 +64,18 @@ impl Episode {
             title: title.to_string(),
             audio_url,
             duration,
+            mime_type: Some(enclosure.mime_type.to_string()).filter(|m| !m.is_empty()),
+            enclosure_length: enclosure.length.trim().parse().ok().filter(|&l| l > 0),
             link: item.link().map(|s| s.to_string()),
             pub_date: item.pub_date().map(|s| s.to_string()),
             description: item.description().map(|s| s.to_string()),
         })
     }
 
+    /// Tells episodes apart across refreshes: by audio URL, or by title when they have none.
+    pub fn key(&self) -> String {
+        self.audio_url.clone().unwrap_or_else(|| self.title.clone())
+    }
 }
 
 pub fn pretty_print(episode: &Episode) -> String {
@@ -52,14 +92,11 @@ pub fn pretty_print(episode: &Episode) -> String {
     }
     
     if let Some(duration) = episode.duration {
-        let hours = duration.as_secs() / 3600;
-        let minutes = (duration.as_secs() % 3600) / 60;
-        let seconds = duration.as_secs() % 60;
-        details.push(format!("⏱️  Duration: {:02}:{:02}:{:02}", hours, minutes, seconds));
+        details.push(format!("⏱️  Duration: {}", format_duration(duration)));
     }
     
     if let Some(audio_url) = &episode.audio_url {
-        details.push(format!("🎧  Audio URL: {}", audio_url));
+        details.push(format!("🎧  Audio URL: {}", redact(audio_url)));
     }
     
     if let Some(description) = &episode.description {
@@ -75,37 +112,96 @@ pub fn pretty_print(episode: &Episode) -> String {
     details.join("\n")
 }
 
+/// Formats a duration as `HH:MM:SS`.
+pub fn format_duration(duration: Duration) -> String {
+    let hours = duration.as_secs() / 3600;
+    let minutes = (duration.as_secs() % 3600) / 60;
+    let seconds = duration.as_secs() % 60;
+    format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+}
+
+/// Appends a feed to the subscriptions file, creating the file when needed.
+/// Returns false, leaving the file alone, when the feed is already listed.
+pub fn add_rss_feed(filename: &str, feed_url: &str) -> Result<bool> {
+    add_subscription(filename, feed_url)
+}
+
+/// The URLs of the subscribed feeds, leaving out disabled ones.
 pub fn read_rss_feeds(filename: &str) -> Result<Vec<String>> {
-    let content = std::fs::read_to_string(filename)?;
-    Ok(content
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .filter(|line| !line.trim_start().starts_with('#'))
-        .filter(|line| !line.trim_start().starts_with("//"))
-        .filter(|line| !line.trim_start().starts_with("--"))
-        .map(|line| line.trim().to_string())
+    Ok(read_subscriptions(filename)?
+        .into_iter()
+        .filter(|subscription| !subscription.settings.disabled)
+        .map(|subscription| subscription.url)
         .collect())
 }
 
-pub fn fetch_episodes(feed_url: &str) -> Result<Vec<Episode>> {
-    let client = reqwest::blocking::Client::new();
-    
-    let content = client.get(feed_url)
-        .send()
-        .with_context(|| format!("Failed to fetch RSS feed from {}", feed_url))?
-        .bytes()
-        .context("Failed to read RSS feed content")?;
-    
-    let channel = Channel::read_from(&content[..])
-        .with_context(|| format!("Failed to parse RSS feed from {}", feed_url))?;
-    
+/// Points a listed feed at the URL it moved to, keeping its settings and its place in the
+/// file. Returns false, leaving the file alone, when the old URL is not listed or is the same
+/// feed as the new one.
+pub fn move_rss_feed(filename: &str, from: &str, to: &str) -> Result<bool> {
+    move_subscription(filename, from, to)
+}
+
+/// Fetches the episodes of a feed over HTTP, on the caller's source so its connections
+/// are reused across feeds.
+pub fn fetch_episodes(source: &HttpSource, feed_url: &str) -> Result<Vec<Episode>> {
+    source.episodes(feed_url)
+}
+
+/// What an RSS document says about its episodes and itself.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedFeed {
+    /// The channel title, when it has one.
+    pub title: Option<String>,
+    pub episodes: Vec<Episode>,
+    /// `itunes:new-feed-url`: the publisher moved the feed there.
+    pub new_feed_url: Option<String>,
+    /// How often the publisher says the feed changes, from `<ttl>` or `sy:updatePeriod`,
+    /// the longer one when it gives both.
+    pub refresh_hint: Option<Duration>,
+}
+
+/// Parses an RSS document.
+pub fn parse_feed(content: &[u8]) -> Result<ParsedFeed> {
+    let channel = Channel::read_from(content)?;
+
     let episodes: Vec<Episode> = channel.items()
         .iter()
         .filter_map(|item| Episode::from_item(item.clone()))
         .collect();
-    
-    println!("Found {} episodes", episodes.len());
-    Ok(episodes)
+    let new_feed_url = channel.itunes_ext()
+        .and_then(|itunes| itunes.new_feed_url())
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+
+    let title = Some(channel.title().trim().to_string()).filter(|title| !title.is_empty());
+
+    info!("Found {} episodes", episodes.len());
+    Ok(ParsedFeed { title, episodes, new_feed_url, refresh_hint: refresh_hint(&channel) })
+}
+
+fn refresh_hint(channel: &Channel) -> Option<Duration> {
+    let ttl = channel.ttl()
+        .and_then(|mins| mins.trim().parse::<u64>().ok())
+        .filter(|&mins| mins > 0)
+        .map(|mins| Duration::from_secs(mins * 60));
+    // updateFrequency counts the updates in each period
+    let update_period = channel.syndication_ext().map(|sy| {
+        let period = match sy.period() {
+            UpdatePeriod::Hourly => HOUR,
+            UpdatePeriod::Daily => 24 * HOUR,
+            UpdatePeriod::Weekly => 7 * 24 * HOUR,
+            UpdatePeriod::Monthly => 30 * 24 * HOUR,
+            UpdatePeriod::Yearly => 365 * 24 * HOUR,
+        };
+        Duration::from_secs(period / u64::from(sy.frequency().max(1)))
+    });
+    ttl.max(update_period)
+}
+
+/// Parses the episodes out of an RSS document.
+pub fn parse_episodes(content: &[u8]) -> Result<Vec<Episode>> {
+    Ok(parse_feed(content)?.episodes)
 }
 
 pub fn parse_duration(duration_str: &str) -> Option<Duration> {
@@ -131,7 +227,8 @@ fn parse_hhmmss(duration_str: &str) -> Option<Duration> {
         let hours = parts[0].parse::<u64>().ok()?;
         let minutes = parts[1].parse::<u64>().ok()?;
         let seconds = parts[2].parse::<u64>().ok()?;
-        Some(Duration::from_secs(hours * 3600 + minutes * 60 + seconds))
+        let seconds = hours.checked_mul(3600)?.checked_add(minutes.checked_mul(60)?)?.checked_add(seconds)?;
+        Some(Duration::from_secs(seconds))
     } else {
         None
     }
@@ -142,7 +239,7 @@ fn parse_mmss(duration_str: &str) -> Option<Duration> {
     if parts.len() == 2 {
         let minutes = parts[0].parse::<u64>().ok()?;
         let seconds = parts[1].parse::<u64>().ok()?;
-        Some(Duration::from_secs(minutes * 60 + seconds))
+        Some(Duration::from_secs(minutes.checked_mul(60)?.checked_add(seconds)?))
     } else {
         None
     }
@@ -0,0 +1,193 @@
+use crate::credentials::{inherit_credentials, redact};
+use crate::episodes::{parse_feed, Episode, ParsedFeed};
+use crate::http::HttpClient;
+use crate::subscriptions::normalize_feed_url;
+use anyhow::{anyhow, Context, Result};
+use log::warn;
+use reqwest::Url;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// A feed document as a source delivered it.
+#[derive(Debug, Clone, Default)]
+pub struct FeedDocument {
+    pub content: Vec<u8>,
+    /// Where the feed lives now, when the source was sent there for good, e.g. by a 301.
+    pub moved_to: Option<String>,
+}
+
+impl From<Vec<u8>> for FeedDocument {
+    fn from(content: Vec<u8>) -> Self {
+        Self { content, moved_to: None }
+    }
+}
+
+/// The title and episodes of a feed, and its new home if it moved.
+#[derive(Debug, Clone, Default)]
+pub struct Feed {
+    /// The channel title, when it has one.
+    pub title: Option<String>,
+    pub episodes: Vec<Episode>,
+    /// From a permanent redirect, or from `itunes:new-feed-url`, which wins when both are there.
+    pub moved_to: Option<String>,
+    /// How often the publisher says the feed changes, from `<ttl>` or `sy:updatePeriod`.
+    pub refresh_hint: Option<Duration>,
+}
+
+/// Where feed documents come from. The store and refresh logic only go through this trait,
+/// so embedders can plug in their own transport or put a cache in front of another source.
+pub trait FeedSource {
+    /// The raw feed document at `url`.
+    fn fetch(&self, url: &str) -> Result<FeedDocument>;
+
+    /// The parsed feed at `url`. Enclosures on the feed's host get its login. A feed only
+    /// counts as moved to an `itunes:new-feed-url` that could be fetched and parsed.
+    fn feed(&self, url: &str) -> Result<Feed> {
+        let (parsed, fetched_from) = fetch_parsed(self, url)?;
+        let announced = parsed.new_feed_url
+            .filter(|new_url| is_web_url(new_url) && !same_feed(new_url, &fetched_from) && !same_feed(new_url, url))
+            .map(|new_url| inherit_credentials(&fetched_from, &new_url));
+        if let Some(announced) = announced {
+            match fetch_parsed(self, &announced) {
+                Ok((parsed, new_home)) => {
+                    let episodes = with_credentials(parsed.episodes, &new_home);
+                    let (title, refresh_hint) = (parsed.title, parsed.refresh_hint);
+                    return Ok(Feed { title, episodes, moved_to: Some(new_home), refresh_hint });
+                }
+                Err(e) => warn!("Not following {} to {}: {:#}", redact(url), redact(&announced), e),
+            }
+        }
+
+        let moved_to = (!same_feed(&fetched_from, url)).then(|| fetched_from.clone());
+        let episodes = with_credentials(parsed.episodes, &fetched_from);
+        Ok(Feed { title: parsed.title, episodes, moved_to, refresh_hint: parsed.refresh_hint })
+    }
+
+    /// The episodes of the feed at `url`, as served there: an announced move is not followed.
+    fn episodes(&self, url: &str) -> Result<Vec<Episode>> {
+        let (parsed, fetched_from) = fetch_parsed(self, url)?;
+        Ok(with_credentials(parsed.episodes, &fetched_from))
+    }
+}
+
+impl<S: FeedSource + ?Sized> FeedSource for &S {
+    fn fetch(&self, url: &str) -> Result<FeedDocument> {
+        (**self).fetch(url)
+    }
+}
+
+impl<S: FeedSource + ?Sized> FeedSource for Box<S> {
+    fn fetch(&self, url: &str) -> Result<FeedDocument> {
+        (**self).fetch(url)
+    }
+}
+
+/// Fetches and parses the feed at `url`, along with where it was fetched from in the end.
+fn fetch_parsed<S: FeedSource + ?Sized>(source: &S, url: &str) -> Result<(ParsedFeed, String)> {
+    let document = source.fetch(url)?;
+    let parsed = parse_feed(&document.content).with_context(|| format!("Failed to parse RSS feed from {}", redact(url)))?;
+    Ok((parsed, document.moved_to.unwrap_or_else(|| url.to_string())))
+}
+
+fn with_credentials(mut episodes: Vec<Episode>, feed_url: &str) -> Vec<Episode> {
+    for audio_url in episodes.iter_mut().filter_map(|episode| episode.audio_url.as_mut()) {
+        *audio_url = inherit_credentials(feed_url, audio_url);
+    }
+    episodes
+}
+
+/// Whether two URLs are the same feed: equal once normalized, whether over HTTP or HTTPS,
+/// so a redirect from one to the other does not count as a move.
+pub fn same_feed(a: &str, b: &str) -> bool {
+    let key = |url: &str| {
+        let url = normalize_feed_url(url).unwrap_or_else(|_| url.trim().to_string());
+        url.split_once("://").map_or(url.clone(), |(_, rest)| rest.to_string())
+    };
+    key(a) == key(b)
+}
+
+fn is_web_url(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Fetches feeds over HTTP(S) with one client, so connections are reused across feeds.
+#[derive(Debug, Clone, Default)]
+pub struct HttpSource {
+    client: HttpClient,
+}
+
+impl HttpSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetches with a client configured by the caller.
+    pub fn with_client(client: HttpClient) -> Self {
+        Self { client }
+    }
+
+    /// The client, for sharing with other downloads such as episode audio.
+    pub fn client(&self) -> &HttpClient {
+        &self.client
+    }
+}
+
+impl FeedSource for HttpSource {
+    fn fetch(&self, url: &str) -> Result<FeedDocument> {
+        let download = self.client.get_feed(url).with_context(|| format!("Failed to fetch RSS feed from {}", redact(url)))?;
+        Ok(FeedDocument { content: download.bytes, moved_to: download.moved_to })
+    }
+}
+
+/// Reads feeds from local files, given as paths or `file://` URLs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSource;
+
+impl FeedSource for FileSource {
+    fn fetch(&self, url: &str) -> Result<FeedDocument> {
+        let path = Path::new(url.strip_prefix("file://").unwrap_or(url));
+        let content = std::fs::read(path).with_context(|| format!("Failed to read RSS feed from {}", path.display()))?;
+        Ok(content.into())
+    }
+}
+
+/// Serves feeds held in memory, e.g. as test fixtures.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    feeds: HashMap<String, Vec<u8>>,
+    moves: HashMap<String, String>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `content` at `url`, replacing what was there.
+    pub fn insert(&mut self, url: impl Into<String>, content: impl Into<Vec<u8>>) {
+        self.feeds.insert(url.into(), content.into());
+    }
+
+    pub fn with_feed(mut self, url: impl Into<String>, content: impl Into<Vec<u8>>) -> Self {
+        self.insert(url, content);
+        self
+    }
+
+    /// Answers requests for `from` with the feed at `to`, like a permanent redirect.
+    pub fn with_move(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.moves.insert(from.into(), to.into());
+        self
+    }
+}
+
+impl FeedSource for MemorySource {
+    fn fetch(&self, url: &str) -> Result<FeedDocument> {
+        let (url, moved_to) = match self.moves.get(url) {
+            Some(to) => (to.as_str(), Some(to.clone())),
+            None => (url, None),
+        };
+        let content = self.feeds.get(url).cloned().ok_or_else(|| anyhow!("No feed at {}", url))?;
+        Ok(FeedDocument { content, moved_to })
+    }
+}
@@ -0,0 +1,532 @@
+use anyhow::{anyhow, bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use crate::config::{KeyList, KeysConfig};
+
+/// A single key press with its modifiers, e.g. `ctrl+c` or `left`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyPress {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+/// A sequence of key presses that triggers an action, e.g. `g g`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord(pub Vec<KeyPress>);
+
+impl KeyPress {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of a character's case, so `shift+g` and `G` are the same key.
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let (modifier_names, key_name) = match spec.strip_suffix("++") {
+            Some(prefix) => (prefix, "+"),
+            None if spec == "+" => ("", "+"),
+            None => spec.rsplit_once('+').unwrap_or(("", spec)),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => bail!("Unknown modifier '{}' in key '{}'", name, spec),
+            };
+        }
+
+        let code = match key_name.to_ascii_lowercase().as_str() {
+            "" => bail!("Missing key in '{}'", spec),
+            "space" => KeyCode::Char(' '),
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name if name.len() > 1 && name.starts_with('f') => {
+                let n: u8 = name[1..].parse().map_err(|_| anyhow!("Unknown key '{}'", key_name))?;
+                KeyCode::F(n)
+            }
+            _ => {
+                let mut chars = key_name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                        KeyCode::Char(c.to_ascii_uppercase())
+                    }
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => bail!("Unknown key '{}'", key_name),
+                }
+            }
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for KeyPress {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Delete => f.write_str("delete"),
+            KeyCode::Insert => f.write_str("insert"),
+            KeyCode::Left => f.write_str("left"),
+            KeyCode::Right => f.write_str("right"),
+            KeyCode::Up => f.write_str("up"),
+            KeyCode::Down => f.write_str("down"),
+            KeyCode::Home => f.write_str("home"),
+            KeyCode::End => f.write_str("end"),
+            KeyCode::PageUp => f.write_str("pageup"),
+            KeyCode::PageDown => f.write_str("pagedown"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+impl KeyChord {
+    /// Parses whitespace separated key presses, e.g. `ctrl+x s` or `g g`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let keys = spec
+            .split_whitespace()
+            .map(KeyPress::parse)
+            .collect::<Result<Vec<_>>>()?;
+        if keys.is_empty() {
+            bail!("Empty key binding");
+        }
+        Ok(Self(keys))
+    }
+
+    /// The text a user types at the line prompt to trigger this chord, if it can be typed at all.
+    /// Chords with modifiers or special keys, e.g. `ctrl+x` or `left`, cannot: they only work
+    /// where single key presses are read, as in the TUI.
+    pub fn as_line_input(&self) -> Option<String> {
+        self.0
+            .iter()
+            .map(|key| match key.code {
+                KeyCode::Char(c) if key.modifiers.is_empty() && c != ' ' => Some(c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn starts_with(&self, prefix: &[KeyPress]) -> bool {
+        self.0.starts_with(prefix)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys: Vec<String> = self.0.iter().map(|key| key.to_string()).collect();
+        f.write_str(&keys.join(" "))
+    }
+}
+
+/// Something a key binding can trigger within one part of the application.
+pub trait Action: Copy + Eq + Hash + fmt::Debug + 'static {
+    /// Config section the bindings are read from, e.g. `keys.player`.
+    const SECTION: &'static str;
+    const ALL: &'static [Self];
+
+    /// Name used for the action in the config file.
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn default_keys(&self) -> &'static [&'static str];
+}
+
+/// Actions available while an episode is playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayerAction {
+    Pause,
+    SkipForward,
+    SkipBackward,
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    Sleep,
+    CancelSleep,
+    Help,
+    Quit,
+}
+
+impl Action for PlayerAction {
+    const SECTION: &'static str = "keys.player";
+    const ALL: &'static [Self] = &[
+        PlayerAction::Pause,
+        PlayerAction::SkipForward,
+        PlayerAction::SkipBackward,
+        PlayerAction::VolumeUp,
+        PlayerAction::VolumeDown,
+        PlayerAction::Mute,
+        PlayerAction::Sleep,
+        PlayerAction::CancelSleep,
+        PlayerAction::Help,
+        PlayerAction::Quit,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            PlayerAction::Pause => "pause",
+            PlayerAction::SkipForward => "skip_forward",
+            PlayerAction::SkipBackward => "skip_backward",
+            PlayerAction::VolumeUp => "volume_up",
+            PlayerAction::VolumeDown => "volume_down",
+            PlayerAction::Mute => "mute",
+            PlayerAction::Sleep => "sleep",
+            PlayerAction::CancelSleep => "cancel_sleep",
+            PlayerAction::Help => "help",
+            PlayerAction::Quit => "quit",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            PlayerAction::Pause => "Pause or resume playback",
+            PlayerAction::SkipForward => "Skip forward",
+            PlayerAction::SkipBackward => "Skip backward",
+            PlayerAction::VolumeUp => "Increase volume",
+            PlayerAction::VolumeDown => "Decrease volume",
+            PlayerAction::Mute => "Mute or unmute",
+            PlayerAction::Sleep => "Start or extend the sleep timer by 15 minutes",
+            PlayerAction::CancelSleep => "Cancel the sleep timer",
+            PlayerAction::Help => "Show this help",
+            PlayerAction::Quit => "Stop playback and quit",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            PlayerAction::Pause => &["p", "space"],
+            PlayerAction::SkipForward => &["f", "right"],
+            PlayerAction::SkipBackward => &["b", "left"],
+            PlayerAction::VolumeUp => &["+", "up"],
+            PlayerAction::VolumeDown => &["-", "down"],
+            PlayerAction::Mute => &["m"],
+            PlayerAction::Sleep => &["z"],
+            PlayerAction::CancelSleep => &["Z"],
+            PlayerAction::Help => &["h", "?"],
+            PlayerAction::Quit => &["q", "ctrl+c"],
+        }
+    }
+}
+
+/// Actions available in the podcast browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TuiAction {
+    Up,
+    Down,
+    Top,
+    Bottom,
+    SwitchPane,
+    Select,
+    PlayPause,
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    Sleep,
+    CancelSleep,
+    AddPodcast,
+    Help,
+    Quit,
+}
+
+impl Action for TuiAction {
+    const SECTION: &'static str = "keys.tui";
+    const ALL: &'static [Self] = &[
+        TuiAction::Up,
+        TuiAction::Down,
+        TuiAction::Top,
+        TuiAction::Bottom,
+        TuiAction::SwitchPane,
+        TuiAction::Select,
+        TuiAction::PlayPause,
+        TuiAction::VolumeUp,
+        TuiAction::VolumeDown,
+        TuiAction::Mute,
+        TuiAction::Sleep,
+        TuiAction::CancelSleep,
+        TuiAction::AddPodcast,
+        TuiAction::Help,
+        TuiAction::Quit,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            TuiAction::Up => "up",
+            TuiAction::Down => "down",
+            TuiAction::Top => "top",
+            TuiAction::Bottom => "bottom",
+            TuiAction::SwitchPane => "switch_pane",
+            TuiAction::Select => "select",
+            TuiAction::PlayPause => "play_pause",
+            TuiAction::VolumeUp => "volume_up",
+            TuiAction::VolumeDown => "volume_down",
+            TuiAction::Mute => "mute",
+            TuiAction::Sleep => "sleep",
+            TuiAction::CancelSleep => "cancel_sleep",
+            TuiAction::AddPodcast => "add_podcast",
+            TuiAction::Help => "help",
+            TuiAction::Quit => "quit",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            TuiAction::Up => "Move selection up",
+            TuiAction::Down => "Move selection down",
+            TuiAction::Top => "Jump to the first item",
+            TuiAction::Bottom => "Jump to the last item",
+            TuiAction::SwitchPane => "Switch between podcasts and episodes",
+            TuiAction::Select => "Open the podcast or play the episode",
+            TuiAction::PlayPause => "Pause or resume playback",
+            TuiAction::VolumeUp => "Increase volume",
+            TuiAction::VolumeDown => "Decrease volume",
+            TuiAction::Mute => "Mute or unmute",
+            TuiAction::Sleep => "Start or extend the sleep timer by 15 minutes",
+            TuiAction::CancelSleep => "Cancel the sleep timer",
+            TuiAction::AddPodcast => "Search the podcast directory and subscribe",
+            TuiAction::Help => "Toggle this help",
+            TuiAction::Quit => "Quit",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            TuiAction::Up => &["up", "k"],
+            TuiAction::Down => &["down", "j"],
+            TuiAction::Top => &["g g", "home"],
+            TuiAction::Bottom => &["G", "end"],
+            TuiAction::SwitchPane => &["tab"],
+            TuiAction::Select => &["enter"],
+            TuiAction::PlayPause => &["space"],
+            TuiAction::VolumeUp => &["+"],
+            TuiAction::VolumeDown => &["-"],
+            TuiAction::Mute => &["m"],
+            TuiAction::Sleep => &["z"],
+            TuiAction::CancelSleep => &["Z"],
+            TuiAction::AddPodcast => &["a"],
+            TuiAction::Help => &["?"],
+            TuiAction::Quit => &["q", "ctrl+c"],
+        }
+    }
+}
+
+/// Validated key bindings for one set of actions.
+#[derive(Debug, Clone)]
+pub struct Bindings<A: Action> {
+    entries: Vec<(KeyChord, A)>,
+}
+
+impl<A: Action> Default for Bindings<A> {
+    fn default() -> Self {
+        Self::from_config(&HashMap::new()).expect("default key bindings must be valid")
+    }
+}
+
+impl<A: Action> Bindings<A> {
+    /// Builds bindings from the defaults, replacing the keys of every action named in `overrides`.
+    ///
+    /// Fails on unknown action names, unparsable keys and conflicting bindings.
+    pub fn from_config(overrides: &HashMap<String, KeyList>) -> Result<Self> {
+        if let Some(unknown) = overrides.keys().find(|name| !A::ALL.iter().any(|a| a.name() == name.as_str())) {
+            let known: Vec<&str> = A::ALL.iter().map(|a| a.name()).collect();
+            bail!("Unknown action '{}' in [{}] (expected one of: {})", unknown, A::SECTION, known.join(", "));
+        }
+
+        let mut entries = Vec::new();
+        for &action in A::ALL {
+            let specs: Vec<&str> = match overrides.get(action.name()) {
+                Some(keys) => keys.as_slice().iter().map(String::as_str).collect(),
+                None => action.default_keys().to_vec(),
+            };
+            for spec in specs {
+                let chord = KeyChord::parse(spec)
+                    .map_err(|e| anyhow!("Invalid key '{}' for {} in [{}]: {}", spec, action.name(), A::SECTION, e))?;
+                entries.push((chord, action));
+            }
+        }
+
+        let bindings = Self { entries };
+        bindings.validate()?;
+        Ok(bindings)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (i, (chord, action)) in self.entries.iter().enumerate() {
+            for (other_chord, other_action) in &self.entries[i + 1..] {
+                if chord == other_chord {
+                    if action == other_action {
+                        continue;
+                    }
+                    bail!(
+                        "Key binding conflict in [{}]: '{}' is bound to both {} and {}",
+                        A::SECTION, chord, action.name(), other_action.name()
+                    );
+                }
+                let (short, long) = if chord.0.len() < other_chord.0.len() {
+                    ((chord, action), (other_chord, other_action))
+                } else {
+                    ((other_chord, other_action), (chord, action))
+                };
+                if long.0.starts_with(&short.0 .0) {
+                    bail!(
+                        "Key binding conflict in [{}]: '{}' ({}) shadows '{}' ({})",
+                        A::SECTION, short.0, short.1.name(), long.0, long.1.name()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn action_for(&self, chord: &KeyChord) -> Option<A> {
+        self.entries.iter().find(|(c, _)| c == chord).map(|(_, action)| *action)
+    }
+
+    /// Looks up the action for text typed at the line prompt.
+    pub fn action_for_line(&self, input: &str) -> Option<A> {
+        let input = input.trim();
+        self.entries
+            .iter()
+            .find(|(chord, _)| chord.as_line_input().as_deref() == Some(input))
+            .map(|(_, action)| *action)
+    }
+
+    pub fn keys_for(&self, action: A) -> Vec<&KeyChord> {
+        self.entries.iter().filter(|(_, a)| *a == action).map(|(chord, _)| chord).collect()
+    }
+
+    fn is_prefix(&self, keys: &[KeyPress]) -> bool {
+        self.entries.iter().any(|(chord, _)| chord.0.len() > keys.len() && chord.starts_with(keys))
+    }
+
+    /// One `(keys, description)` pair per action, in declaration order.
+    pub fn help_lines(&self) -> Vec<(String, &'static str)> {
+        self.help_lines_where(|_| true)
+    }
+
+    fn help_lines_where(&self, include: impl Fn(&KeyChord) -> bool) -> Vec<(String, &'static str)> {
+        A::ALL
+            .iter()
+            .filter_map(|&action| {
+                let keys: Vec<String> = self
+                    .keys_for(action)
+                    .into_iter()
+                    .filter(|chord| include(chord))
+                    .map(|chord| chord.to_string())
+                    .collect();
+                (!keys.is_empty()).then(|| (keys.join(", "), action.description()))
+            })
+            .collect()
+    }
+
+    /// Formats the help lines as an aligned text table.
+    pub fn help_text(&self) -> String {
+        format_help(self.help_lines())
+    }
+
+    /// The help for the line prompt, leaving out the chords that cannot be typed there.
+    pub fn line_help_text(&self) -> String {
+        format_help(self.help_lines_where(|chord| chord.as_line_input().is_some()))
+    }
+}
+
+fn format_help(lines: Vec<(String, &'static str)>) -> String {
+    let width = lines.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+    lines
+        .iter()
+        .map(|(keys, description)| format!("  {:<width$}  {}", keys, description, width = width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Turns a stream of key presses into actions, buffering the keys of a chord in progress.
+#[derive(Debug, Default)]
+pub struct ChordMatcher {
+    pending: Vec<KeyPress>,
+}
+
+impl ChordMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed<A: Action>(&mut self, key: KeyPress, bindings: &Bindings<A>) -> Option<A> {
+        self.pending.push(key);
+        if let Some(action) = bindings.action_for(&KeyChord(self.pending.clone())) {
+            self.pending.clear();
+            return Some(action);
+        }
+        if bindings.is_prefix(&self.pending) {
+            return None;
+        }
+
+        // The chord in progress went nowhere; give the latest key a fresh start.
+        self.pending = vec![key];
+        if let Some(action) = bindings.action_for(&KeyChord(self.pending.clone())) {
+            self.pending.clear();
+            return Some(action);
+        }
+        if !bindings.is_prefix(&self.pending) {
+            self.pending.clear();
+        }
+        None
+    }
+
+    pub fn pending(&self) -> &[KeyPress] {
+        &self.pending
+    }
+}
+
+/// Bindings for every part of the application, validated at startup.
+#[derive(Debug, Clone, Default)]
+pub struct KeyBindings {
+    pub player: Bindings<PlayerAction>,
+    pub tui: Bindings<TuiAction>,
+}
+
+impl KeyBindings {
+    pub fn from_config(config: &KeysConfig) -> Result<Self> {
+        Ok(Self {
+            player: Bindings::from_config(&config.player)?,
+            tui: Bindings::from_config(&config.tui)?,
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::sync::Once;
+use std::time::Duration;
+use crate::audio_player::PlayerCommand;
+use crate::key_bindings::{ChordMatcher, KeyPress, PlayerAction};
+use crate::keyboard_controls::KeyboardControls;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Keeps the terminal in raw mode for as long as it is alive.
+///
+/// The terminal is restored when the guard is dropped, including while unwinding from a
+/// panic; a panic hook additionally restores it before the panic message is printed.
+struct RawModeGuard {
+    _private: (),
+}
+
+impl RawModeGuard {
+    fn enable() -> Result<Self> {
+        install_panic_hook();
+        enable_raw_mode()?;
+        Ok(Self { _private: () })
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+    }
+}
+
+fn install_panic_hook() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let _ = disable_raw_mode();
+            previous(info);
+        }));
+    });
+}
+
+/// Raw mode is only held while waiting for a key, so everything the player prints in
+/// between still gets normal line endings.
+fn read_key() -> Option<KeyEvent> {
+    let _raw_mode = RawModeGuard::enable();
+    match event::poll(POLL_INTERVAL) {
+        Ok(true) => match event::read() {
+            Ok(Event::Key(key)) => Some(key),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Turns single key presses into player commands, without waiting for Enter.
+pub struct KeyEventInput<'a> {
+    controls: &'a KeyboardControls,
+    chords: ChordMatcher,
+}
+
+impl<'a> KeyEventInput<'a> {
+    pub fn new(controls: &'a KeyboardControls) -> Self {
+        Self {
+            controls,
+            chords: ChordMatcher::new(),
+        }
+    }
+
+    /// Waits briefly for a key press; returns `Ignore` when none arrived so callers can keep ticking.
+    pub fn next_command(&mut self) -> PlayerCommand {
+        match read_key() {
+            Some(key) => self.handle_key(key),
+            None => PlayerCommand::Ignore,
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PlayerCommand {
+        if key.kind == KeyEventKind::Release {
+            return PlayerCommand::Ignore;
+        }
+        match self.chords.feed(KeyPress::from(key), self.controls.bindings()) {
+            Some(PlayerAction::Help) => {
+                self.controls.print_key_help();
+                PlayerCommand::Ignore
+            }
+            Some(action) => self.controls.command_for(action),
+            None => PlayerCommand::Ignore,
+        }
+    }
+}
@@ -1,24 +1,19 @@
-use log::{debug, info};
-use std::collections::HashMap;
+use anyhow::{anyhow, Result};
+use log::debug;
 use std::io::BufRead;
 use std::time::{Duration, Instant};
 use crate::audio_player::PlayerCommand;
+use crate::command_parser::{parse_time, parse_volume_step, split_command, Keyword, ARGUMENT_EXAMPLES};
+use crate::config::PlaybackConfig;
+use crate::key_bindings::{Action, Bindings, PlayerAction};
+use crate::sleep_timer::DEFAULT_SLEEP_STEP;
 
 const COOLDOWN: Duration = Duration::from_millis(250);
 pub const VOLUME_STEP: f32 = 0.1;
 pub const SKIP_SECONDS: i64 = 10;
 
 lazy_static::lazy_static! {
-    static ref COMMAND_MAP: HashMap<&'static str, PlayerCommand> = {
-        let mut map = HashMap::new();
-        map.insert("p", PlayerCommand::Pause);
-        map.insert("q", PlayerCommand::Quit);
-        map.insert("+", PlayerCommand::VolumeUp(VOLUME_STEP));
-        map.insert("-", PlayerCommand::VolumeDown(VOLUME_STEP));
-        map.insert("f", PlayerCommand::SkipForward(SKIP_SECONDS));
-        map.insert("b", PlayerCommand::SkipBackward(SKIP_SECONDS));
-        map
-    };
+    static ref DEFAULT_CONTROLS: KeyboardControls = KeyboardControls::new();
 }
 
 pub trait Cooldown {
@@ -49,15 +44,85 @@ impl CooldownHandler {
     }
 }
 
+impl Default for CooldownHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Clone)]
-pub struct KeyboardControls;
+pub struct KeyboardControls {
+    bindings: Bindings<PlayerAction>,
+    volume_step: f32,
+    skip_forward: i64,
+    skip_backward: i64,
+    cooldown: Duration,
+}
+
+impl Default for KeyboardControls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl KeyboardControls {
-    pub fn new() -> Self { Self { } }
+    pub fn new() -> Self {
+        Self {
+            bindings: Bindings::default(),
+            volume_step: VOLUME_STEP,
+            skip_forward: SKIP_SECONDS,
+            skip_backward: SKIP_SECONDS,
+            cooldown: COOLDOWN,
+        }
+    }
+
+    /// Builds controls with the default key bindings and the skip, volume and cooldown values of the playback config.
+    pub fn from_config(config: &PlaybackConfig) -> Self {
+        Self::with_bindings(Bindings::default(), config)
+    }
+
+    pub fn with_bindings(bindings: Bindings<PlayerAction>, config: &PlaybackConfig) -> Self {
+        Self {
+            bindings,
+            volume_step: config.volume_step,
+            skip_forward: config.skip_forward_secs,
+            skip_backward: config.skip_backward_secs,
+            cooldown: config.input_cooldown(),
+        }
+    }
+
+    pub fn bindings(&self) -> &Bindings<PlayerAction> {
+        &self.bindings
+    }
+
+    /// The `PlayerCommand` an action stands for; `Help` is handled by the controls themselves.
+    pub fn command_for(&self, action: PlayerAction) -> PlayerCommand {
+        match action {
+            PlayerAction::Pause => PlayerCommand::Pause,
+            PlayerAction::SkipForward => PlayerCommand::SkipForward(self.skip_forward),
+            PlayerAction::SkipBackward => PlayerCommand::SkipBackward(self.skip_backward),
+            PlayerAction::VolumeUp => PlayerCommand::VolumeUp(self.volume_step),
+            PlayerAction::VolumeDown => PlayerCommand::VolumeDown(self.volume_step),
+            PlayerAction::Mute => PlayerCommand::ToggleMute,
+            PlayerAction::Sleep => PlayerCommand::ExtendSleep(DEFAULT_SLEEP_STEP),
+            PlayerAction::CancelSleep => PlayerCommand::CancelSleep,
+            PlayerAction::Quit => PlayerCommand::Quit,
+            PlayerAction::Help => PlayerCommand::Ignore,
+        }
+    }
 
+    /// Reads a command with the default key bindings and steps.
     pub fn get_user_input<T: Cooldown, R: BufRead>(
         cooldown_handler: &mut T,
         reader: &mut R,
+    ) -> PlayerCommand {
+        DEFAULT_CONTROLS.read_command(cooldown_handler, reader)
+    }
+
+    pub fn read_command<T: Cooldown, R: BufRead>(
+        &self,
+        cooldown_handler: &mut T,
+        reader: &mut R,
     ) -> PlayerCommand {
         // Read user input safely
         let input = match Self::read_input(reader) {
@@ -70,13 +135,24 @@ impl KeyboardControls {
             return PlayerCommand::Ignore;
         }
 
-        // Translate input into a command
-        let command = Self::translate_command(&input);
+        if self.bindings.action_for_line(&input) == Some(PlayerAction::Help) {
+            self.print_help();
+            return PlayerCommand::Ignore;
+        }
+
+        // Translate input into a command, reporting anything we cannot make sense of
+        let command = match self.parse(&input) {
+            Ok(command) => command,
+            Err(e) => {
+                println!("⚠️  {}", e);
+                return PlayerCommand::Ignore;
+            }
+        };
 
-        debug!("Cooldown active: {}", cooldown_handler.is_cooldown_active(COOLDOWN));
+        debug!("Cooldown active: {}", cooldown_handler.is_cooldown_active(self.cooldown));
 
         // Check cooldown
-        if cooldown_handler.is_cooldown_active(COOLDOWN) {
+        if cooldown_handler.is_cooldown_active(self.cooldown) {
             return PlayerCommand::Ignore;
         }
 
@@ -96,15 +172,70 @@ impl KeyboardControls {
         Ok(input)
     }
 
-    /// Translates input string into the corresponding `PlayerCommand`
+    /// Translates input string into the corresponding `PlayerCommand` using the default bindings
     pub fn translate_command(input: &str) -> PlayerCommand {
-        COMMAND_MAP.get(input.trim()).cloned().unwrap_or(PlayerCommand::Ignore)
+        DEFAULT_CONTROLS.translate(input)
+    }
+
+    /// Translates input string into the corresponding `PlayerCommand`, mapping invalid input to `Ignore`
+    pub fn translate(&self, input: &str) -> PlayerCommand {
+        self.parse(input).unwrap_or(PlayerCommand::Ignore)
     }
 
-    pub fn print_help() {
-        for (shortcut, _command) in COMMAND_MAP.iter() {
-            println!("{}", shortcut);
+    /// Parses a key binding, optionally followed by an argument (`f 45`, `+ 0.2`),
+    /// or a keyword command (`vol 0.6`, `speed 1.5`).
+    pub fn parse(&self, input: &str) -> Result<PlayerCommand> {
+        let (word, argument) = split_command(input);
+        if word.is_empty() {
+            return Ok(PlayerCommand::Ignore);
         }
+
+        let action = self.bindings.action_for_line(word);
+        let Some(argument) = argument else {
+            return match (action, Keyword::parse(word)) {
+                (Some(action), _) => Ok(self.command_for(action)),
+                (None, Some(_)) => Err(anyhow!("'{}' needs an argument, e.g. {}", word, Self::example_for(word))),
+                (None, None) => Err(anyhow!("Unknown command '{}'", word)),
+            };
+        };
+
+        match action {
+            Some(PlayerAction::SkipForward) => Ok(PlayerCommand::SkipForward(parse_skip(argument)?)),
+            Some(PlayerAction::SkipBackward) => Ok(PlayerCommand::SkipBackward(parse_skip(argument)?)),
+            Some(PlayerAction::VolumeUp) => Ok(PlayerCommand::VolumeUp(parse_volume_step(argument)?)),
+            Some(PlayerAction::VolumeDown) => Ok(PlayerCommand::VolumeDown(parse_volume_step(argument)?)),
+            Some(action) => Err(anyhow!("'{}' ({}) does not take an argument", word, action.description())),
+            None => match Keyword::parse(word) {
+                Some(keyword) => keyword.command(argument),
+                None => Err(anyhow!("Unknown command '{}'", word)),
+            },
+        }
+    }
+
+    fn example_for(word: &str) -> String {
+        ARGUMENT_EXAMPLES
+            .iter()
+            .flat_map(|(examples, _)| examples.split(", "))
+            .find(|example| example.split_whitespace().next() == Some(word))
+            .map_or_else(|| format!("{} <value>", word), str::to_string)
     }
 
-}
\ No newline at end of file
+    /// Help for single-keypress input, where commands with arguments are not available.
+    pub fn print_key_help(&self) {
+        println!("Controls:");
+        println!("{}", self.bindings.help_text());
+    }
+
+    pub fn print_help(&self) {
+        println!("Controls (type a command and press Enter):");
+        println!("{}", self.bindings.line_help_text());
+        for (example, description) in ARGUMENT_EXAMPLES {
+            println!("  {:<26}  {}", example, description);
+        }
+    }
+
+}
+
+fn parse_skip(argument: &str) -> Result<i64> {
+    i64::try_from(parse_time(argument)?.as_secs()).map_err(|_| anyhow!("Invalid time '{}' (too long)", argument.trim()))
+}
@@ -1,11 +1,37 @@
 pub mod audio_player;
+pub mod audio_device;
+pub mod audio_output;
+pub mod audio_duration;
+pub mod audio_decoder;
+pub mod opus;
+pub mod loudness;
+pub mod state_file;
+pub mod silence;
+pub mod volume;
+pub mod chapters;
+pub mod sleep_timer;
 pub mod audio_control;
 pub mod keyboard_controls;
 pub mod episodes;
+pub mod credentials;
+pub mod directory;
+pub mod discovery;
+pub mod feed_source;
+pub mod http;
 pub mod podcast_manager;
+pub mod refresh_scheduler;
+pub mod subscriptions;
+pub mod output;
+pub mod config;
+pub mod key_bindings;
+pub mod command_parser;
+pub mod key_input;
+pub mod line_input;
 
 pub use episodes::{read_rss_feeds, fetch_episodes, Episode, pretty_print};
+pub use feed_source::{FeedSource, FileSource, HttpSource, MemorySource};
 pub use audio_player::{AudioPlayer, PlayerCommand};
 pub use keyboard_controls::{KeyboardControls, CooldownHandler, Cooldown};
 pub use audio_control::play_episode;
-pub use podcast_manager::PodcastStore;
\ No newline at end of file
+pub use podcast_manager::PodcastStore;
+pub use output::OutputFormat;
@@ -0,0 +1,46 @@
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+lazy_static::lazy_static! {
+    static ref LINES: Mutex<Receiver<String>> = Mutex::new(spawn_reader());
+}
+
+/// Reads stdin line by line on its own thread, so waiting for Enter does not stop the
+/// player from doing other work. Every line read goes through this one reader; a second
+/// one would steal lines from it.
+fn spawn_reader() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        loop {
+            let mut line = String::new();
+            match stdin.lock().read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) if sender.send(line).is_err() => break,
+                Ok(_) => {}
+            }
+        }
+    });
+    receiver
+}
+
+/// Waits for the next line of input; empty once stdin is closed.
+pub fn read_line() -> String {
+    LINES.lock().unwrap().recv().unwrap_or_default()
+}
+
+/// Waits up to `timeout` for the next line of input.
+pub fn read_line_timeout(timeout: Duration) -> Option<String> {
+    match LINES.lock().unwrap().recv_timeout(timeout) {
+        Ok(line) => Some(line),
+        Err(RecvTimeoutError::Timeout) => None,
+        // Nothing more will arrive; wait anyway so callers polling in a loop do not spin
+        Err(RecvTimeoutError::Disconnected) => {
+            thread::sleep(timeout);
+            None
+        }
+    }
+}
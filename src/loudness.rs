@@ -0,0 +1,304 @@
+use anyhow::Result;
+use crate::state_file::{load_json, save_json};
+use rodio::Source;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Loudness most podcast platforms normalize to.
+pub const DEFAULT_TARGET_LUFS: f64 = -16.0;
+
+/// Peaks are held below this level (-1 dBFS) after the normalization gain is applied.
+const LIMITER_CEILING: f32 = 0.891;
+
+/// Blocks quieter than this never count towards the integrated loudness.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// Measures integrated loudness as specified by ITU-R BS.1770-4 and EBU R128.
+///
+/// Samples are K-weighted, the mean square is taken over 400 ms blocks overlapping by 75%, and
+/// the blocks are gated: absolutely at -70 LUFS, then relative to their own loudness.
+pub struct LoudnessMeter {
+    channels: usize,
+    weights: Vec<f64>,
+    filters: Vec<KWeighting>,
+    hop_len: usize,
+    hop_frames: usize,
+    hop_energy: f64,
+    channel: usize,
+    frame_energy: f64,
+    /// Mean square (channel weighted) of every 100 ms hop seen so far.
+    hops: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        let channels = channels.max(1) as usize;
+        Self {
+            channels,
+            weights: (0..channels).map(|c| channel_weight(c, channels)).collect(),
+            filters: (0..channels).map(|_| KWeighting::new(sample_rate as f64)).collect(),
+            hop_len: (sample_rate as usize / 10).max(1),
+            hop_frames: 0,
+            hop_energy: 0.0,
+            channel: 0,
+            frame_energy: 0.0,
+            hops: Vec::new(),
+        }
+    }
+
+    /// Adds one interleaved sample in the range -1.0..=1.0.
+    pub fn push(&mut self, sample: f32) {
+        let filtered = self.filters[self.channel].process(sample as f64);
+        self.frame_energy += self.weights[self.channel] * filtered * filtered;
+
+        self.channel += 1;
+        if self.channel < self.channels {
+            return;
+        }
+        self.channel = 0;
+        self.hop_energy += self.frame_energy;
+        self.frame_energy = 0.0;
+        self.hop_frames += 1;
+        if self.hop_frames == self.hop_len {
+            self.hops.push(self.hop_energy / self.hop_len as f64);
+            self.hop_energy = 0.0;
+            self.hop_frames = 0;
+        }
+    }
+
+    /// The integrated loudness in LUFS, or `None` when there is nothing above the absolute gate.
+    pub fn integrated(&self) -> Option<f64> {
+        let blocks: Vec<f64> = self.hops.windows(4).map(|w| w.iter().sum::<f64>() / 4.0).collect();
+
+        let audible: Vec<f64> = blocks.into_iter().filter(|&p| loudness(p) > ABSOLUTE_GATE_LUFS).collect();
+        if audible.is_empty() {
+            return None;
+        }
+        let threshold = loudness(mean(&audible)) + RELATIVE_GATE_LU;
+
+        let gated: Vec<f64> = audible.into_iter().filter(|&p| loudness(p) > threshold).collect();
+        if gated.is_empty() {
+            return None;
+        }
+        Some(loudness(mean(&gated)))
+    }
+}
+
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Surround channels count for more; the LFE channel of 5.1 does not count at all.
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channels, channel) {
+        (6.., 3) => 0.0,
+        (6.., 4 | 5) => 1.41,
+        _ => 1.0,
+    }
+}
+
+/// The two-stage K-weighting filter: a high shelf modelling the head, then a high-pass.
+struct KWeighting {
+    stages: [Biquad; 2],
+}
+
+impl KWeighting {
+    fn new(rate: f64) -> Self {
+        // Coefficients derived for any sample rate, as in BS.1770 reference implementations
+        let f0 = 1681.974450955533;
+        let gain = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (PI * f0 / rate).tan();
+        let vh = 10f64.powf(gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (PI * f0 / rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        Self { stages: [shelf, high_pass] }
+    }
+
+    fn process(&mut self, sample: f64) -> f64 {
+        self.stages.iter_mut().fold(sample, |x, stage| stage.process(x))
+    }
+}
+
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self { b, a, z: [0.0; 2] }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// Measures the integrated loudness of a whole source.
+pub fn measure<S: Source<Item = i16>>(source: S) -> Option<f64> {
+    let mut meter = LoudnessMeter::new(source.channels(), source.sample_rate());
+    for sample in source {
+        meter.push(sample as f32 / i16::MAX as f32);
+    }
+    meter.integrated()
+}
+
+/// The linear gain that brings audio measured at `measured_lufs` to `target_lufs`.
+pub fn normalization_gain(measured_lufs: f64, target_lufs: f64) -> f32 {
+    10f64.powf((target_lufs - measured_lufs) / 20.0) as f32
+}
+
+/// A gain that can be changed while the audio is playing, e.g. once a background analysis finishes.
+#[derive(Clone)]
+pub struct SharedGain(Arc<AtomicU32>);
+
+impl SharedGain {
+    pub fn new(gain: f32) -> Self {
+        Self(Arc::new(AtomicU32::new(gain.to_bits())))
+    }
+
+    pub fn set(&self, gain: f32) {
+        self.0.store(gain.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// Applies the normalization gain and keeps the result from clipping.
+///
+/// Gain changes are ramped so a late analysis result does not cause an audible jump. The
+/// limiter reduces gain instantly when a peak would exceed -1 dBFS and recovers over ~100 ms.
+pub struct Normalized<S> {
+    source: S,
+    gain: SharedGain,
+    current_gain: f32,
+    envelope: f32,
+    ramp: f32,
+    release: f32,
+}
+
+impl<S: Source<Item = i16>> Normalized<S> {
+    pub fn new(source: S, gain: SharedGain) -> Self {
+        let samples_per_sec = (source.sample_rate() * source.channels() as u32).max(1) as f32;
+        Self {
+            current_gain: gain.get(),
+            source,
+            gain,
+            envelope: 1.0,
+            ramp: 1.0 / (0.2 * samples_per_sec),
+            release: 1.0 / (0.1 * samples_per_sec),
+        }
+    }
+}
+
+impl<S: Source<Item = i16>> Iterator for Normalized<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.source.next()? as f32 / i16::MAX as f32;
+
+        self.current_gain += (self.gain.get() - self.current_gain) * self.ramp;
+
+        let amplified = sample * self.current_gain;
+        let limit = if amplified.abs() > LIMITER_CEILING { LIMITER_CEILING / amplified.abs() } else { 1.0 };
+        self.envelope = if limit < self.envelope { limit } else { (self.envelope + self.release).min(limit) };
+        Some(amplified * self.envelope)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.source.size_hint()
+    }
+}
+
+impl<S: Source<Item = i16>> Source for Normalized<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
+        self.source.try_seek(pos)
+    }
+}
+
+/// Normalization settings shared by every episode the player starts.
+#[derive(Clone)]
+pub struct Normalization {
+    pub target_lufs: f64,
+    pub cache: Arc<Mutex<LoudnessCache>>,
+}
+
+impl Normalization {
+    pub fn new(target_lufs: f64, cache: LoudnessCache) -> Self {
+        Self { target_lufs, cache: Arc::new(Mutex::new(cache)) }
+    }
+}
+
+/// Measured loudness per episode audio URL, kept in the data directory so each episode is analysed once.
+pub struct LoudnessCache {
+    path: PathBuf,
+    entries: HashMap<String, f64>,
+}
+
+impl LoudnessCache {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let entries = load_json(&path, "loudness cache")?;
+        Ok(Self { path, entries })
+    }
+
+    /// Where the cache lives inside the data directory.
+    pub fn default_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("loudness.json")
+    }
+
+    pub fn get(&self, audio_url: &str) -> Option<f64> {
+        self.entries.get(audio_url).copied()
+    }
+
+    pub fn insert(&mut self, audio_url: &str, lufs: f64) -> Result<()> {
+        self.entries.insert(audio_url.to_string(), lufs);
+        save_json(&self.path, &self.entries)
+    }
+}
@@ -1,39 +1,284 @@
 use anyhow::Result;
+use clap::Parser;
 use env_logger::Env;
 use log::{info, error};
 use rss_reader::{
+    audio_device::{default_output_device, output_devices},
     audio_player::AudioPlayer, 
-    fetch_episodes, 
     read_rss_feeds, 
+    credentials::redact,
+    directory::DirectoryResult,
+    discovery::{discover_feeds, FeedCandidate},
     play_episode,
-    episodes::pretty_print
+    config::{Config, ConfigOverrides},
+    key_bindings::KeyBindings,
+    line_input,
+    loudness::{LoudnessCache, Normalization},
+    silence::{SilenceTrimming, TimeSavedLog},
+    volume::VolumeControl,
+    episodes::{move_rss_feed, pretty_print},
+    feed_source::{same_feed, FeedSource, HttpSource},
+    http::HttpClient,
+    output::{render_directory_results, render_episodes, render_podcasts},
+    podcast_manager::{load_podcasts, FeedMove, Podcast},
+    refresh_scheduler::BackgroundRefresh,
+    subscriptions::read_subscriptions,
+    KeyboardControls,
+    OutputFormat,
+    PodcastStore,
 };
 
+mod cli;
 mod tui;
 
+use cli::{Cli, Command};
+
 fn main() -> Result<()> {
     // Initialize logging
     env_logger::init_from_env(Env::default().default_filter_or("info"));
 
-    info!("Starting RSS Reader Application");
+    let cli = Cli::parse();
+    let config = Config::load(&ConfigOverrides::from_env()?.merge(cli.overrides()))?;
+    let key_bindings = KeyBindings::from_config(&config.keys)?;
+    let feeds_file = config.paths.subscriptions.to_string_lossy().to_string();
+    let source = HttpSource::with_client(HttpClient::new(config.network.http_settings())?);
 
-    // Fetch and read RSS feeds
-    let feeds = match read_rss_feeds("rss-db.txt") {
-        Ok(feeds) => {
-            info!("Successfully read RSS feeds");
-            feeds
+    match cli.command {
+        Some(Command::Podcasts { format, tag }) => {
+            let mut store = PodcastStore::new();
+            report_moves(&load_podcasts(&feeds_file, &mut store, &source)?);
+            let mut podcasts = store.list_podcasts();
+            if let Some(tag) = tag {
+                podcasts.retain(|podcast| podcast.settings().has_tag(&tag));
+            }
+            println!("{}", render_podcasts(&podcasts, format)?);
+            Ok(())
+        },
+        Some(Command::Episodes { feed_url, format }) => {
+            let feed_url = match feed_url {
+                Some(feed_url) => feed_url,
+                None => read_rss_feeds(&feeds_file)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("No feeds found"))?,
+            };
+            let feed = source.feed(&feed_url)?;
+            if let Some(to) = feed.moved_to {
+                follow_move(&feeds_file, FeedMove { from: feed_url, to })?;
+            }
+            println!("{}", render_episodes(&feed.episodes, format)?);
+            Ok(())
+        },
+        Some(Command::Play { feed_url }) => play_from_prompt(&config, &source, key_bindings, feed_url),
+        Some(Command::Add { url }) => add_feed(&feeds_file, &source, &url),
+        Some(Command::SearchDirectory { query, limit, format }) => {
+            let directory = config.directory.provider(source.client().clone());
+            let results = directory.search(&query.join(" "), limit.unwrap_or(config.directory.limit))?;
+            if results.is_empty() && format == OutputFormat::Text {
+                println!("No podcasts found on {}", directory.name());
+            } else {
+                println!("{}", render_directory_results(&results, format)?);
+            }
+            Ok(())
+        },
+        Some(Command::Remove { url }) => {
+            let mut store = PodcastStore::with_subscription_file(&feeds_file);
+            if store.unsubscribe(&url)? {
+                println!("Unsubscribed from {}", redact(&url));
+            } else {
+                println!("Not subscribed to {}", redact(&url));
+            }
+            Ok(())
+        },
+        Some(Command::Rename { url, title }) => {
+            PodcastStore::with_subscription_file(&feeds_file).rename(&url, title.as_deref())?;
+            match title {
+                Some(title) => println!("Renamed {} to {}", redact(&url), title),
+                None => println!("{} uses the feed's own title again", redact(&url)),
+            }
+            Ok(())
+        },
+        Some(Command::Tag { url, tags, remove }) => {
+            let mut store = PodcastStore::with_subscription_file(&feeds_file);
+            for tag in tags {
+                let changed = if remove { store.untag(&url, &tag)? } else { store.tag(&url, &tag)? };
+                if !changed {
+                    println!("{} {} tagged {}", redact(&url), if remove { "was not" } else { "is already" }, tag);
+                }
+            }
+            Ok(())
         },
+        Some(Command::Devices) => {
+            let default = default_output_device();
+            for device in output_devices()? {
+                let marker = if Some(&device) == default.as_ref() { " (default)" } else { "" };
+                println!("{}{}", device, marker);
+            }
+            Ok(())
+        },
+        None => run_interactive(&config, &source, key_bindings),
+    }
+}
+
+fn run_interactive(config: &Config, source: &HttpSource, key_bindings: KeyBindings) -> Result<()> {
+    info!("Starting RSS Reader Application");
+
+    let mut store = PodcastStore::new();
+    let moves = match load_podcasts(&config.paths.subscriptions.to_string_lossy(), &mut store, source) {
+        Ok(moves) => moves,
         Err(e) => {
-            error!("Failed to read RSS feeds: {}", e);
+            error!("Failed to load podcasts: {}", e);
             return Err(e);
         }
     };
+    let podcasts = store.list_podcasts().into_iter().cloned().collect();
+
+    let mut audio_player = audio_player(config, source)?;
+
+    // Initialize TUI
+    let mut tui = tui::Tui::new(
+        podcasts,
+        config.ui.theme,
+        key_bindings.tui,
+        config.playback.volume_step,
+        config.playback.default_speed,
+    )?;
+    if let Some(status) = moves_status(&moves) {
+        tui.set_status(status);
+    }
+    let feeds_file = config.paths.subscriptions.to_string_lossy().to_string();
+    let subscribe_source = source.clone();
+    tui.set_directory(
+        config.directory.provider(source.client().clone()),
+        config.directory.limit,
+        move |result| subscribe(&feeds_file, &subscribe_source, result),
+    );
+    if let Some(settings) = config.refresh.schedule_settings() {
+        info!("Refreshing feeds in the background every {} minutes", config.refresh.interval_mins);
+        tui.set_background_refresh(BackgroundRefresh::spawn(store, source.clone(), settings));
+    }
+
+    info!("Launching Terminal User Interface");
+    tui.run(&mut audio_player)
+}
+
+fn add_feed(feeds_file: &str, source: &HttpSource, url: &str) -> Result<()> {
+    let candidates = discover_feeds(source.client(), url)?;
+    let candidate = match candidates.as_slice() {
+        [only] => only,
+        _ => pick_feed(&candidates)?,
+    };
+
+    let mut store = PodcastStore::with_subscription_file(feeds_file);
+    match store.subscribe(&candidate.url, source)? {
+        Some(podcast) => println!("Subscribed to {} ({} episodes)", podcast.title(), podcast.episodes().len()),
+        None => println!("Already subscribed to {}", candidate.title.clone().unwrap_or_else(|| redact(&candidate.url))),
+    }
+    Ok(())
+}
+
+/// Subscribes to a show found in the directory. Returns `None` when it is already subscribed.
+fn subscribe(feeds_file: &str, source: &HttpSource, result: &DirectoryResult) -> Result<Option<Podcast>> {
+    let mut store = PodcastStore::with_subscription_file(feeds_file);
+    Ok(store.subscribe(&result.feed_url, source)?.cloned())
+}
+
+fn pick_feed(candidates: &[FeedCandidate]) -> Result<&FeedCandidate> {
+    println!("Found {} feeds:", candidates.len());
+    for (i, candidate) in candidates.iter().enumerate() {
+        match &candidate.title {
+            Some(title) => println!("{}. {} ({})", i + 1, title, redact(&candidate.url)),
+            None => println!("{}. {}", i + 1, redact(&candidate.url)),
+        }
+    }
+    println!("\nSelect feed (1-{}):", candidates.len());
+    let input = line_input::read_line();
+    input.trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|choice| candidates.get(choice.checked_sub(1)?))
+        .ok_or_else(|| anyhow::anyhow!("No feed number {}", input.trim()))
+}
+
+/// Tells the user on stderr which subscriptions now point at the feeds' new homes.
+fn report_moves(moves: &[FeedMove]) {
+    for feed_move in moves {
+        eprintln!("📦 {}; the subscription now uses the new URL", feed_move);
+    }
+}
+
+/// Points the subscription for a feed that moved at its new URL, and says so. A feed that is
+/// not subscribed only gets the move reported.
+fn follow_move(feeds_file: &str, feed_move: FeedMove) -> Result<()> {
+    if move_rss_feed(feeds_file, &feed_move.from, &feed_move.to)? {
+        report_moves(&[feed_move]);
+    } else {
+        eprintln!("📦 {}", feed_move);
+    }
+    Ok(())
+}
+
+/// A one-line summary of the moves for the TUI status bar.
+fn moves_status(moves: &[FeedMove]) -> Option<String> {
+    match moves {
+        [] => None,
+        [feed_move] => Some(format!("📦 {}; the subscription now uses the new URL", feed_move)),
+        [first, ..] => Some(format!(
+            "📦 {} feeds moved and their subscriptions now use the new URLs, e.g. {}",
+            moves.len(),
+            first
+        )),
+    }
+}
+
+fn audio_player(config: &Config, source: &HttpSource) -> Result<AudioPlayer> {
+    let mut audio_player = AudioPlayer::with_output(config.playback.output_backend())?;
+    audio_player.set_http_client(source.client().clone());
+    audio_player.set_speed(config.playback.default_speed)?;
+    if config.playback.normalize {
+        let cache = LoudnessCache::load(LoudnessCache::default_path(&config.paths.data_dir))?;
+        audio_player.set_normalization(Some(Normalization::new(config.playback.target_lufs, cache)));
+    }
+    let volume_path = VolumeControl::default_path(&config.paths.data_dir);
+    audio_player.set_volume_control(VolumeControl::load(volume_path, config.playback.max_volume)?);
+    if config.playback.trim_silence {
+        let log = TimeSavedLog::load(TimeSavedLog::default_path(&config.paths.data_dir))?;
+        audio_player.set_silence_trimming(Some(SilenceTrimming::new(config.playback.trim_settings(), log)));
+    }
+    Ok(audio_player)
+}
+
+fn play_from_prompt(config: &Config, source: &HttpSource, key_bindings: KeyBindings, feed_url: Option<String>) -> Result<()> {
+    // Fetch and read RSS feeds
+    let feed_url = match feed_url {
+        Some(feed_url) => feed_url,
+        None => match read_rss_feeds(&config.paths.subscriptions.to_string_lossy()) {
+            Ok(feeds) => {
+                info!("Successfully read RSS feeds");
+                feeds.into_iter().next().ok_or_else(|| anyhow::anyhow!("No feeds found"))?
+            },
+            Err(e) => {
+                error!("Failed to read RSS feeds: {}", e);
+                return Err(e);
+            }
+        },
+    };
+
+    // The subscriber's settings for the feed, when it is subscribed
+    let settings = read_subscriptions(&config.paths.subscriptions.to_string_lossy())
+        .ok()
+        .and_then(|subscriptions| subscriptions.into_iter().find(|subscription| same_feed(&subscription.url, &feed_url)))
+        .map(|subscription| subscription.settings)
+        .unwrap_or_default();
 
     // Fetch episodes
-    let episodes = match fetch_episodes(&feeds.first().ok_or_else(|| anyhow::anyhow!("No feeds found"))?) {
-        Ok(episodes) => {
+    let mut episodes = match source.feed(&feed_url) {
+        Ok(feed) => {
             info!("Successfully fetched episodes");
-            episodes
+            if let Some(to) = feed.moved_to {
+                follow_move(&config.paths.subscriptions.to_string_lossy(), FeedMove { from: feed_url, to })?;
+            }
+            feed.episodes
         },
         Err(e) => {
             error!("Failed to fetch episodes: {}", e);
@@ -41,29 +286,6 @@ fn main() -> Result<()> {
         }
     };
 
-    // Pretty print episodes
-    for episode in &episodes {
-        println!("{}", pretty_print(episode));
-    }
-
-    // Example lists for TUI
-    let left_items = vec![
-        "Podcast 1".to_string(), 
-        "Podcast 2".to_string(), 
-        "Podcast 3".to_string()
-    ];
-    let right_items = vec![
-        "Episode A".to_string(), 
-        "Episode B".to_string(), 
-        "Episode C".to_string()
-    ];
-
-    // Initialize TUI
-    let mut tui = tui::Tui::new(left_items, right_items)?;
-    
-    info!("Launching Terminal User Interface");
-    tui.run()?;
-
     // List episodes
     for (i, episode) in episodes.iter().enumerate() {
         println!("{}. {}", i + 1, episode.title);
@@ -71,21 +293,28 @@ fn main() -> Result<()> {
     
     // Get episode selection
     println!("\nSelect episode (1-{}):", episodes.len());
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
+    let input = line_input::read_line();
 
-    let mut audio_player = AudioPlayer::new()?;
+    let mut audio_player = audio_player(config, source)?;
+    let controls = KeyboardControls::with_bindings(key_bindings.player, &config.playback);
     
     let episode_num: usize = input.trim().parse()?;
     if episode_num > 0 && episode_num <= episodes.len() {
-        let selected_episode = &episodes[episode_num - 1];
+        let selected_episode = &mut episodes[episode_num - 1];
         
         // Display episode details
         println!("\n--- Episode Details ---");
         println!("{}", pretty_print(selected_episode));
         
         // Play the episode
-        play_episode(&mut audio_player, selected_episode)?;
+        play_episode(
+            &mut audio_player,
+            selected_episode,
+            &settings,
+            config.playback.default_speed,
+            &controls,
+            config.playback.input_mode,
+        )?;
     }
     
     Ok(())
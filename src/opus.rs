@@ -0,0 +1,103 @@
+//! An Opus decoder for symphonia. Symphonia reads Ogg Opus streams but has no decoder for the
+//! codec, so this one hands the packets to libopus.
+
+use audiopus::coder::{Decoder as Libopus, GenericCtl};
+use audiopus::packet::Packet as OpusPacket;
+use audiopus::{Channels as OpusChannels, MutSignals, SampleRate};
+use std::sync::Mutex;
+use symphonia::core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal, SignalSpec};
+use symphonia::core::codecs::{
+    CodecDescriptor, CodecParameters, Decoder, DecoderOptions, FinalizeResult, CODEC_TYPE_OPUS,
+};
+use symphonia::core::errors::{decode_error, unsupported_error, Result};
+use symphonia::core::formats::Packet;
+use symphonia::core::support_codec;
+
+/// Opus always decodes at 48 kHz here, the rate Ogg Opus timestamps count in.
+const SAMPLE_RATE: u32 = 48_000;
+
+/// The most frames a packet can hold: 120 ms at 48 kHz.
+const MAX_PACKET_FRAMES: usize = 5760;
+
+/// Decodes mono and stereo Opus streams, the only layouts the plain libopus decoder handles.
+pub struct OpusDecoder {
+    params: CodecParameters,
+    // libopus' decoder state can move between threads but not be shared; symphonia wants both.
+    decoder: Mutex<Libopus>,
+    /// Frames at the start of the stream that only warm up the decoder.
+    pre_skip: u64,
+    /// libopus' interleaved output for the last packet.
+    interleaved: Vec<i16>,
+    buffer: AudioBuffer<i16>,
+}
+
+impl Decoder for OpusDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        let head = match params.extra_data.as_deref() {
+            Some(head) if head.len() >= 19 && head.starts_with(b"OpusHead") => head,
+            _ => return decode_error("opus: missing identification header"),
+        };
+        let (channels, layout) = match (head[9], head[18]) {
+            (1, 0) => (OpusChannels::Mono, Channels::FRONT_LEFT),
+            (2, 0) => (OpusChannels::Stereo, Channels::FRONT_LEFT | Channels::FRONT_RIGHT),
+            _ => return unsupported_error("opus: multichannel streams are not supported"),
+        };
+        let pre_skip = u16::from_le_bytes([head[10], head[11]]);
+        let output_gain = i16::from_le_bytes([head[16], head[17]]);
+
+        let decoder = Libopus::new(SampleRate::Hz48000, channels)
+            .or_else(|_| decode_error("opus: could not create the decoder"))?;
+        decoder.set_gain(i32::from(output_gain)).or_else(|_| decode_error("opus: invalid output gain"))?;
+
+        Ok(Self {
+            params: params.clone(),
+            decoder: Mutex::new(decoder),
+            pre_skip: u64::from(pre_skip),
+            interleaved: vec![0; MAX_PACKET_FRAMES * layout.count()],
+            buffer: AudioBuffer::new(MAX_PACKET_FRAMES as u64, SignalSpec::new(SAMPLE_RATE, layout)),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_OPUS, "opus", "Opus")]
+    }
+
+    fn reset(&mut self) {
+        let _ = self.decoder.get_mut().unwrap().reset_state();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        let input = OpusPacket::try_from(packet.buf()).or_else(|_| decode_error("opus: empty packet"))?;
+        let output = MutSignals::try_from(&mut self.interleaved[..]).or_else(|_| decode_error("opus: no output buffer"))?;
+        let frames = self
+            .decoder
+            .get_mut()
+            .unwrap()
+            .decode(Some(input), output, false)
+            .or_else(|_| decode_error("opus: invalid packet"))?;
+
+        let channels = self.buffer.spec().channels.count();
+        self.buffer.clear();
+        self.buffer.render_reserved(Some(frames));
+        for channel in 0..channels {
+            for (frame, sample) in self.buffer.chan_mut(channel).iter_mut().enumerate() {
+                *sample = self.interleaved[frame * channels + channel];
+            }
+        }
+        let warm_up = self.pre_skip.saturating_sub(packet.ts()) as usize;
+        self.buffer.trim(packet.trim_start() as usize + warm_up, packet.trim_end() as usize);
+        Ok(self.buffer.as_audio_buffer_ref())
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        FinalizeResult::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buffer.as_audio_buffer_ref()
+    }
+}
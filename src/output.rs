@@ -0,0 +1,71 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use crate::directory::{pretty_print_result, DirectoryResult};
+use crate::episodes::{pretty_print, Episode};
+use crate::podcast_manager::{pretty_print_podcast, Podcast};
+
+/// How listing commands render their results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Human readable, emoji-decorated text.
+    #[default]
+    Text,
+    /// A single pretty-printed JSON array.
+    Json,
+    /// One compact JSON object per line.
+    Ndjson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            other => Err(anyhow!("Unknown output format '{}' (expected text, json or ndjson)", other)),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Everything rendered has the credentials in its URLs masked; `Serialize` itself keeps
+/// them, so state files do not lose them.
+pub fn render_episodes(episodes: &[Episode], format: OutputFormat) -> Result<String> {
+    let episodes: Vec<Episode> = episodes.iter().map(Episode::redacted).collect();
+    render(&episodes, format, pretty_print)
+}
+
+pub fn render_podcasts(podcasts: &[&Podcast], format: OutputFormat) -> Result<String> {
+    let podcasts: Vec<Podcast> = podcasts.iter().map(|podcast| podcast.redacted()).collect();
+    render(&podcasts, format, pretty_print_podcast)
+}
+
+pub fn render_directory_results(results: &[DirectoryResult], format: OutputFormat) -> Result<String> {
+    render(results, format, pretty_print_result)
+}
+
+fn render<T: Serialize>(items: &[T], format: OutputFormat, text: impl Fn(&T) -> String) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Text => items.iter().map(text).collect::<Vec<_>>().join("\n\n"),
+        OutputFormat::Json => serde_json::to_string_pretty(items)?,
+        OutputFormat::Ndjson => items
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n"),
+    })
+}
@@ -1,21 +1,45 @@
-use std::collections::HashMap;
-use std::time::SystemTime;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::credentials::redact;
 use crate::episodes::Episode;
-use anyhow::Result;
+use crate::feed_source::{same_feed, FeedSource};
+use crate::subscriptions::{
+    add_subscription, move_subscription, normalize_feed_url, read_subscriptions, remove_subscription,
+    update_subscription, FeedSettings,
+};
+use anyhow::{anyhow, bail, Result};
+use log::info;
+use serde::Serialize;
+use std::fmt;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Podcast {
     feed_url: String,
     title: String,
     episodes: Vec<Episode>,
+    #[serde(serialize_with = "unix_secs::serialize")]
     time_added: SystemTime,
+    #[serde(serialize_with = "unix_secs::serialize_option")]
     last_updated: Option<SystemTime>,
     total_episodes: usize,
     description: Option<String>,
     author: Option<String>,
+    settings: FeedSettings,
+    #[serde(skip)]
+    refresh_hint: Option<Duration>,
 }
 
 impl Podcast {
+    /// A copy with the credentials in its feed and audio URLs masked, for display and exports.
+    pub fn redacted(&self) -> Self {
+        Self {
+            feed_url: redact(&self.feed_url),
+            episodes: self.episodes.iter().map(Episode::redacted).collect(),
+            ..self.clone()
+        }
+    }
+
     pub fn new(feed_url: String, title: String, episodes: Vec<Episode>) -> Self {
         Self {
             feed_url,
@@ -26,7 +50,18 @@ impl Podcast {
             last_updated: None,
             description: None,
             author: None,
+            settings: FeedSettings::default(),
+            refresh_hint: None,
+        }
+    }
+
+    /// Applies the subscriber's settings for the feed; a custom title replaces the feed's.
+    pub fn with_settings(mut self, settings: FeedSettings) -> Self {
+        if let Some(title) = &settings.title {
+            self.title = title.clone();
         }
+        self.settings = settings;
+        self
     }
 
     pub fn feed_url(&self) -> &str {
@@ -41,22 +76,133 @@ impl Podcast {
         &self.episodes
     }
 
+    pub fn episodes_mut(&mut self) -> &mut [Episode] {
+        &mut self.episodes
+    }
+
     pub fn time_added(&self) -> SystemTime {
         self.time_added
     }
+
+    pub fn last_updated(&self) -> Option<SystemTime> {
+        self.last_updated
+    }
+
+    /// Replaces the episodes with a fresh fetch of the feed and returns how many are new,
+    /// going by their audio URL, or their title when they have none. Episodes that were
+    /// already known keep what was learned about them, such as their measured duration.
+    pub fn update_episodes(&mut self, episodes: Vec<Episode>) -> usize {
+        let known: HashSet<String> = self.episodes.iter().map(Episode::key).collect();
+        let new = episodes.iter().filter(|episode| !known.contains(&episode.key())).count();
+        self.total_episodes = episodes.len();
+        let known = std::mem::replace(&mut self.episodes, episodes);
+        self.keep_episode_state(&known);
+        self.last_updated = Some(SystemTime::now());
+        new
+    }
+
+    /// Carries per-episode state over from an earlier copy of the episodes: a duration the
+    /// player measured beats the one the feed announces.
+    pub fn keep_episode_state(&mut self, known: &[Episode]) {
+        let known: HashMap<String, &Episode> = known.iter().map(|episode| (episode.key(), episode)).collect();
+        for episode in &mut self.episodes {
+            if let Some(known) = known.get(&episode.key()) {
+                episode.duration = known.duration.or(episode.duration);
+            }
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn settings(&self) -> &FeedSettings {
+        &self.settings
+    }
+
+    /// How often the feed says it changes, as of the last fetch.
+    pub fn refresh_hint(&self) -> Option<Duration> {
+        self.refresh_hint
+    }
 }
 
+/// Serializes `SystemTime`s as seconds since the Unix epoch.
+mod unix_secs {
+    use serde::Serializer;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn secs(time: &SystemTime) -> u64 {
+        time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+    }
+
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(secs(time))
+    }
+
+    pub fn serialize_option<S: Serializer>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => serializer.serialize_some(&secs(time)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+pub fn pretty_print_podcast(podcast: &Podcast) -> String {
+    let mut details = Vec::new();
+
+    details.push(format!("📻  Podcast: {}", podcast.title()));
+    details.push(format!("📡  Feed: {}", redact(podcast.feed_url())));
+
+    if let Some(author) = podcast.author() {
+        details.push(format!("👤  Author: {}", author));
+    }
+
+    if let Some(folder) = &podcast.settings().folder {
+        details.push(format!("📁  Folder: {}", folder));
+    }
+
+    if !podcast.settings().tags.is_empty() {
+        details.push(format!("🏷️  Tags: {}", podcast.settings().tags.join(", ")));
+    }
+
+    details.push(format!("🎧  Episodes: {}", podcast.episodes().len()));
+
+    details.join("\n")
+}
+
+#[derive(Default)]
 pub struct PodcastStore {
     podcasts: HashMap<String, Podcast>,
+    /// Where subscription changes are written back to.
+    subscription_file: Option<PathBuf>,
 }
 
 impl PodcastStore {
     pub fn new() -> Self {
         Self {
             podcasts: HashMap::new(),
+            subscription_file: None,
         }
     }
 
+    /// A store whose subscription changes are saved to `path`.
+    pub fn with_subscription_file(path: impl Into<PathBuf>) -> Self {
+        Self { subscription_file: Some(path.into()), ..Self::new() }
+    }
+
+    pub fn subscription_file(&self) -> Option<&Path> {
+        self.subscription_file.as_deref()
+    }
+
+    /// Detaches the subscription file, so this store no longer writes it.
+    pub fn take_subscription_file(&mut self) -> Option<PathBuf> {
+        self.subscription_file.take()
+    }
+
     pub fn add_podcast(&mut self, podcast: Podcast) {
         self.podcasts.insert(podcast.feed_url().to_string(), podcast);
     }
@@ -69,6 +215,13 @@ impl PodcastStore {
         self.podcasts.values().map(|podcast| podcast.title().to_string()).collect()
     }
 
+    /// Returns all podcasts ordered by feed URL, so listings are stable between runs.
+    pub fn list_podcasts(&self) -> Vec<&Podcast> {
+        let mut podcasts: Vec<&Podcast> = self.podcasts.values().collect();
+        podcasts.sort_by(|a, b| a.feed_url().cmp(b.feed_url()));
+        podcasts
+    }
+
     pub fn get_podcast(&self, feed_url: &str) -> Option<&Podcast> {
         self.podcasts.get(feed_url)
     }
@@ -76,32 +229,191 @@ impl PodcastStore {
     pub fn get_episodes(&self, feed_url: &str) -> Option<&[Episode]> {
         self.podcasts.get(feed_url).map(|podcast| podcast.episodes())
     }
+
+    /// Files a podcast under its new feed URL, keeping its episodes and history. When the
+    /// store already has the new URL, that podcast stays and the old entry goes.
+    pub fn move_podcast(&mut self, from: &str, to: &str) -> Result<()> {
+        let mut podcast = self.podcasts.remove(from).ok_or_else(|| anyhow!("Unknown podcast {}", redact(from)))?;
+        if self.podcasts.contains_key(to) {
+            info!("Dropping {}, which duplicates {}", redact(from), redact(to));
+            return Ok(());
+        }
+        podcast.feed_url = to.to_string();
+        self.podcasts.insert(to.to_string(), podcast);
+        Ok(())
+    }
+
+    /// Subscribes to the feed at `url` after checking that it is one, adding it to the
+    /// subscription file and the store. The URL is normalized first, and a feed that moved is
+    /// subscribed at its new home. Returns `None` when the feed is already subscribed.
+    pub fn subscribe(&mut self, url: &str, source: &dyn FeedSource) -> Result<Option<&Podcast>> {
+        let file = self.file()?;
+        let url = normalize_feed_url(url)?;
+        if self.find(&url).is_some() {
+            return Ok(None);
+        }
+        let feed = source.feed(&url)?;
+        let feed_url = match feed.moved_to {
+            Some(to) => normalize_feed_url(&to)?,
+            None => url,
+        };
+        if self.find(&feed_url).is_some() || !add_subscription(&file, &feed_url)? {
+            return Ok(None);
+        }
+
+        let title = podcast_title(feed.title, &feed.episodes, &feed_url);
+        info!("Subscribed to {}", redact(&feed_url));
+        let mut podcast = Podcast::new(feed_url.clone(), title, feed.episodes);
+        podcast.refresh_hint = feed.refresh_hint;
+        self.add_podcast(podcast);
+        Ok(self.podcasts.get(&feed_url))
+    }
+
+    /// Removes a feed from the subscription file and the store. Returns false when it was
+    /// not subscribed.
+    pub fn unsubscribe(&mut self, url: &str) -> Result<bool> {
+        let removed = remove_subscription(&self.file()?, url)?;
+        let known = self.find(url).and_then(|feed_url| self.podcasts.remove(&feed_url)).is_some();
+        Ok(removed || known)
+    }
+
+    /// Gives a podcast a custom title, or goes back to the feed's own title for `None`
+    /// (shown from the next load on).
+    pub fn rename(&mut self, url: &str, title: Option<&str>) -> Result<()> {
+        update_subscription(&self.file()?, url, |entry| entry.set_title(title))?;
+        if let Some(podcast) = self.find(url).and_then(|feed_url| self.podcasts.get_mut(&feed_url)) {
+            podcast.settings.title = title.map(str::trim).filter(|title| !title.is_empty()).map(str::to_string);
+            if let Some(title) = &podcast.settings.title {
+                podcast.title = title.clone();
+            }
+        }
+        Ok(())
+    }
+
+    /// Tags a podcast. Returns false when it already had the tag.
+    pub fn tag(&mut self, url: &str, tag: &str) -> Result<bool> {
+        if tag.trim().is_empty() {
+            bail!("Tags cannot be empty");
+        }
+        let added = update_subscription(&self.file()?, url, |entry| entry.add_tag(tag))?;
+        if let Some(podcast) = self.find(url).and_then(|feed_url| self.podcasts.get_mut(&feed_url)) {
+            if added {
+                podcast.settings.tags.push(tag.trim().to_string());
+            }
+        }
+        Ok(added)
+    }
+
+    /// Takes a tag off a podcast. Returns false when it did not have the tag.
+    pub fn untag(&mut self, url: &str, tag: &str) -> Result<bool> {
+        let removed = update_subscription(&self.file()?, url, |entry| entry.remove_tag(tag))?;
+        if let Some(podcast) = self.find(url).and_then(|feed_url| self.podcasts.get_mut(&feed_url)) {
+            podcast.settings.tags.retain(|t| !t.eq_ignore_ascii_case(tag.trim()));
+        }
+        Ok(removed)
+    }
+
+    fn file(&self) -> Result<String> {
+        self.subscription_file
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("The podcast store has no subscription file"))
+    }
+
+    /// The feed URL a podcast is filed under, for any spelling of it.
+    fn find(&self, url: &str) -> Option<String> {
+        self.podcasts.keys().find(|feed_url| same_feed(feed_url, url)).cloned()
+    }
+
+    /// Fetches a podcast's feed again, following it when it moved.
+    pub fn refresh_podcast(&mut self, feed_url: &str, source: &dyn FeedSource) -> Result<Refresh> {
+        if !self.podcasts.contains_key(feed_url) {
+            bail!("Unknown podcast {}", redact(feed_url));
+        }
+        let feed = source.feed(feed_url)?;
+        let moved = feed.moved_to.map(|to| FeedMove { from: feed_url.to_string(), to });
+        let feed_url = match &moved {
+            Some(feed_move) => {
+                info!("{}", feed_move);
+                self.move_podcast(&feed_move.from, &feed_move.to)?;
+                &feed_move.to
+            }
+            None => feed_url,
+        };
+        let podcast = self.podcasts.get_mut(feed_url).expect("The podcast was just checked or moved");
+        podcast.refresh_hint = feed.refresh_hint;
+        Ok(Refresh { new_episodes: podcast.update_episodes(feed.episodes), moved })
+    }
+}
+
+/// The feed's own title, else its first episode's, else the feed URL without credentials.
+fn podcast_title(feed_title: Option<String>, episodes: &[Episode], feed_url: &str) -> String {
+    feed_title
+        .or_else(|| episodes.first().map(|episode| episode.title.clone()))
+        .unwrap_or_else(|| redact(feed_url))
+}
+
+/// What refreshing a podcast found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Refresh {
+    pub new_episodes: usize,
+    pub moved: Option<FeedMove>,
 }
 
+/// A feed that moved for good, through a permanent redirect or `itunes:new-feed-url`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedMove {
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for FeedMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Feed {} moved to {}", redact(&self.from), redact(&self.to))
+    }
+}
+
+/// Loads every enabled feed listed in the subscriptions file into the store, with its
+/// settings. Moved feeds are filed under their new URL, which replaces the old one in the
+/// file. The store saves subscription changes to the same file. Returns the moves, so the
+/// user can be told about them.
 pub fn load_podcasts(
-    filename: &str, 
-    podcast_manager: &mut PodcastStore, 
-    read_feeds_fn: impl Fn(&str) -> Result<Vec<String>>,
-    fetch_episodes_fn: impl Fn(&str) -> Result<Vec<Episode>>
-) -> Result<()> {
-    let feed_urls = read_feeds_fn(filename)?;
-    
-    for feed_url in feed_urls {
-        let episodes = fetch_episodes_fn(&feed_url)?;
-        
-        // Extract title from first episode or use feed URL
-        let title = episodes.first()
-            .map(|ep| ep.title.clone())
-            .unwrap_or_else(|| feed_url.clone());
-        
-        let podcast = Podcast::new(
-            feed_url.clone(), 
-            title, 
+    filename: &str,
+    podcast_manager: &mut PodcastStore,
+    source: &dyn FeedSource,
+) -> Result<Vec<FeedMove>> {
+    podcast_manager.subscription_file = Some(PathBuf::from(filename));
+    let mut moves = Vec::new();
+    for subscription in read_subscriptions(filename)? {
+        if subscription.settings.disabled {
+            info!("Skipping disabled feed {}", redact(&subscription.url));
+            continue;
+        }
+        let subscribed_url = subscription.url;
+        let feed = source.feed(&subscribed_url)?;
+        let episodes = feed.episodes;
+        let feed_url = match feed.moved_to {
+            Some(to) => {
+                let feed_move = FeedMove { from: subscribed_url, to: to.clone() };
+                info!("{}", feed_move);
+                move_subscription(filename, &feed_move.from, &feed_move.to)?;
+                moves.push(feed_move);
+                to
+            }
+            None => subscribed_url,
+        };
+
+        let title = podcast_title(feed.title, &episodes, &feed_url);
+
+        let mut podcast = Podcast::new(
+            feed_url.clone(),
+            title,
             episodes
-        );
-        
+        ).with_settings(subscription.settings);
+        podcast.refresh_hint = feed.refresh_hint;
+
         podcast_manager.add_podcast(podcast);
     }
-    
-    Ok(())
+
+    Ok(moves)
 }
@@ -0,0 +1,261 @@
+//! Keeps feeds current while the app runs. Each feed has its own schedule: spread out with
+//! jitter, stretched when the feed says it changes rarely and backed off while it fails.
+
+use crate::credentials::redact;
+use crate::feed_source::FeedSource;
+use crate::podcast_manager::{FeedMove, Podcast, PodcastStore, Refresh};
+use crate::subscriptions::move_subscription;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+/// How long the background thread sleeps when no feed is scheduled.
+const IDLE_WAIT: Duration = Duration::from_secs(60 * 60);
+
+/// Failures in a row after which the backoff stops growing; the wait is capped long before.
+const MAX_DOUBLINGS: u32 = 16;
+
+/// How often feeds are refreshed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleSettings {
+    /// The wait between refreshes of a feed that works and gives no hint.
+    pub interval: Duration,
+    /// The share of each wait, from 0 to 1, that is added or taken off at random.
+    pub jitter: f64,
+    /// The longest wait before jitter, for hints and backoff alike.
+    pub max_interval: Duration,
+}
+
+impl Default for ScheduleSettings {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60 * 60),
+            jitter: 0.1,
+            max_interval: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FeedState {
+    due: Instant,
+    hint: Option<Duration>,
+    failures: u32,
+}
+
+/// When each feed is due for its next refresh.
+#[derive(Debug, Clone)]
+pub struct RefreshSchedule {
+    settings: ScheduleSettings,
+    feeds: HashMap<String, FeedState>,
+}
+
+impl RefreshSchedule {
+    pub fn new(settings: ScheduleSettings) -> Self {
+        Self { settings, feeds: HashMap::new() }
+    }
+
+    /// Schedules a feed that was fetched at `now`, honouring how often it says it changes.
+    pub fn add(&mut self, feed_url: &str, hint: Option<Duration>, now: Instant) {
+        let mut state = FeedState { due: now, hint, failures: 0 };
+        state.due = now + self.wait(&state);
+        self.feeds.insert(feed_url.to_string(), state);
+    }
+
+    /// Stops refreshing a feed. Returns false when it was not scheduled.
+    pub fn remove(&mut self, feed_url: &str) -> bool {
+        self.feeds.remove(feed_url).is_some()
+    }
+
+    /// Files a feed's schedule under the URL it moved to. A schedule already there is kept.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        if let Some(state) = self.feeds.remove(from) {
+            self.feeds.entry(to.to_string()).or_insert(state);
+        }
+    }
+
+    /// The feeds due at `now`, the longest overdue first.
+    pub fn due(&self, now: Instant) -> Vec<String> {
+        let mut due: Vec<(&String, &FeedState)> = self.feeds.iter().filter(|(_, state)| state.due <= now).collect();
+        due.sort_by(|a, b| a.1.due.cmp(&b.1.due).then_with(|| a.0.cmp(b.0)));
+        due.into_iter().map(|(feed_url, _)| feed_url.clone()).collect()
+    }
+
+    /// When the feed is due next.
+    pub fn due_at(&self, feed_url: &str) -> Option<Instant> {
+        self.feeds.get(feed_url).map(|state| state.due)
+    }
+
+    /// When the first of the feeds comes due.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.feeds.values().map(|state| state.due).min()
+    }
+
+    /// How often refreshing the feed failed in a row.
+    pub fn failures(&self, feed_url: &str) -> u32 {
+        self.feeds.get(feed_url).map_or(0, |state| state.failures)
+    }
+
+    /// Records a refresh that worked at `now` and schedules the next one. Returns the wait.
+    pub fn succeeded(&mut self, feed_url: &str, hint: Option<Duration>, now: Instant) -> Duration {
+        self.reschedule(feed_url, now, |state| {
+            state.hint = hint;
+            state.failures = 0;
+        })
+    }
+
+    /// Records a refresh that failed at `now` and schedules a retry, which waits twice as long
+    /// for every failure in a row. Returns the wait.
+    pub fn failed(&mut self, feed_url: &str, now: Instant) -> Duration {
+        self.reschedule(feed_url, now, |state| state.failures = state.failures.saturating_add(1))
+    }
+
+    fn reschedule(&mut self, feed_url: &str, now: Instant, update: impl FnOnce(&mut FeedState)) -> Duration {
+        let mut state = self.feeds.remove(feed_url).unwrap_or(FeedState { due: now, hint: None, failures: 0 });
+        update(&mut state);
+        let wait = self.wait(&state);
+        state.due = now + wait;
+        self.feeds.insert(feed_url.to_string(), state);
+        wait
+    }
+
+    /// The interval, or the feed's hint when that is longer, doubled for every failure and
+    /// capped, with jitter on top.
+    fn wait(&self, state: &FeedState) -> Duration {
+        let ScheduleSettings { interval, jitter, max_interval } = self.settings;
+        let base = state.hint.map_or(interval, |hint| hint.max(interval));
+        let backoff = base.saturating_mul(2u32.pow(state.failures.min(MAX_DOUBLINGS)));
+        jittered(backoff.min(max_interval.max(interval)), jitter)
+    }
+}
+
+fn jittered(wait: Duration, jitter: f64) -> Duration {
+    let jitter = jitter.clamp(0.0, 1.0);
+    if jitter == 0.0 {
+        return wait;
+    }
+    wait.mul_f64(1.0 + jitter * (fastrand::f64() * 2.0 - 1.0))
+}
+
+/// What a background refresh found.
+#[derive(Debug, Clone)]
+pub enum RefreshEvent {
+    /// The feed was fetched; `podcast` is how it looks now.
+    Refreshed { podcast: Box<Podcast>, refresh: Refresh },
+    /// Fetching the feed failed; it is tried again after `retry_in`.
+    Failed { feed_url: String, error: String, retry_in: Duration },
+}
+
+/// Refreshes the podcasts of a store on a background thread, reporting each refresh as it
+/// happens. The thread stops once this is dropped.
+pub struct BackgroundRefresh {
+    events: Receiver<RefreshEvent>,
+    added: Sender<Podcast>,
+    /// The subscription file moved feeds are saved to, by whoever reads the events.
+    subscription_file: Option<PathBuf>,
+}
+
+impl BackgroundRefresh {
+    /// Starts refreshing every podcast in `store`, first one interval (or hint) from now,
+    /// since they were just loaded.
+    pub fn spawn<S: FeedSource + Send + 'static>(mut store: PodcastStore, source: S, settings: ScheduleSettings) -> Self {
+        let subscription_file = store.take_subscription_file();
+        let (event_sender, events) = mpsc::channel();
+        let (added, added_receiver) = mpsc::channel();
+        std::thread::spawn(move || refresh_loop(store, &source, settings, &added_receiver, &event_sender));
+        Self { events, added, subscription_file }
+    }
+
+    /// Refreshes a podcast subscribed to after the refresh started as well.
+    pub fn add(&self, podcast: Podcast) {
+        let _ = self.added.send(podcast);
+    }
+
+    /// What was found since the last call, without waiting. Feeds that moved are saved
+    /// to the subscription file first.
+    pub fn events(&self) -> Vec<RefreshEvent> {
+        let events: Vec<RefreshEvent> = self.events.try_iter().collect();
+        for event in &events {
+            if let RefreshEvent::Refreshed { refresh: Refresh { moved: Some(feed_move), .. }, .. } = event {
+                self.save_move(feed_move);
+            }
+        }
+        events
+    }
+
+    fn save_move(&self, feed_move: &FeedMove) {
+        if let Some(path) = &self.subscription_file {
+            if let Err(e) = move_subscription(&path.to_string_lossy(), &feed_move.from, &feed_move.to) {
+                warn!("Could not save that {}: {:#}", feed_move, e);
+            }
+        }
+    }
+}
+
+fn refresh_loop(
+    mut store: PodcastStore,
+    source: &dyn FeedSource,
+    settings: ScheduleSettings,
+    added: &Receiver<Podcast>,
+    events: &Sender<RefreshEvent>,
+) {
+    let mut schedule = RefreshSchedule::new(settings);
+    let now = Instant::now();
+    for podcast in store.list_podcasts() {
+        schedule.add(podcast.feed_url(), podcast.refresh_hint(), now);
+    }
+
+    loop {
+        let wait = schedule.next_due().map_or(IDLE_WAIT, |due| due.saturating_duration_since(Instant::now()));
+        match added.recv_timeout(wait) {
+            Ok(podcast) => {
+                schedule.add(podcast.feed_url(), podcast.refresh_hint(), Instant::now());
+                store.add_podcast(podcast);
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        for feed_url in schedule.due(Instant::now()) {
+            let event = refresh_feed(&mut store, &mut schedule, source, &feed_url);
+            if events.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn refresh_feed(
+    store: &mut PodcastStore,
+    schedule: &mut RefreshSchedule,
+    source: &dyn FeedSource,
+    feed_url: &str,
+) -> RefreshEvent {
+    match store.refresh_podcast(feed_url, source) {
+        Ok(refresh) => {
+            let feed_url = match &refresh.moved {
+                Some(feed_move) => {
+                    schedule.rename(&feed_move.from, &feed_move.to);
+                    &feed_move.to
+                }
+                None => feed_url,
+            };
+            let podcast = store.get_podcast(feed_url).cloned().expect("The podcast was just refreshed");
+            let wait = schedule.succeeded(feed_url, podcast.refresh_hint(), Instant::now());
+            info!(
+                "Refreshed {}: {} new episodes, next refresh in {} minutes",
+                redact(feed_url),
+                refresh.new_episodes,
+                wait.as_secs() / 60
+            );
+            RefreshEvent::Refreshed { podcast: Box::new(podcast), refresh }
+        }
+        Err(e) => {
+            let retry_in = schedule.failed(feed_url, Instant::now());
+            warn!("Refreshing {} failed, retrying in {} minutes: {:#}", redact(feed_url), retry_in.as_secs() / 60, e);
+            RefreshEvent::Failed { feed_url: feed_url.to_string(), error: format!("{:#}", e), retry_in }
+        }
+    }
+}
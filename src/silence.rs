@@ -0,0 +1,237 @@
+use anyhow::Result;
+use crate::state_file::{load_json, save_json};
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const DEFAULT_THRESHOLD_DB: f32 = -45.0;
+pub const DEFAULT_MAX_PAUSE: Duration = Duration::from_millis(500);
+
+/// What counts as a pause and how much of it is kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrimSettings {
+    /// Frames whose peak stays below this level (dBFS) are silent.
+    pub threshold_db: f32,
+    /// Pauses are shortened to this length; shorter ones are left alone.
+    pub max_pause: Duration,
+}
+
+impl Default for TrimSettings {
+    fn default() -> Self {
+        Self { threshold_db: DEFAULT_THRESHOLD_DB, max_pause: DEFAULT_MAX_PAUSE }
+    }
+}
+
+/// Silence trimming settings shared by every episode the player starts.
+#[derive(Clone)]
+pub struct SilenceTrimming {
+    pub settings: TrimSettings,
+    pub log: Arc<Mutex<TimeSavedLog>>,
+}
+
+impl SilenceTrimming {
+    pub fn new(settings: TrimSettings, log: TimeSavedLog) -> Self {
+        Self { settings, log: Arc::new(Mutex::new(log)) }
+    }
+}
+
+/// Time saved by trimming, for the current episode and for all episodes ever played.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TimeSaved {
+    pub episode: Duration,
+    pub overall: Duration,
+}
+
+/// Counts the audio a [`TrimSilence`] dropped; shared with the player, which reads it while playing.
+#[derive(Clone, Default)]
+pub struct TrimCounter {
+    /// Microseconds dropped since the last seek, the gap between output and media position.
+    since_seek: Arc<AtomicU64>,
+    /// Microseconds dropped in the whole episode.
+    episode: Arc<AtomicU64>,
+}
+
+impl TrimCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn since_seek(&self) -> Duration {
+        Duration::from_micros(self.since_seek.load(Ordering::Relaxed))
+    }
+
+    pub fn episode(&self) -> Duration {
+        Duration::from_micros(self.episode.load(Ordering::Relaxed))
+    }
+
+    /// Starts counting for a new episode.
+    pub fn reset(&self) {
+        self.since_seek.store(0, Ordering::Relaxed);
+        self.episode.store(0, Ordering::Relaxed);
+    }
+
+    fn add(&self, micros: u64) {
+        self.since_seek.fetch_add(micros, Ordering::Relaxed);
+        self.episode.fetch_add(micros, Ordering::Relaxed);
+    }
+}
+
+/// Shortens pauses longer than [`TrimSettings::max_pause`] by dropping the silent frames past it.
+///
+/// Without settings the source is passed through untouched, so the player can always use it.
+pub struct TrimSilence<S> {
+    source: S,
+    threshold: f32,
+    keep_frames: u64,
+    silent_frames: u64,
+    frame: Vec<f32>,
+    next_in_frame: usize,
+    micros_per_frame: f64,
+    counter: TrimCounter,
+    enabled: bool,
+}
+
+impl<S: Source<Item = f32>> TrimSilence<S> {
+    pub fn new(source: S, settings: Option<TrimSettings>, counter: TrimCounter) -> Self {
+        let sample_rate = source.sample_rate().max(1);
+        let enabled = settings.is_some();
+        let settings = settings.unwrap_or_default();
+        Self {
+            threshold: 10f32.powf(settings.threshold_db / 20.0),
+            keep_frames: (settings.max_pause.as_secs_f64() * sample_rate as f64) as u64,
+            silent_frames: 0,
+            frame: Vec::with_capacity(source.channels() as usize),
+            next_in_frame: 0,
+            micros_per_frame: 1_000_000.0 / sample_rate as f64,
+            counter,
+            enabled,
+            source,
+        }
+    }
+
+    /// Reads one frame (a sample for every channel); returns false at the end of the source.
+    fn read_frame(&mut self) -> bool {
+        self.frame.clear();
+        self.next_in_frame = 0;
+        for _ in 0..self.source.channels().max(1) {
+            match self.source.next() {
+                Some(sample) => self.frame.push(sample),
+                None => break,
+            }
+        }
+        !self.frame.is_empty()
+    }
+
+    fn is_silent(&self) -> bool {
+        self.frame.iter().all(|sample| sample.abs() < self.threshold)
+    }
+
+    fn count_dropped(&self, frames: u64) {
+        if frames > 0 {
+            self.counter.add((frames as f64 * self.micros_per_frame) as u64);
+        }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for TrimSilence<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if !self.enabled {
+            return self.source.next();
+        }
+
+        if self.next_in_frame == self.frame.len() {
+            let mut dropped = 0;
+            loop {
+                if !self.read_frame() {
+                    self.count_dropped(dropped);
+                    return None;
+                }
+                if !self.is_silent() {
+                    self.silent_frames = 0;
+                    break;
+                }
+                self.silent_frames += 1;
+                if self.silent_frames <= self.keep_frames {
+                    break;
+                }
+                dropped += 1;
+            }
+            self.count_dropped(dropped);
+        }
+
+        let sample = self.frame[self.next_in_frame];
+        self.next_in_frame += 1;
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for TrimSilence<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        // Dropping frames makes the remaining length of the inner source meaningless
+        if self.enabled { None } else { self.source.current_frame_len() }
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
+        self.source.try_seek(pos)?;
+        self.frame.clear();
+        self.next_in_frame = 0;
+        self.silent_frames = 0;
+        // The output position restarts at `pos`, so earlier savings no longer offset it
+        self.counter.since_seek.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct TimeSavedFile {
+    total_secs: f64,
+}
+
+/// The time trimming has saved over all episodes, kept in the data directory.
+pub struct TimeSavedLog {
+    path: PathBuf,
+    total: Duration,
+}
+
+impl TimeSavedLog {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file: TimeSavedFile = load_json(&path, "time saved log")?;
+        Ok(Self { path, total: Duration::from_secs_f64(file.total_secs.max(0.0)) })
+    }
+
+    /// Where the log lives inside the data directory.
+    pub fn default_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("time_saved.json")
+    }
+
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Adds the time saved in a finished episode and writes the new total.
+    pub fn add(&mut self, saved: Duration) -> Result<()> {
+        if saved.is_zero() {
+            return Ok(());
+        }
+        self.total += saved;
+        save_json(&self.path, &TimeSavedFile { total_secs: self.total.as_secs_f64() })
+    }
+}
@@ -0,0 +1,96 @@
+use crate::chapters::{chapter_at, Chapter};
+use crate::volume::amplitude;
+use anyhow::{anyhow, bail, Result};
+use std::time::Duration;
+
+/// The volume fades out over this much time before the timer stops playback.
+pub const FADE_DURATION: Duration = Duration::from_secs(30);
+
+/// How much a sleep key press adds to the timer.
+pub const DEFAULT_SLEEP_STEP: Duration = Duration::from_secs(15 * 60);
+
+/// When a sleep timer stops playback.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SleepMode {
+    /// After this much listening time.
+    After(Duration),
+    EndOfEpisode,
+    EndOfChapter,
+}
+
+/// A running sleep timer.
+///
+/// A timer for a number of minutes counts listening time, so it stands still while paused. The
+/// end-of-episode and end-of-chapter timers wait for a position in the episode instead, and
+/// their remaining time depends on the playback speed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SleepTimer {
+    deadline: Deadline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Deadline {
+    Remaining(Duration),
+    Position(Duration),
+}
+
+impl SleepTimer {
+    /// Starts a timer while playing at `position`; the episode's duration and chapters are
+    /// needed for the timers that wait for their end.
+    pub fn new(mode: SleepMode, position: Duration, duration: Option<Duration>, chapters: &[Chapter]) -> Result<Self> {
+        let deadline = match mode {
+            SleepMode::After(time) => {
+                if time.is_zero() {
+                    bail!("The sleep timer needs a time greater than zero");
+                }
+                Deadline::Remaining(time)
+            }
+            SleepMode::EndOfEpisode => Deadline::Position(
+                duration.ok_or_else(|| anyhow!("Cannot sleep at the end of the episode: its duration is unknown"))?,
+            ),
+            SleepMode::EndOfChapter => {
+                if chapters.is_empty() {
+                    bail!("Cannot sleep at the end of the chapter: this episode has no chapters");
+                }
+                let chapter = chapter_at(chapters, position)
+                    .ok_or_else(|| anyhow!("Cannot sleep at the end of the chapter: no chapter is playing"))?;
+                Deadline::Position(chapter.end)
+            }
+        };
+        Ok(Self { deadline })
+    }
+
+    /// Whether the timer refers to a position in the episode that is playing.
+    pub fn is_episode_bound(&self) -> bool {
+        matches!(self.deadline, Deadline::Position(_))
+    }
+
+    /// Counts down `played` listening time.
+    pub fn elapse(&mut self, played: Duration) {
+        if let Deadline::Remaining(remaining) = &mut self.deadline {
+            *remaining = remaining.saturating_sub(played);
+        }
+    }
+
+    /// Listening time left at `position` when playing at `speed`.
+    pub fn remaining(&self, position: Duration, speed: f32) -> Duration {
+        match self.deadline {
+            Deadline::Remaining(remaining) => remaining,
+            Deadline::Position(end) => end.saturating_sub(position).div_f32(speed.max(f32::EPSILON)),
+        }
+    }
+
+    /// Adds time to the timer. A timer waiting for a position becomes a plain countdown.
+    pub fn extend(&mut self, by: Duration, position: Duration, speed: f32) {
+        self.deadline = Deadline::Remaining(self.remaining(position, speed) + by);
+    }
+}
+
+/// The amplitude for the fade-out with `remaining` time left: unchanged until the last
+/// [`FADE_DURATION`], then down to silence in equal steps of perceived loudness.
+pub fn fade_gain(remaining: Duration) -> f32 {
+    if remaining >= FADE_DURATION {
+        return 1.0;
+    }
+    amplitude(remaining.as_secs_f32() / FADE_DURATION.as_secs_f32())
+}
@@ -0,0 +1,42 @@
+//! Small JSON state files kept in the data directory, like the loudness cache.
+
+use anyhow::{Context, Result};
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+/// Reads the state at `path`, or the default when there is none yet. Losing this state only
+/// costs starting over, so a corrupt file is ignored with a warning instead of failing.
+pub fn load_json<T: Default + DeserializeOwned>(path: &Path, what: &str) -> Result<T> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring corrupt {} {}: {}", what, path.display(), e);
+            T::default()
+        })),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {} {}", what, path.display())),
+    }
+}
+
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    write_atomically(path, &serde_json::to_string_pretty(value)?)
+}
+
+/// Replaces `path` with `content` so readers see either the old or the new file, never a
+/// partial one: the content goes to a uniquely named file next to it, with the same
+/// permissions, is flushed to disk and then renamed over `path`.
+pub fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir)?;
+    let mut temp = NamedTempFile::new_in(dir).with_context(|| format!("Failed to create a file in {}", dir.display()))?;
+    if let Ok(metadata) = std::fs::metadata(path) {
+        temp.as_file().set_permissions(metadata.permissions())?;
+    }
+    temp.write_all(content.as_bytes())?;
+    temp.as_file().sync_all()?;
+    temp.persist(path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,442 @@
+//! The subscription file, in one of two formats.
+//!
+//! The line format lists one feed URL per line; blank lines and lines starting with `#`, `//`
+//! or `--` are skipped. The TOML format has a `[[feed]]` table per feed, with its settings:
+//!
+//! ```toml
+//! [[feed]]
+//! url = "https://example.com/feed.xml"
+//! title = "Example Show"
+//! folder = "News"
+//! tags = ["daily", "politics"]
+//! speed = 1.5
+//! skip_intro_secs = 30
+//! auto_download = 3
+//! disabled = false
+//! ```
+//!
+//! A file with any line starting with `[` is read as TOML, anything else as lines. Changes
+//! are written back in place, keeping comments and the order of feeds.
+
+use crate::audio_player::{MAX_SPEED, MIN_SPEED};
+use crate::credentials::redact;
+use crate::feed_source::same_feed;
+use crate::state_file::write_atomically;
+use anyhow::{anyhow, bail, Context, Result};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use toml_edit::{value, Array, DocumentMut, Item, Table};
+
+lazy_static::lazy_static! {
+    /// Held across every read-modify-write of a subscription file, so edits from the TUI and
+    /// the refresh cannot interleave and lose one another.
+    static ref WRITES: Mutex<()> = Mutex::new(());
+}
+
+/// Query parameters that only track where a link was clicked.
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "_hsenc", "_hsmi"];
+
+/// Schemes podcast apps use in subscribe links, which stand for plain HTTP(S).
+const PODCAST_SCHEMES: &[&str] = &["feed", "itpc", "pcast", "podcast"];
+
+/// A feed in the subscription file.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Subscription {
+    pub url: String,
+    pub settings: FeedSettings,
+}
+
+impl Subscription {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), settings: FeedSettings::default() }
+    }
+}
+
+/// What a subscriber chose for one feed. Unset values fall back to the feed and the config.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct FeedSettings {
+    /// Shown instead of the title the feed gives itself.
+    pub title: Option<String>,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+    /// Playback speed for this feed's episodes, instead of `playback.default_speed`.
+    pub speed: Option<f32>,
+    /// Episodes start this far in, past a fixed-length intro.
+    pub skip_intro_secs: Option<u64>,
+    /// How many of the newest episodes to keep downloaded. Reserved: read and kept, but
+    /// nothing downloads episodes ahead yet.
+    pub auto_download: Option<usize>,
+    /// Stays in the file but is not loaded.
+    pub disabled: bool,
+}
+
+impl FeedSettings {
+    pub fn skip_intro(&self) -> Option<Duration> {
+        self.skip_intro_secs.filter(|&secs| secs > 0).map(Duration::from_secs)
+    }
+
+    /// Whether the feed has `tag` among its tags or as its folder, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().chain(&self.folder).any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SubscriptionFile {
+    #[serde(default)]
+    feed: Vec<FeedTable>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FeedTable {
+    url: String,
+    title: Option<String>,
+    folder: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    speed: Option<f32>,
+    skip_intro_secs: Option<u64>,
+    auto_download: Option<usize>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+impl From<FeedTable> for Subscription {
+    fn from(table: FeedTable) -> Self {
+        Self {
+            url: table.url.trim().to_string(),
+            settings: FeedSettings {
+                title: table.title.filter(|title| !title.trim().is_empty()),
+                folder: table.folder.filter(|folder| !folder.trim().is_empty()),
+                tags: table.tags,
+                speed: table.speed,
+                skip_intro_secs: table.skip_intro_secs,
+                auto_download: table.auto_download,
+                disabled: table.disabled,
+            },
+        }
+    }
+}
+
+/// Reads every subscription in the file, disabled ones included.
+pub fn read_subscriptions(filename: &str) -> Result<Vec<Subscription>> {
+    let content = std::fs::read_to_string(filename)?;
+    parse_subscriptions(&content).with_context(|| format!("Invalid subscription file {}", filename))
+}
+
+/// Parses a subscription file in either format.
+pub fn parse_subscriptions(content: &str) -> Result<Vec<Subscription>> {
+    if !is_toml(content) {
+        return Ok(content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !is_line_comment(line))
+            .map(Subscription::new)
+            .collect());
+    }
+
+    let file: SubscriptionFile = toml::from_str(content)?;
+    let subscriptions: Vec<Subscription> = file.feed.into_iter().map(Subscription::from).collect();
+    for (i, subscription) in subscriptions.iter().enumerate() {
+        if subscription.url.is_empty() {
+            bail!("Feed {} has no URL", i + 1);
+        }
+        if let Some(speed) = subscription.settings.speed.filter(|speed| !(MIN_SPEED..=MAX_SPEED).contains(speed)) {
+            bail!("Feed {} has a speed of {}; it must be between {} and {}", i + 1, speed, MIN_SPEED, MAX_SPEED);
+        }
+    }
+    Ok(subscriptions)
+}
+
+/// Whether `content` is in the TOML format rather than the line format.
+pub fn is_toml(content: &str) -> bool {
+    content.lines().any(|line| line.trim_start().starts_with('['))
+}
+
+/// The canonical form of a feed URL: `https://` when it has no scheme, podcast app schemes
+/// such as `feed://` turned into `https://`, no fragment, tracking parameters (`utm_*` and
+/// the like) or trailing slash. Fails for anything but HTTP(S) URLs.
+pub fn normalize_feed_url(url: &str) -> Result<String> {
+    let url = url.trim();
+    let with_scheme = match url.split_once("://") {
+        Some((scheme, rest)) if PODCAST_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) => format!("https://{}", rest),
+        Some(_) => url.to_string(),
+        // `mailto:x` has a scheme, `example.com:8080/feed` a port
+        None if has_scheme(url) => url.to_string(),
+        None => format!("https://{}", url),
+    };
+    let mut parsed = Url::parse(&with_scheme).with_context(|| format!("Invalid feed URL {}", redact(url)))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none_or(str::is_empty) {
+        bail!("Invalid feed URL {}: only http and https feeds are supported", redact(url));
+    }
+
+    parsed.set_fragment(None);
+    if parsed.query_pairs().any(|(name, _)| is_tracking(&name)) {
+        let pairs: Vec<(String, String)> = parsed
+            .query_pairs()
+            .filter(|(name, _)| !is_tracking(name))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        parsed.set_query(None);
+        if !pairs.is_empty() {
+            parsed.query_pairs_mut().extend_pairs(pairs);
+        }
+    }
+    if parsed.query() == Some("") {
+        parsed.set_query(None);
+    }
+    let path = parsed.path().to_string();
+    if path.len() > 1 && path.ends_with('/') {
+        parsed.set_path(path.trim_end_matches('/'));
+    }
+    Ok(parsed.into())
+}
+
+fn has_scheme(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|parsed| {
+        let rest = &url[parsed.scheme().len() + 1..];
+        !rest.starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+fn is_tracking(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+/// Appends a feed to the subscription file in the file's own format, creating the file
+/// when needed. Returns false, leaving the file alone, when the feed is already listed.
+pub fn add_subscription(filename: &str, feed_url: &str) -> Result<bool> {
+    let _writing = lock_writes();
+    let content = read_or_empty(filename)?;
+    let subscriptions = parse_subscriptions(&content).with_context(|| format!("Invalid subscription file {}", filename))?;
+    if subscriptions.iter().any(|subscription| same_feed(&subscription.url, feed_url)) {
+        return Ok(false);
+    }
+
+    let separator = if content.is_empty() || content.ends_with('\n') { "" } else { "\n" };
+    let entry = if is_toml(&content) {
+        format!("\n[[feed]]\nurl = {}\n", toml::Value::String(feed_url.to_string()))
+    } else {
+        format!("{}\n", feed_url)
+    };
+    write_atomically(Path::new(filename), &format!("{}{}{}", content, separator, entry))?;
+    Ok(true)
+}
+
+/// Takes a feed out of the subscription file, leaving every other line as it was.
+/// Returns false when the feed is not listed.
+pub fn remove_subscription(filename: &str, feed_url: &str) -> Result<bool> {
+    let _writing = lock_writes();
+    let content = read_or_empty(filename)?;
+    let Some(updated) = without_feed(filename, &content, feed_url)? else {
+        return Ok(false);
+    };
+    write_atomically(Path::new(filename), &updated)?;
+    Ok(true)
+}
+
+/// Points a listed feed at the URL it moved to, keeping its settings and its place in the
+/// file. When a different entry already has the new URL, that one stays and the old one
+/// goes. Returns false, leaving the file alone, when the old URL is not listed or is the
+/// same feed as the new one, e.g. after a redirect from HTTP to HTTPS.
+pub fn move_subscription(filename: &str, from: &str, to: &str) -> Result<bool> {
+    let _writing = lock_writes();
+    let content = read_or_empty(filename)?;
+    let subscriptions = parse_subscriptions(&content).with_context(|| format!("Invalid subscription file {}", filename))?;
+    let listed = |url: &str| subscriptions.iter().any(|subscription| same_feed(&subscription.url, url));
+    if same_feed(from, to) || !listed(from) {
+        return Ok(false);
+    }
+
+    let updated = if listed(to) {
+        without_feed(filename, &content, from)?.expect("The old URL is listed")
+    } else if is_toml(&content) {
+        let mut document = parse_document(filename, &content)?;
+        if let Some(feeds) = document.get_mut("feed").and_then(Item::as_array_of_tables_mut) {
+            for table in feeds.iter_mut().filter(|table| table_is_feed(table, from)) {
+                let url = table.get_mut("url").and_then(Item::as_value_mut).expect("Matched by its url");
+                // Keeps the comment after the URL, if any
+                let decor = url.decor().clone();
+                *url = to.into();
+                *url.decor_mut() = decor;
+            }
+        }
+        document.to_string()
+    } else {
+        content
+            .lines()
+            .map(|line| {
+                if is_line_comment(line) || line.trim().is_empty() || !same_feed(line, from) {
+                    format!("{}\n", line)
+                } else {
+                    format!("{}\n", to)
+                }
+            })
+            .collect()
+    };
+    write_atomically(Path::new(filename), &updated)?;
+    Ok(true)
+}
+
+/// Changes the settings of a listed feed in place, keeping comments and the order of feeds.
+/// A file in the line format is turned into TOML first, since lines cannot hold settings.
+/// Returns what `edit` returned, or fails when the feed is not listed.
+pub fn update_subscription<T>(filename: &str, feed_url: &str, edit: impl FnOnce(&mut SubscriptionEntry) -> T) -> Result<T> {
+    let _writing = lock_writes();
+    let content = read_or_empty(filename)?;
+    let content = if is_toml(&content) { content } else { lines_to_toml(&content) };
+    let mut document = parse_document(filename, &content)?;
+    let table = document
+        .get_mut("feed")
+        .and_then(Item::as_array_of_tables_mut)
+        .and_then(|feeds| feeds.iter_mut().find(|table| table_is_feed(table, feed_url)))
+        .ok_or_else(|| anyhow!("{} is not in the subscription file {}", redact(feed_url), filename))?;
+    let result = edit(&mut SubscriptionEntry { table });
+    write_atomically(Path::new(filename), &document.to_string())?;
+    Ok(result)
+}
+
+/// One `[[feed]]` table of the subscription file, being edited.
+pub struct SubscriptionEntry<'a> {
+    table: &'a mut Table,
+}
+
+impl SubscriptionEntry<'_> {
+    /// Sets the custom title, or removes it for `None`.
+    pub fn set_title(&mut self, title: Option<&str>) {
+        match title.map(str::trim).filter(|title| !title.is_empty()) {
+            Some(title) => self.table["title"] = value(title),
+            None => {
+                self.table.remove("title");
+            }
+        }
+    }
+
+    /// Adds a tag; false when the feed already has it.
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        let tag = tag.trim();
+        let tags = self.table.entry("tags").or_insert_with(|| value(Array::new()));
+        let Some(tags) = tags.as_array_mut() else {
+            *tags = value(Array::from_iter([tag]));
+            return true;
+        };
+        if tags.iter().any(|t| t.as_str().is_some_and(|t| t.eq_ignore_ascii_case(tag))) {
+            return false;
+        }
+        tags.push(tag);
+        true
+    }
+
+    /// Removes a tag; false when the feed did not have it.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let Some(tags) = self.table.get_mut("tags").and_then(Item::as_array_mut) else {
+            return false;
+        };
+        let before = tags.len();
+        tags.retain(|t| !t.as_str().is_some_and(|t| t.eq_ignore_ascii_case(tag.trim())));
+        let removed = tags.len() != before;
+        if tags.is_empty() {
+            self.table.remove("tags");
+        }
+        removed
+    }
+}
+
+/// The file without the feed, or `None` when the feed is not listed.
+fn without_feed(filename: &str, content: &str, feed_url: &str) -> Result<Option<String>> {
+    let updated = if is_toml(content) {
+        let mut document = parse_document(filename, content)?;
+        let mut removed = false;
+        while let Some(feeds) = document.get_mut("feed").and_then(Item::as_array_of_tables_mut) {
+            let Some(index) = feeds.iter().position(|table| table_is_feed(table, feed_url)) else {
+                break;
+            };
+            // Comments above the table belong to the file, not the feed, so they stay
+            let comments = decor_prefix(feeds.get(index));
+            feeds.remove(index);
+            removed = true;
+            match feeds.get_mut(index) {
+                Some(next) => {
+                    let prefix = format!("{}{}", comments, decor_prefix(Some(next)));
+                    next.decor_mut().set_prefix(prefix);
+                }
+                None => {
+                    let trailing = format!("{}{}", comments, document.trailing().as_str().unwrap_or_default());
+                    document.set_trailing(trailing);
+                }
+            }
+        }
+        if !removed {
+            return Ok(None);
+        }
+        document.to_string()
+    } else {
+        let mut removed = false;
+        let lines: Vec<&str> = content
+            .lines()
+            .filter(|line| {
+                let matches = !is_line_comment(line) && !line.trim().is_empty() && same_feed(line, feed_url);
+                removed |= matches;
+                !matches
+            })
+            .collect();
+        if !removed {
+            return Ok(None);
+        }
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    };
+    Ok(Some(updated))
+}
+
+fn decor_prefix(table: Option<&Table>) -> String {
+    table.and_then(|table| table.decor().prefix()).and_then(|prefix| prefix.as_str()).unwrap_or_default().to_string()
+}
+
+fn table_is_feed(table: &Table, feed_url: &str) -> bool {
+    table.get("url").and_then(Item::as_str).is_some_and(|url| same_feed(url, feed_url))
+}
+
+fn parse_document(filename: &str, content: &str) -> Result<DocumentMut> {
+    // Validates the entries too, so edits never start from a file that does not load
+    parse_subscriptions(content).with_context(|| format!("Invalid subscription file {}", filename))?;
+    content.parse().with_context(|| format!("Invalid subscription file {}", filename))
+}
+
+fn is_line_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('#') || line.starts_with("//") || line.starts_with("--")
+}
+
+/// The line format as TOML, with its comments turned into TOML comments.
+fn lines_to_toml(content: &str) -> String {
+    let mut toml = String::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() {
+            toml.push('\n');
+        } else if is_line_comment(line) {
+            let text = line.trim_start_matches('#').trim_start_matches("//").trim_start_matches("--");
+            toml.push_str(&format!("#{}\n", text));
+        } else {
+            toml.push_str(&format!("[[feed]]\nurl = {}\n", toml::Value::String(line.to_string())));
+        }
+    }
+    toml
+}
+
+fn read_or_empty(filename: &str) -> Result<String> {
+    match std::fs::read_to_string(filename) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(anyhow::Error::new(e).context(format!("Failed to read {}", filename))),
+    }
+}
+
+fn lock_writes() -> MutexGuard<'static, ()> {
+    WRITES.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -1,118 +1,796 @@
 use anyhow::Result;
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
-    style::{Style, Color},
+    layout::{Constraint, Direction, Layout, Position, Rect},
+    style::{Style, Color, Modifier},
     text::Text,
-    widgets::{Block, Borders, List, ListItem},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph},
     Terminal,
 };
 use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    event::{self, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
 };
+use rss_reader::audio_control::{percent_of_duration, start_episode};
+use rss_reader::audio_player::AudioPlayerTrait;
+use rss_reader::config::Theme;
+use rss_reader::directory::{DirectoryProvider, DirectoryResult};
+use rss_reader::episodes::{format_duration, Episode};
+use rss_reader::key_bindings::{Bindings, ChordMatcher, KeyChord, KeyPress, TuiAction};
+use rss_reader::credentials::redact;
+use rss_reader::podcast_manager::{Podcast, Refresh};
+use rss_reader::refresh_scheduler::{BackgroundRefresh, RefreshEvent};
+use rss_reader::silence::TimeSaved;
+use rss_reader::sleep_timer::DEFAULT_SLEEP_STEP;
 use std::io::{stdout, Stdout};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How often the progress bar is redrawn while no input arrives.
+const TICK: Duration = Duration::from_millis(250);
+
+/// Alternating row colors for the podcast and episode panes.
+struct Palette {
+    left: (Color, Color),
+    right: (Color, Color),
+    progress: Color,
+}
+
+impl Palette {
+    fn for_theme(theme: Theme) -> Self {
+        match theme {
+            Theme::Default => Self {
+                left: (Color::Green, Color::LightGreen),
+                right: (Color::Blue, Color::LightBlue),
+                progress: Color::Cyan,
+            },
+            Theme::Light => Self {
+                left: (Color::Black, Color::DarkGray),
+                right: (Color::Blue, Color::Magenta),
+                progress: Color::Blue,
+            },
+            Theme::Mono => Self {
+                left: (Color::Reset, Color::Reset),
+                right: (Color::Reset, Color::Reset),
+                progress: Color::Reset,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pane {
+    Left,
+    Right,
+}
+
+/// What the now-playing bar shows.
+#[derive(Default)]
+struct NowPlaying {
+    title: Option<String>,
+    position: Duration,
+    duration: Option<Duration>,
+    paused: bool,
+    volume: f32,
+    muted: bool,
+    saved: Option<TimeSaved>,
+    sleep_remaining: Option<Duration>,
+    status: String,
+}
+
+/// Subscribes to a directory result, returning the new podcast, or `None` when it is already
+/// subscribed. Called from a worker thread.
+pub type Subscriber = Arc<dyn Fn(&DirectoryResult) -> Result<Option<Podcast>> + Send + Sync>;
+
+/// Where the "Add podcast" dialog searches and how it subscribes.
+struct Directory {
+    provider: Arc<dyn DirectoryProvider>,
+    limit: usize,
+    subscribe: Subscriber,
+}
+
+/// What a directory worker thread sends back when it is done.
+enum DirectoryReply {
+    Searched { query: String, results: Result<Vec<DirectoryResult>> },
+    Subscribed { title: String, podcast: Result<Option<Box<Podcast>>> },
+}
+
+/// The "Add podcast" dialog: a search box over the directory and the shows it found.
+#[derive(Default)]
+struct AddDialog {
+    query: String,
+    /// The query the results are for; Enter searches again once the query differs.
+    searched: Option<String>,
+    results: Vec<DirectoryResult>,
+    state: ListState,
+    message: String,
+}
 
 pub struct Tui {
     terminal: Terminal<CrosstermBackend<Stdout>>,
-    left_items: Vec<String>,
-    right_items: Vec<String>,
+    podcasts: Vec<Podcast>,
+    left_state: ListState,
+    right_state: ListState,
+    focus: Pane,
+    show_help: bool,
+    palette: Palette,
+    bindings: Bindings<TuiAction>,
+    chords: ChordMatcher,
+    volume_step: f32,
+    /// The speed for feeds without one of their own.
+    default_speed: f32,
+    now_playing: NowPlaying,
+    /// Where the progress bar was last drawn, for mapping mouse clicks to positions.
+    progress_area: Rect,
+    /// The percentage under the mouse while the progress bar is being dragged.
+    drag_percent: Option<f32>,
+    directory: Option<Directory>,
+    add_dialog: Option<AddDialog>,
+    /// The search or subscription running in the background, if any. Kept when the dialog
+    /// closes, so a subscription that goes through still shows up.
+    directory_work: Option<Receiver<DirectoryReply>>,
+    background_refresh: Option<BackgroundRefresh>,
 }
 
 impl Tui {
-    pub fn new(left_items: Vec<String>, right_items: Vec<String>) -> Result<Self> {
+    pub fn new(
+        podcasts: Vec<Podcast>,
+        theme: Theme,
+        bindings: Bindings<TuiAction>,
+        volume_step: f32,
+        default_speed: f32,
+    ) -> Result<Self> {
         enable_raw_mode()?;
-        execute!(stdout(), EnterAlternateScreen)?;
-        
+        execute!(stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+
         let backend = CrosstermBackend::new(stdout());
         let terminal = Terminal::new(backend)?;
 
-        Ok(Self { 
-            terminal, 
-            left_items, 
-            right_items 
+        Ok(Self {
+            terminal,
+            podcasts,
+            left_state: ListState::default().with_selected(Some(0)),
+            right_state: ListState::default().with_selected(Some(0)),
+            focus: Pane::Left,
+            show_help: false,
+            palette: Palette::for_theme(theme),
+            bindings,
+            chords: ChordMatcher::new(),
+            volume_step,
+            default_speed,
+            now_playing: NowPlaying::default(),
+            progress_area: Rect::default(),
+            drag_percent: None,
+            directory: None,
+            add_dialog: None,
+            directory_work: None,
+            background_refresh: None,
         })
     }
 
-    pub fn run(&mut self) -> Result<()> {
+    /// Shows a message in the status bar until the next one replaces it.
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.now_playing.status = status.into();
+    }
+
+    /// Enables the "Add podcast" dialog, searching `provider` for up to `limit` shows.
+    pub fn set_directory(
+        &mut self,
+        provider: Box<dyn DirectoryProvider>,
+        limit: usize,
+        subscribe: impl Fn(&DirectoryResult) -> Result<Option<Podcast>> + Send + Sync + 'static,
+    ) {
+        self.directory = Some(Directory { provider: Arc::from(provider), limit, subscribe: Arc::new(subscribe) });
+    }
+
+    /// Shows what `refresh` finds while the TUI is open; it stops when the TUI does.
+    pub fn set_background_refresh(&mut self, refresh: BackgroundRefresh) {
+        self.background_refresh = Some(refresh);
+    }
+
+    pub fn run<P: AudioPlayerTrait>(&mut self, player: &mut P) -> Result<()> {
         loop {
+            self.apply_refreshes();
+            match player.check_output_device() {
+                Ok(Some(device)) => self.now_playing.status = format!("Output device went away, now playing to {}", device),
+                Ok(None) => {}
+                Err(e) => self.now_playing.status = format!("⚠️  {}", e),
+            }
+            self.apply_directory_reply();
+            self.refresh_now_playing(player);
             self.draw()?;
 
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => break,
-                    _ => {}
+            if !event::poll(TICK)? {
+                continue;
+            }
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    let action = if self.add_dialog.is_some() {
+                        self.handle_dialog_key(key)
+                    } else {
+                        self.chords.feed(KeyPress::from(key), &self.bindings)
+                    };
+                    match action {
+                        Some(TuiAction::Quit) => break,
+                        Some(action) => self.handle_action(action, player),
+                        None => {}
+                    }
                 }
+                Event::Mouse(mouse) => self.handle_mouse(mouse, player),
+                _ => {}
             }
         }
 
-        self.cleanup()?;
-        Ok(())
+        player.stop()
+    }
+
+    fn handle_action<P: AudioPlayerTrait>(&mut self, action: TuiAction, player: &mut P) {
+        let len = match self.focus {
+            Pane::Left => self.podcasts.len(),
+            Pane::Right => self.episodes().len(),
+        };
+        let state = match self.focus {
+            Pane::Left => &mut self.left_state,
+            Pane::Right => &mut self.right_state,
+        };
+        let last = len.saturating_sub(1);
+
+        match action {
+            TuiAction::Up => state.select(Some(state.selected().unwrap_or(0).saturating_sub(1))),
+            TuiAction::Down => state.select(Some((state.selected().unwrap_or(0) + 1).min(last))),
+            TuiAction::Top => state.select(Some(0)),
+            TuiAction::Bottom => state.select(Some(last)),
+            TuiAction::SwitchPane => {
+                self.focus = match self.focus {
+                    Pane::Left => Pane::Right,
+                    Pane::Right => Pane::Left,
+                }
+            }
+            TuiAction::Select => match self.focus {
+                Pane::Left => {
+                    self.right_state.select(Some(0));
+                    self.focus = Pane::Right;
+                }
+                Pane::Right => self.play_selected(player),
+            },
+            TuiAction::PlayPause => {
+                if let Err(e) = player.pause() {
+                    self.now_playing.status = format!("⚠️  {}", e);
+                }
+            }
+            TuiAction::VolumeUp | TuiAction::VolumeDown | TuiAction::Mute => {
+                let result = match action {
+                    TuiAction::VolumeUp => player.adjust_volume(self.volume_step),
+                    TuiAction::VolumeDown => player.adjust_volume(-self.volume_step),
+                    _ => player.toggle_mute(),
+                };
+                if let Err(e) = result {
+                    self.now_playing.status = format!("⚠️  {}", e);
+                }
+            }
+            TuiAction::Sleep => {
+                self.now_playing.status = match player.extend_sleep_timer(DEFAULT_SLEEP_STEP) {
+                    Ok(()) => "Sleep timer extended".to_string(),
+                    Err(e) => format!("⚠️  {}", e),
+                };
+            }
+            TuiAction::CancelSleep => {
+                player.cancel_sleep_timer();
+                self.now_playing.status = "Sleep timer cancelled".to_string();
+            }
+            TuiAction::AddPodcast => match &self.directory {
+                Some(directory) => {
+                    self.add_dialog = Some(AddDialog {
+                        message: format!("Type a name and press Enter to search {}", directory.provider.name()),
+                        ..AddDialog::default()
+                    })
+                }
+                None => self.now_playing.status = "⚠️  No podcast directory configured".to_string(),
+            },
+            TuiAction::Help => self.show_help = !self.show_help,
+            TuiAction::Quit => {}
+        }
+
+        // Moving through podcasts starts their episode list from the top
+        if self.focus == Pane::Left && matches!(action, TuiAction::Up | TuiAction::Down | TuiAction::Top | TuiAction::Bottom) {
+            self.right_state.select(Some(0));
+        }
+    }
+
+    /// Typing edits the query, Up and Down pick a result, Enter searches or subscribes, Esc closes.
+    /// A key bound to quitting with a modifier, like Ctrl+C, still quits.
+    fn handle_dialog_key(&mut self, key: KeyEvent) -> Option<TuiAction> {
+        let Some(dialog) = &mut self.add_dialog else {
+            return None;
+        };
+        let last = dialog.results.len().saturating_sub(1);
+        let busy = self.directory_work.is_some();
+        // Shift is part of the character; other modifiers make it a shortcut, not text
+        let typed = key.modifiers.difference(KeyModifiers::SHIFT).is_empty();
+        match key.code {
+            _ if !typed && self.bindings.action_for(&KeyChord(vec![KeyPress::from(key)])) == Some(TuiAction::Quit) => {
+                return Some(TuiAction::Quit);
+            }
+            KeyCode::Esc => self.add_dialog = None,
+            KeyCode::Up => dialog.state.select(Some(dialog.state.selected().unwrap_or(0).saturating_sub(1))),
+            KeyCode::Down => dialog.state.select(Some((dialog.state.selected().unwrap_or(0) + 1).min(last))),
+            KeyCode::Backspace => {
+                dialog.query.pop();
+            }
+            KeyCode::Char(c) if typed => dialog.query.push(c),
+            KeyCode::Enter if busy => {}
+            KeyCode::Enter if dialog.searched.as_deref() == Some(dialog.query.trim()) && !dialog.results.is_empty() => {
+                self.subscribe_selected()
+            }
+            KeyCode::Enter if !dialog.query.trim().is_empty() => self.search_directory(),
+            _ => {}
+        }
+        None
+    }
+
+    /// Starts searching the directory in the background; the results arrive on a later tick.
+    fn search_directory(&mut self) {
+        let (Some(directory), Some(dialog)) = (&self.directory, &mut self.add_dialog) else {
+            return;
+        };
+        let query = dialog.query.trim().to_string();
+        dialog.message = format!("Searching {}…", directory.provider.name());
+
+        let (provider, limit) = (Arc::clone(&directory.provider), directory.limit);
+        self.directory_work = Some(spawn_directory_work(move || {
+            let results = provider.search(&query, limit);
+            DirectoryReply::Searched { query, results }
+        }));
+    }
+
+    /// Starts subscribing to the selected show in the background.
+    fn subscribe_selected(&mut self) {
+        let (Some(directory), Some(dialog)) = (&self.directory, &mut self.add_dialog) else {
+            return;
+        };
+        let Some(result) = dialog.state.selected().and_then(|i| dialog.results.get(i)).cloned() else {
+            return;
+        };
+        dialog.message = format!("Subscribing to {}…", result.title);
+
+        let subscribe = Arc::clone(&directory.subscribe);
+        self.directory_work = Some(spawn_directory_work(move || {
+            let podcast = subscribe(&result).map(|podcast| podcast.map(Box::new));
+            DirectoryReply::Subscribed { title: result.title, podcast }
+        }));
+    }
+
+    /// Takes in the directory worker's reply once it is there.
+    fn apply_directory_reply(&mut self) {
+        let Some(work) = &self.directory_work else {
+            return;
+        };
+        let reply = match work.try_recv() {
+            Ok(reply) => reply,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.directory_work = None;
+                self.now_playing.status = "⚠️  The podcast directory stopped unexpectedly".to_string();
+                return;
+            }
+        };
+        self.directory_work = None;
+
+        match reply {
+            DirectoryReply::Searched { query, results } => {
+                // The dialog may have been closed while searching
+                let Some(dialog) = &mut self.add_dialog else {
+                    return;
+                };
+                match results {
+                    Ok(results) => {
+                        dialog.message = match results.len() {
+                            0 => format!("No podcasts found for \"{}\"", query),
+                            n => format!("{} podcasts found; press Enter to subscribe", n),
+                        };
+                        dialog.results = results;
+                    }
+                    Err(e) => {
+                        dialog.message = format!("⚠️  {:#}", e);
+                        dialog.results.clear();
+                    }
+                }
+                dialog.state.select(Some(0));
+                dialog.searched = Some(query);
+            }
+            DirectoryReply::Subscribed { title, podcast } => match podcast {
+                Ok(Some(podcast)) => {
+                    self.now_playing.status = format!("Subscribed to {} ({} episodes)", podcast.title(), podcast.episodes().len());
+                    if let Some(background_refresh) = &self.background_refresh {
+                        background_refresh.add((*podcast).clone());
+                    }
+                    self.podcasts.push(*podcast);
+                    self.left_state.select(Some(self.podcasts.len() - 1));
+                    self.right_state.select(Some(0));
+                    self.focus = Pane::Left;
+                    self.add_dialog = None;
+                }
+                Ok(None) => {
+                    self.now_playing.status = format!("Already subscribed to {}", title);
+                    self.add_dialog = None;
+                }
+                Err(e) => match &mut self.add_dialog {
+                    Some(dialog) => dialog.message = format!("⚠️  {:#}", e),
+                    None => self.now_playing.status = format!("⚠️  {:#}", e),
+                },
+            },
+        }
+    }
+
+    /// Takes in what the background refresh found since the last tick.
+    fn apply_refreshes(&mut self) {
+        let Some(background_refresh) = &self.background_refresh else {
+            return;
+        };
+        for event in background_refresh.events() {
+            match event {
+                RefreshEvent::Refreshed { podcast, refresh } => self.replace_podcast(*podcast, &refresh),
+                RefreshEvent::Failed { feed_url, error, retry_in } => {
+                    let name = self
+                        .podcasts
+                        .iter()
+                        .find(|podcast| podcast.feed_url() == feed_url)
+                        .map_or_else(|| redact(&feed_url), |podcast| podcast.title().to_string());
+                    self.now_playing.status = format!(
+                        "⚠️  Refreshing {} failed, retrying in {}: {}",
+                        name,
+                        format_duration(retry_in),
+                        error
+                    );
+                }
+            }
+        }
+    }
+
+    /// Swaps in a refreshed podcast, keeping the selected episode selected.
+    fn replace_podcast(&mut self, mut podcast: Podcast, refresh: &Refresh) {
+        let old_url = refresh.moved.as_ref().map_or(podcast.feed_url(), |feed_move| feed_move.from.as_str());
+        let index = self
+            .podcasts
+            .iter()
+            .position(|known| known.feed_url() == podcast.feed_url() || known.feed_url() == old_url);
+        let index = match index {
+            Some(index) => index,
+            None => return,
+        };
+
+        if let Some(feed_move) = &refresh.moved {
+            self.now_playing.status = feed_move.to_string();
+        }
+        if refresh.new_episodes > 0 {
+            self.now_playing.status = match refresh.new_episodes {
+                1 => format!("🆕 1 new episode of {}", podcast.title()),
+                n => format!("🆕 {} new episodes of {}", n, podcast.title()),
+            };
+        }
+
+        let selected_episode = if self.left_state.selected() == Some(index) {
+            self.right_state.selected().and_then(|i| self.episodes().get(i)).map(Episode::key)
+        } else {
+            None
+        };
+        // Durations measured while playing are only in this copy of the podcast
+        podcast.keep_episode_state(self.podcasts[index].episodes());
+        self.podcasts[index] = podcast;
+        if let Some(key) = selected_episode {
+            let position = self.episodes().iter().position(|episode| episode.key() == key);
+            self.right_state.select(Some(position.unwrap_or(0)));
+        }
+    }
+
+    /// Clicking the progress bar jumps to that spot; dragging previews the target until release.
+    fn handle_mouse<P: AudioPlayerTrait>(&mut self, mouse: MouseEvent, player: &mut P) {
+        let area = self.progress_area;
+        let inside = area.contains(Position::new(mouse.column, mouse.row));
+        let percent = || {
+            let offset = mouse.column.saturating_sub(area.x) as f32;
+            (offset / area.width.saturating_sub(1).max(1) as f32 * 100.0).clamp(0.0, 100.0)
+        };
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) if inside => self.drag_percent = Some(percent()),
+            MouseEventKind::Drag(MouseButton::Left) if self.drag_percent.is_some() => {
+                self.drag_percent = Some(percent())
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                if let Some(percent) = self.drag_percent.take() {
+                    self.seek_percent(percent, player);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn seek_percent<P: AudioPlayerTrait>(&mut self, percent: f32, player: &mut P) {
+        let result = percent_of_duration(player, percent).and_then(|position| player.seek(position));
+        self.now_playing.status = match result {
+            Ok(()) => format!("Jumped to {}", format_duration(player.position())),
+            Err(e) => format!("⚠️  {}", e),
+        };
+    }
+
+    fn play_selected<P: AudioPlayerTrait>(&mut self, player: &mut P) {
+        let Some(episode) = self.right_state.selected().and_then(|i| self.episodes().get(i)).cloned() else {
+            return;
+        };
+        let settings = self
+            .left_state
+            .selected()
+            .and_then(|i| self.podcasts.get(i))
+            .map(|podcast| podcast.settings().clone())
+            .unwrap_or_default();
+
+        self.now_playing.status = "Loading…".to_string();
+        self.now_playing.title = Some(episode.title.clone());
+        let _ = self.draw();
+
+        self.now_playing.status = match start_episode(player, &episode, &settings, self.default_speed) {
+            Ok(()) => {
+                self.store_duration(player.duration());
+                String::new()
+            }
+            Err(e) => {
+                self.now_playing.title = None;
+                format!("⚠️  Failed to play episode: {}", e)
+            }
+        };
+    }
+
+    /// Keeps the duration the player measured on the selected episode.
+    fn store_duration(&mut self, duration: Option<Duration>) {
+        let (Some(podcast), Some(episode), Some(duration)) =
+            (self.left_state.selected(), self.right_state.selected(), duration)
+        else {
+            return;
+        };
+        if let Some(episode) = self.podcasts.get_mut(podcast).and_then(|p| p.episodes_mut().get_mut(episode)) {
+            episode.duration = Some(duration);
+        }
+    }
+
+    fn refresh_now_playing<P: AudioPlayerTrait>(&mut self, player: &P) {
+        self.now_playing.position = player.position();
+        self.now_playing.duration = player.duration();
+        self.now_playing.paused = player.is_paused();
+        self.now_playing.volume = player.volume();
+        self.now_playing.muted = player.is_muted();
+        self.now_playing.saved = player.time_saved();
+        self.now_playing.sleep_remaining = player.sleep_remaining();
+    }
+
+    fn episodes(&self) -> &[Episode] {
+        self.left_state
+            .selected()
+            .and_then(|i| self.podcasts.get(i))
+            .map_or(&[], |podcast| podcast.episodes())
     }
 
     fn draw(&mut self) -> Result<()> {
+        let palette = &self.palette;
+        let focus = self.focus;
+        let help = self.show_help.then(|| self.bindings.help_text());
+        let left_items: Vec<&str> = self.podcasts.iter().map(|p| p.title()).collect();
+        let right_items: Vec<&str> = self
+            .left_state
+            .selected()
+            .and_then(|i| self.podcasts.get(i))
+            .map_or(&[][..], |podcast| podcast.episodes())
+            .iter()
+            .map(|e| e.title.as_str())
+            .collect();
+        let left_state = &mut self.left_state;
+        let right_state = &mut self.right_state;
+        let now_playing = &self.now_playing;
+        let drag_percent = self.drag_percent;
+        let mut progress_area = self.progress_area;
+        let directory_name = self.directory.as_ref().map_or("", |directory| directory.provider.name());
+        let add_dialog = &mut self.add_dialog;
+
         self.terminal.draw(|frame| {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(3)])
+                .split(frame.area());
             let layout = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([
                     Constraint::Percentage(50),
                     Constraint::Percentage(50),
                 ])
-                .split(frame.area());
+                .split(rows[0]);
 
             // Convert left items to ListItems with colored text
-            let left_list_items: Vec<ListItem> = self.left_items
+            let left_list_items: Vec<ListItem> = left_items
                 .iter()
                 .enumerate()
                 .map(|(i, item)| {
-                    let style = if i % 2 == 0 { 
-                        Style::default().fg(Color::Green) 
-                    } else { 
-                        Style::default().fg(Color::LightGreen) 
+                    let style = if i % 2 == 0 {
+                        Style::default().fg(palette.left.0)
+                    } else {
+                        Style::default().fg(palette.left.1)
                     };
-                    ListItem::new(Text::styled(item.clone(), style))
+                    ListItem::new(Text::styled(item.to_string(), style))
                 })
                 .collect();
 
             // Convert right items to ListItems with colored text
-            let right_list_items: Vec<ListItem> = self.right_items
+            let right_list_items: Vec<ListItem> = right_items
                 .iter()
                 .enumerate()
                 .map(|(i, item)| {
-                    let style = if i % 2 == 0 { 
-                        Style::default().fg(Color::Blue) 
-                    } else { 
-                        Style::default().fg(Color::LightBlue) 
+                    let style = if i % 2 == 0 {
+                        Style::default().fg(palette.right.0)
+                    } else {
+                        Style::default().fg(palette.right.1)
                     };
-                    ListItem::new(Text::styled(item.clone(), style))
+                    ListItem::new(Text::styled(item.to_string(), style))
                 })
                 .collect();
 
+            // Highlight the border of the focused pane
+            let pane_block = |title: &'static str, pane: Pane| {
+                let block = Block::default().borders(Borders::ALL).title(title);
+                if pane == focus {
+                    block.border_style(Style::default().add_modifier(Modifier::BOLD))
+                } else {
+                    block
+                }
+            };
+            let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
             // Create left pane list
             let left_pane = List::new(left_list_items)
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .title("Podcasts"));
-            
+                .block(pane_block("Podcasts", Pane::Left))
+                .highlight_style(highlight);
+
             // Create right pane list
             let right_pane = List::new(right_list_items)
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .title("Episodes"));
+                .block(pane_block("Episodes", Pane::Right))
+                .highlight_style(highlight);
 
             // Render panes
-            frame.render_widget(left_pane, layout[0]);
-            frame.render_widget(right_pane, layout[1]);
+            frame.render_stateful_widget(left_pane, layout[0], left_state);
+            frame.render_stateful_widget(right_pane, layout[1], right_state);
+
+            // Render the now-playing bar; while dragging it shows the target instead of the position
+            let ratio = match (drag_percent, now_playing.duration) {
+                (Some(percent), _) => f64::from(percent) / 100.0,
+                (None, Some(total)) if !total.is_zero() => {
+                    (now_playing.position.as_secs_f64() / total.as_secs_f64()).min(1.0)
+                }
+                _ => 0.0,
+            };
+            let shown = match (drag_percent, now_playing.duration) {
+                (Some(_), Some(total)) => total.mul_f64(ratio),
+                _ => now_playing.position,
+            };
+            let mut label = match now_playing.duration {
+                Some(total) => format!("{} / {}", format_duration(shown), format_duration(total)),
+                None => format_duration(shown),
+            };
+            if let Some(saved) = now_playing.saved {
+                label.push_str(&format!(
+                    "  ✂ {} saved ({} overall)",
+                    format_duration(saved.episode),
+                    format_duration(saved.overall)
+                ));
+            }
+            let mut volume = if now_playing.muted {
+                "🔇 muted".to_string()
+            } else {
+                format!("🔊 {:.0}%", now_playing.volume * 100.0)
+            };
+            if let Some(remaining) = now_playing.sleep_remaining {
+                volume.push_str(&format!("  💤 {}", format_duration(remaining)));
+            }
+            let title = match &now_playing.title {
+                Some(title) if now_playing.paused => format!("⏸ {}  {}", title, volume),
+                Some(title) => format!("▶ {}  {}", title, volume),
+                None => format!("Nothing playing  {}", volume),
+            };
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(now_playing.status.as_str());
+            progress_area = block.inner(rows[1]);
+            frame.render_widget(
+                Gauge::default()
+                    .block(block)
+                    .gauge_style(Style::default().fg(palette.progress))
+                    .ratio(ratio)
+                    .label(label),
+                rows[1],
+            );
+
+            // Render the "Add podcast" dialog over the panes
+            if let Some(dialog) = add_dialog {
+                let area = centered(frame.area(), 70, 60);
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Add podcast from {}", directory_name))
+                    .title_bottom(dialog.message.as_str());
+                let parts = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(1)])
+                    .split(block.inner(area));
+                let results: Vec<ListItem> = dialog
+                    .results
+                    .iter()
+                    .map(|result| match &result.author {
+                        Some(author) => ListItem::new(format!("{} — {}", result.title, author)),
+                        None => ListItem::new(result.title.as_str()),
+                    })
+                    .collect();
+                frame.render_widget(Clear, area);
+                frame.render_widget(block, area);
+                frame.render_widget(
+                    Paragraph::new(format!("{}▏", dialog.query)).block(Block::default().borders(Borders::ALL).title("Search")),
+                    parts[0],
+                );
+                frame.render_stateful_widget(List::new(results).highlight_style(highlight), parts[1], &mut dialog.state);
+            }
+
+            // Render the help overlay on top
+            if let Some(help) = help {
+                let area = centered(frame.area(), 60, 50);
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Paragraph::new(help).block(Block::default().borders(Borders::ALL).title("Help")),
+                    area,
+                );
+            }
         })?;
 
+        self.progress_area = progress_area;
         Ok(())
     }
+}
 
-    fn cleanup(&mut self) -> Result<()> {
-        disable_raw_mode()?;
-        execute!(self.terminal.backend_mut(), LeaveAlternateScreen)?;
-        self.terminal.show_cursor()?;
-        Ok(())
+/// Restores the terminal however the TUI ends, including on errors and panics.
+impl Drop for Tui {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture);
+        let _ = self.terminal.show_cursor();
     }
 }
+
+/// Runs `work` on its own thread, so the network calls it makes do not freeze the UI.
+fn spawn_directory_work(work: impl FnOnce() -> DirectoryReply + Send + 'static) -> Receiver<DirectoryReply> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(work());
+    });
+    receiver
+}
+
+/// A rectangle of the given percentage size in the middle of `area`.
+fn centered(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
@@ -0,0 +1,191 @@
+use anyhow::Result;
+use crate::loudness::SharedGain;
+use crate::state_file::{load_json, save_json};
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Loudest level by default: the episode as published (after normalization).
+pub const DEFAULT_MAX_VOLUME: f32 = 1.0;
+
+/// Attenuation at the bottom of the volume scale; level 1.0 is 0 dB.
+const DYNAMIC_RANGE_DB: f32 = 40.0;
+
+/// Maps a perceptual volume level to a linear amplitude.
+///
+/// Loudness is perceived logarithmically, so equal steps in level are equal steps in decibels:
+/// 0.5 is -20 dB, 1.0 is unchanged, and 0.0 is silent.
+pub fn amplitude(level: f32) -> f32 {
+    if level <= 0.0 {
+        return 0.0;
+    }
+    10f32.powf(DYNAMIC_RANGE_DB * (level - 1.0) / 20.0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct VolumeFile {
+    level: f32,
+    muted: bool,
+}
+
+impl Default for VolumeFile {
+    fn default() -> Self {
+        Self { level: DEFAULT_MAX_VOLUME, muted: false }
+    }
+}
+
+/// The volume the user chose, kept across episodes and, when it has a file, across sessions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeControl {
+    level: f32,
+    max: f32,
+    muted: bool,
+    path: Option<PathBuf>,
+    /// What the file holds, so unchanged volumes are not written again.
+    saved: VolumeFile,
+}
+
+impl VolumeControl {
+    pub fn new(max: f32) -> Self {
+        let max = max.max(0.0);
+        Self { level: DEFAULT_MAX_VOLUME.min(max), max, muted: false, path: None, saved: VolumeFile::default() }
+    }
+
+    /// Restores the last volume from `path`. Changes are only written back by [`save`](Self::save).
+    pub fn load(path: impl Into<PathBuf>, max: f32) -> Result<Self> {
+        let path = path.into();
+        let saved: VolumeFile = load_json(&path, "volume")?;
+        let mut volume = Self::new(max);
+        volume.level = saved.level.clamp(0.0, volume.max);
+        volume.muted = saved.muted;
+        volume.saved = saved;
+        volume.path = Some(path);
+        Ok(volume)
+    }
+
+    /// Where the volume is kept inside the data directory.
+    pub fn default_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("volume.json")
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// The amplitude to apply to the audio, silent while muted.
+    pub fn amplitude(&self) -> f32 {
+        if self.muted { 0.0 } else { amplitude(self.level) }
+    }
+
+    /// Sets the level, clamped to `0.0..=max`. Changing the volume also unmutes.
+    pub fn set(&mut self, level: f32) {
+        self.level = level.clamp(0.0, self.max);
+        self.muted = false;
+    }
+
+    pub fn adjust(&mut self, step: f32) {
+        self.set(self.level + step)
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+    }
+
+    /// Writes the volume to its file if it changed since it was loaded or last saved. Volume
+    /// keys are pressed in bursts, so this runs once playback ends rather than on every change.
+    pub fn save(&mut self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let file = VolumeFile { level: self.level, muted: self.muted };
+        if file != self.saved {
+            save_json(path, &file)?;
+            self.saved = file;
+        }
+        Ok(())
+    }
+}
+
+impl Default for VolumeControl {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_VOLUME)
+    }
+}
+
+/// Applies the user's volume and any fade as the last step of playback, after silence trimming
+/// has looked at the audio, so a low, muted or fading volume does not count as a pause.
+///
+/// Changes are ramped over ~20 ms to avoid clicks. Levels above 1.0 can clip.
+pub struct Volume<S> {
+    source: S,
+    amplitude: SharedGain,
+    fade: SharedGain,
+    current: f32,
+    ramp: f32,
+}
+
+impl<S: Source<Item = f32>> Volume<S> {
+    pub fn new(source: S, amplitude: SharedGain) -> Self {
+        let samples_per_sec = (source.sample_rate() * source.channels() as u32).max(1) as f32;
+        Self {
+            current: amplitude.get(),
+            source,
+            amplitude,
+            fade: SharedGain::new(1.0),
+            ramp: 1.0 / (0.02 * samples_per_sec),
+        }
+    }
+
+    /// Also applies `fade`, e.g. the fade-out of a sleep timer.
+    pub fn with_fade(mut self, fade: SharedGain) -> Self {
+        self.current *= fade.get();
+        self.fade = fade;
+        self
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Volume<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.source.next()?;
+        let target = self.amplitude.get() * self.fade.get();
+        self.current += (target - self.current) * self.ramp.min(1.0);
+        Some(sample * self.current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.source.size_hint()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Volume<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
+        self.source.try_seek(pos)
+    }
+}
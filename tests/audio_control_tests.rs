@@ -1,16 +1,20 @@
-use rss_reader::audio_control::{process_command, run, get_next_command};
+use rss_reader::audio_control::{process_command, run, get_next_command, percent_of_duration, start_episode};
 use rss_reader::audio_player::{AudioPlayerTrait, PlayerCommand};
 use rss_reader::keyboard_controls::{CooldownHandler, Cooldown};
 use rss_reader::episodes::Episode;
+use rss_reader::silence::TimeSaved;
+use rss_reader::sleep_timer::SleepMode;
+use rss_reader::subscriptions::FeedSettings;
 use anyhow::Result;
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::io::{Cursor, BufRead};
+use std::io::Cursor;
 use std::time::Duration;
 
 // Mock AudioPlayer for testing
 struct MockAudioPlayer {
     actions: Rc<RefCell<Vec<String>>>,
+    duration: Option<Duration>,
 }
 
 impl AudioPlayerTrait for MockAudioPlayer {
@@ -39,16 +43,92 @@ impl AudioPlayerTrait for MockAudioPlayer {
         Ok(())
     }
 
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        self.actions.borrow_mut().push(format!("seek: {}", position.as_secs()));
+        Ok(())
+    }
+
     fn adjust_volume(&mut self, step: f32) -> Result<()> {
         self.actions.borrow_mut().push(format!("volume: {}", step));
         Ok(())
     }
+
+    fn set_volume(&mut self, volume: f32) -> Result<()> {
+        self.actions.borrow_mut().push(format!("set volume: {}", volume));
+        Ok(())
+    }
+
+    fn set_speed(&mut self, speed: f32) -> Result<()> {
+        self.actions.borrow_mut().push(format!("speed: {}", speed));
+        Ok(())
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    fn position(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn is_paused(&self) -> bool {
+        false
+    }
+
+    fn volume(&self) -> f32 {
+        1.0
+    }
+
+    fn time_saved(&self) -> Option<TimeSaved> {
+        None
+    }
+
+    fn toggle_mute(&mut self) -> Result<()> {
+        self.actions.borrow_mut().push("toggle_mute".to_string());
+        Ok(())
+    }
+
+    fn is_muted(&self) -> bool {
+        false
+    }
+
+    fn set_sleep_timer(&mut self, mode: SleepMode) -> Result<()> {
+        self.actions.borrow_mut().push(format!("sleep: {:?}", mode));
+        Ok(())
+    }
+
+    fn extend_sleep_timer(&mut self, by: Duration) -> Result<()> {
+        self.actions.borrow_mut().push(format!("extend sleep: {}", by.as_secs()));
+        Ok(())
+    }
+
+    fn cancel_sleep_timer(&mut self) {
+        self.actions.borrow_mut().push("cancel sleep".to_string());
+    }
+
+    fn sleep_remaining(&self) -> Option<Duration> {
+        None
+    }
+
+    fn set_output_device(&mut self, device: Option<&str>) -> Result<()> {
+        self.actions.borrow_mut().push(format!("device: {}", device.unwrap_or("default")));
+        Ok(())
+    }
+
+    fn output_device(&self) -> String {
+        "mock".to_string()
+    }
+
+    fn check_output_device(&mut self) -> Result<Option<String>> {
+        Ok(None)
+    }
 }
 
 impl MockAudioPlayer {
     fn new() -> Self {
         Self {
             actions: Rc::new(RefCell::new(Vec::new())),
+            duration: None,
         }
     }
 
@@ -135,6 +215,15 @@ fn test_process_command() {
         (PlayerCommand::SkipBackward(10), "skip: -10"),
         (PlayerCommand::VolumeUp(0.1), "volume: 0.1"),
         (PlayerCommand::VolumeDown(0.1), "volume: -0.1"),
+        (PlayerCommand::Seek(Duration::from_secs(3750)), "seek: 3750"),
+        (PlayerCommand::SetVolume(0.6), "set volume: 0.6"),
+        (PlayerCommand::SetSpeed(1.5), "speed: 1.5"),
+        (PlayerCommand::ToggleMute, "toggle_mute"),
+        (PlayerCommand::Sleep(SleepMode::EndOfEpisode), "sleep: EndOfEpisode"),
+        (PlayerCommand::ExtendSleep(Duration::from_secs(600)), "extend sleep: 600"),
+        (PlayerCommand::CancelSleep, "cancel sleep"),
+        (PlayerCommand::SetOutputDevice(Some("USB".to_string())), "device: USB"),
+        (PlayerCommand::SetOutputDevice(None), "device: default"),
     ];
 
     for (command, expected_action) in test_cases {
@@ -180,3 +269,49 @@ fn test_process_command_ignore() {
         );
     }
 }
+
+#[test]
+fn test_process_command_seek_percent() {
+    let mut player = MockAudioPlayer::new();
+    player.duration = Some(Duration::from_secs(3600));
+
+    process_command(&mut player, PlayerCommand::SeekPercent(40.0)).unwrap();
+    assert_eq!(player.get_actions(), vec!["seek: 1440"]);
+
+    // Without a known duration the seek fails instead of guessing
+    let mut player = MockAudioPlayer::new();
+    assert!(process_command(&mut player, PlayerCommand::SeekPercent(40.0)).is_err());
+    assert!(player.get_actions().is_empty());
+}
+
+#[test]
+fn test_percent_of_duration_is_clamped() {
+    let mut player = MockAudioPlayer::new();
+    player.duration = Some(Duration::from_secs(200));
+
+    assert_eq!(percent_of_duration(&player, 25.0).unwrap(), Duration::from_secs(50));
+    assert_eq!(percent_of_duration(&player, 150.0).unwrap(), Duration::from_secs(200));
+    assert_eq!(percent_of_duration(&player, -5.0).unwrap(), Duration::ZERO);
+}
+
+#[test]
+fn test_start_episode_applies_feed_settings() {
+    let episode = Episode { title: "Episode".to_string(), ..Default::default() };
+    let settings = FeedSettings { speed: Some(1.5), skip_intro_secs: Some(30), ..Default::default() };
+
+    let mut player = MockAudioPlayer::new();
+    player.duration = Some(Duration::from_secs(600));
+    start_episode(&mut player, &episode, &settings, 1.0).unwrap();
+    assert_eq!(player.get_actions(), vec!["speed: 1.5", "play: Episode", "seek: 30"]);
+
+    // Feeds without settings play at the default speed from the start
+    let mut player = MockAudioPlayer::new();
+    start_episode(&mut player, &episode, &FeedSettings::default(), 1.25).unwrap();
+    assert_eq!(player.get_actions(), vec!["speed: 1.25", "play: Episode"]);
+
+    // An intro longer than the episode is not skipped
+    let mut player = MockAudioPlayer::new();
+    player.duration = Some(Duration::from_secs(20));
+    start_episode(&mut player, &episode, &settings, 1.0).unwrap();
+    assert_eq!(player.get_actions(), vec!["speed: 1.5", "play: Episode"]);
+}
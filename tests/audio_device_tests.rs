@@ -0,0 +1,29 @@
+use rss_reader::audio_device::find_device;
+
+fn names() -> Vec<String> {
+    ["default", "Built-in Speakers", "USB Headset", "USB Headset Monitor"].map(String::from).to_vec()
+}
+
+#[test]
+fn test_exact_name_wins() {
+    let names = names();
+    assert_eq!(find_device(&names, "USB Headset").unwrap(), "USB Headset");
+}
+
+#[test]
+fn test_unique_part_of_a_name_matches_ignoring_case() {
+    let names = names();
+    assert_eq!(find_device(&names, "speakers").unwrap(), "Built-in Speakers");
+    assert_eq!(find_device(&names, "monitor").unwrap(), "USB Headset Monitor");
+}
+
+#[test]
+fn test_ambiguous_or_unknown_names_are_errors() {
+    let names = names();
+
+    let error = find_device(&names, "usb").unwrap_err().to_string();
+    assert!(error.contains("several"), "{}", error);
+
+    let error = find_device(&names, "hdmi").unwrap_err().to_string();
+    assert!(error.contains("Built-in Speakers"), "Error should list the devices: {}", error);
+}
@@ -0,0 +1,110 @@
+use rss_reader::audio_duration::{estimate_from_length, mp3_duration, resolve_duration, DurationSource};
+use rss_reader::Episode;
+use std::time::Duration;
+
+// MPEG-1 Layer III, 128 kbps, 44.1 kHz, stereo: 417 bytes and 1152 samples per frame
+const HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0x00];
+const FRAME_LEN: usize = 417;
+
+fn frame() -> Vec<u8> {
+    let mut frame = vec![0u8; FRAME_LEN];
+    frame[..4].copy_from_slice(&HEADER);
+    frame
+}
+
+fn frames(count: usize) -> Vec<u8> {
+    (0..count).flat_map(|_| frame()).collect()
+}
+
+fn samples(count: u64) -> Duration {
+    Duration::from_secs_f64((count * 1152) as f64 / 44100.0)
+}
+
+fn id3_tag(size: usize) -> Vec<u8> {
+    let mut tag = vec![b'I', b'D', b'3', 4, 0, 0];
+    tag.extend([(size >> 21) as u8 & 0x7F, (size >> 14) as u8 & 0x7F, (size >> 7) as u8 & 0x7F, size as u8 & 0x7F]);
+    // Tag contents that happen to look like a frame sync must not be mistaken for audio
+    tag.extend(std::iter::repeat_n(0xFF, size));
+    tag
+}
+
+#[test]
+fn test_frame_scan() {
+    assert_eq!(mp3_duration(&frames(100)), Some((samples(100), DurationSource::FrameScan)));
+}
+
+#[test]
+fn test_xing_header_wins_over_scan() {
+    let mut first = frame();
+    first[36..40].copy_from_slice(b"Xing");
+    first[40..44].copy_from_slice(&1u32.to_be_bytes());
+    first[44..48].copy_from_slice(&5000u32.to_be_bytes());
+    let mut bytes = first;
+    bytes.extend(frames(10));
+
+    assert_eq!(mp3_duration(&bytes), Some((samples(5000), DurationSource::XingHeader)));
+}
+
+#[test]
+fn test_vbri_header() {
+    let mut first = frame();
+    first[36..40].copy_from_slice(b"VBRI");
+    first[50..54].copy_from_slice(&2500u32.to_be_bytes());
+    let mut bytes = first;
+    bytes.extend(frames(10));
+
+    assert_eq!(mp3_duration(&bytes), Some((samples(2500), DurationSource::VbriHeader)));
+}
+
+#[test]
+fn test_id3_tag_is_skipped() {
+    let mut bytes = id3_tag(300);
+    bytes.extend(frames(20));
+    bytes.extend(b"TAG trailing id3v1 data");
+
+    assert_eq!(mp3_duration(&bytes), Some((samples(20), DurationSource::FrameScan)));
+}
+
+#[test]
+fn test_not_mp3() {
+    assert_eq!(mp3_duration(b"ftypM4A not an mp3 at all"), None);
+    assert_eq!(mp3_duration(&[]), None);
+}
+
+#[test]
+fn test_resolve_duration_fallbacks() {
+    let feed_duration = Duration::from_secs(1800);
+    let episode = Episode {
+        duration: Some(feed_duration),
+        enclosure_length: Some(FRAME_LEN as u64 * 1000),
+        ..Default::default()
+    };
+
+    // The decoder knows best
+    assert_eq!(
+        resolve_duration(Some(Duration::from_secs(60)), &frames(10), &episode),
+        Some((Duration::from_secs(60), DurationSource::Decoder))
+    );
+
+    // Only the start of the file: frames are not counted, the feed value is used
+    assert_eq!(
+        resolve_duration(None, &frames(10), &episode),
+        Some((feed_duration, DurationSource::Feed))
+    );
+
+    // Without a feed value the enclosure length and bitrate give an estimate
+    let episode = Episode { duration: None, ..episode };
+    let (estimate, source) = resolve_duration(None, &frames(10), &episode).unwrap();
+    assert_eq!(source, DurationSource::EnclosureLength);
+    assert!((estimate.as_secs_f64() - samples(1000).as_secs_f64()).abs() < 0.1);
+
+    // Nothing to go on
+    let unknown = Episode::default();
+    assert_eq!(resolve_duration(None, b"not audio", &unknown), None);
+}
+
+#[test]
+fn test_estimate_from_length() {
+    assert_eq!(estimate_from_length(16_000, 128_000), Some(Duration::from_secs(1)));
+    assert_eq!(estimate_from_length(16_000, 0), None);
+}
@@ -0,0 +1,157 @@
+mod support;
+
+use rss_reader::audio_output::OutputBackend;
+use rss_reader::audio_player::{AudioPlayer, AudioPlayerTrait};
+use rss_reader::episodes::{fetch_episodes, Episode};
+use rss_reader::feed_source::HttpSource;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use support::{fast_client, Fixture, FixtureServer};
+
+const SAMPLE_RATE: u32 = 8000;
+
+/// The headless outputs write 44.1 kHz, in chunks of 10 ms.
+const OUTPUT_RATE: u32 = 44100;
+const CHUNK_FRAMES: u32 = OUTPUT_RATE / 100;
+
+/// A mono 440 Hz tone at half amplitude.
+fn tone(seconds: f32) -> Arc<[u8]> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut bytes = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+    for i in 0..(seconds * SAMPLE_RATE as f32) as u32 {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        writer.write_sample(((t * 440.0 * std::f32::consts::TAU).sin() * 0.5 * i16::MAX as f32) as i16).unwrap();
+    }
+    writer.finalize().unwrap();
+    bytes.into_inner().into()
+}
+
+fn episode() -> Episode {
+    Episode { title: "Tone".to_string(), ..Default::default() }
+}
+
+/// Waits until `condition` holds, failing the test after a few seconds.
+fn wait_for(what: &str, mut condition: impl FnMut() -> bool) {
+    let started = Instant::now();
+    while !condition() {
+        assert!(started.elapsed() < Duration::from_secs(5), "Timed out waiting for {}", what);
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn test_null_output_plays_and_seeks_without_a_sound_card() {
+    let mut player = AudioPlayer::with_output(OutputBackend::Null { speed: 20.0 }).unwrap();
+    player.play_audio(&episode(), tone(10.0), Some("audio/wav".to_string())).unwrap();
+
+    assert_eq!(player.duration(), Some(Duration::from_secs(10)));
+    wait_for("playback to advance", || player.position() >= Duration::from_secs(1));
+
+    player.seek(Duration::from_secs(8)).unwrap();
+    assert!(player.position() >= Duration::from_secs(8), "Position after seek: {:?}", player.position());
+
+    player.stop().unwrap();
+}
+
+#[test]
+fn test_wav_output_records_what_is_played() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.wav");
+    let mut player = AudioPlayer::with_output(OutputBackend::Wav { path: path.clone(), speed: 50.0 }).unwrap();
+    player.play_audio(&episode(), tone(0.5), None).unwrap();
+
+    wait_for("the whole tone to be written", || recorded_frames(&path) >= OUTPUT_RATE / 2);
+    player.stop().unwrap();
+
+    // Only the tone is written, not the silence that follows it until the stop
+    let frames = recorded_frames(&path);
+    assert!(frames - OUTPUT_RATE / 2 <= CHUNK_FRAMES, "Recorded {} frames", frames);
+    let mut reader = hound::WavReader::open(&path).unwrap();
+    assert_eq!(reader.spec().channels, 2);
+    let peak = reader.samples::<i16>().map(|s| s.unwrap().unsigned_abs()).max().unwrap();
+    assert!(peak > i16::MAX as u16 / 4, "The tone should be audible in the file, peak {}", peak);
+}
+
+#[test]
+fn test_wav_output_writes_nothing_while_paused() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.wav");
+    let mut player = AudioPlayer::with_output(OutputBackend::Wav { path: path.clone(), speed: 20.0 }).unwrap();
+    player.play_audio(&episode(), tone(10.0), None).unwrap();
+    wait_for("playback to advance", || player.position() >= Duration::from_secs(1));
+
+    // A paused sink is still pulled, which is how it handles seeks, so each seek returns
+    // once the output got to it. A few seeks in, the audio from before the pause is written.
+    player.pause().unwrap();
+    for _ in 0..3 {
+        player.seek(Duration::from_secs(5)).unwrap();
+    }
+    let frames = recorded_frames(&path);
+    for _ in 0..20 {
+        player.seek(Duration::from_secs(5)).unwrap();
+    }
+    assert_eq!(recorded_frames(&path), frames);
+    assert_eq!(player.position(), Duration::from_secs(5));
+
+    player.resume().unwrap();
+    wait_for("the output to write again", || recorded_frames(&path) > frames);
+    player.stop().unwrap();
+}
+
+fn recorded_frames(path: &Path) -> u32 {
+    match hound::WavReader::open(path) {
+        Ok(reader) => reader.duration(),
+        Err(_) => 0,
+    }
+}
+
+#[test]
+fn test_play_downloads_the_enclosure_over_http() {
+    let server = FixtureServer::start();
+    let feed = format!(
+        r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Tones</title>
+<item><title>Tone</title><enclosure url="{}" length="0" type="audio/wav"/></item>
+</channel></rss>"#,
+        server.url("/tone.wav")
+    );
+    server.serve("/feed.rss", Fixture::feed(feed));
+    server.serve("/tone.wav", Fixture::audio(tone(2.0).to_vec(), "audio/wav"));
+
+    let episodes = fetch_episodes(&HttpSource::with_client(fast_client()), &server.url("/feed.rss")).unwrap();
+    let mut player = AudioPlayer::with_output(OutputBackend::Null { speed: 20.0 }).unwrap();
+    player.play(&episodes[0]).unwrap();
+
+    assert_eq!(player.duration(), Some(Duration::from_secs(2)));
+    wait_for("playback to advance", || player.position() > Duration::ZERO);
+    assert_eq!(server.requests_to("/tone.wav").len(), 1);
+    player.stop().unwrap();
+}
+
+#[test]
+fn test_output_backends_parse() {
+    assert_eq!("device".parse::<OutputBackend>().unwrap(), OutputBackend::Device(None));
+    assert_eq!(
+        "device:USB Headset".parse::<OutputBackend>().unwrap(),
+        OutputBackend::Device(Some("USB Headset".to_string()))
+    );
+    assert_eq!("null".parse::<OutputBackend>().unwrap(), OutputBackend::Null { speed: 1.0 });
+    assert_eq!("null:8".parse::<OutputBackend>().unwrap(), OutputBackend::Null { speed: 8.0 });
+    assert_eq!(
+        "wav:/tmp/out.wav".parse::<OutputBackend>().unwrap(),
+        OutputBackend::Wav { path: "/tmp/out.wav".into(), speed: 1.0 }
+    );
+
+    for invalid in ["speaker", "null:0", "null:fast", "wav"] {
+        assert!(invalid.parse::<OutputBackend>().is_err(), "Expected an error for '{}'", invalid);
+    }
+}
@@ -0,0 +1,153 @@
+mod support;
+
+use rodio::Source;
+use rss_reader::audio_player::{decode, PlaybackError};
+use std::time::Duration;
+use support::audio::{flac, m4a, mp3, ogg, ogg_opus, ogg_vorbis, OPUS_SAMPLE_RATE};
+
+const SAMPLE_RATE: u32 = 8000;
+
+/// A mono 16-bit WAV file whose samples hold the second they belong to, times 1000.
+fn wav(seconds: u32) -> Vec<u8> {
+    let samples: Vec<i16> = (0..seconds * SAMPLE_RATE).map(|i| (i / SAMPLE_RATE * 1000) as i16).collect();
+    let data_len = samples.len() as u32 * 2;
+
+    let mut bytes = Vec::new();
+    bytes.extend(b"RIFF");
+    bytes.extend((36 + data_len).to_le_bytes());
+    bytes.extend(b"WAVEfmt ");
+    bytes.extend(16u32.to_le_bytes());
+    bytes.extend(1u16.to_le_bytes()); // PCM
+    bytes.extend(1u16.to_le_bytes()); // mono
+    bytes.extend(SAMPLE_RATE.to_le_bytes());
+    bytes.extend((SAMPLE_RATE * 2).to_le_bytes());
+    bytes.extend(2u16.to_le_bytes());
+    bytes.extend(16u16.to_le_bytes());
+    bytes.extend(b"data");
+    bytes.extend(data_len.to_le_bytes());
+    bytes.extend(samples.iter().flat_map(|s| s.to_le_bytes()));
+    bytes
+}
+
+#[test]
+fn test_decoded_audio_seeks() {
+    let mut source = decode(wav(3), None).unwrap();
+    assert_eq!(source.next(), Some(0));
+
+    source.try_seek(Duration::from_secs(2)).unwrap();
+    assert_eq!(source.next(), Some(2000));
+
+    source.try_seek(Duration::from_millis(1500)).unwrap();
+    assert_eq!(source.next(), Some(1000));
+}
+
+/// Seeks ten seconds of `bytes` forwards and back and checks that exactly the samples after
+/// each target are left to play.
+fn assert_seeks_exactly(bytes: Vec<u8>, mime_type: &str) {
+    let total = decode(bytes.clone(), Some(mime_type)).unwrap().count();
+    let mut source = decode(bytes, Some(mime_type)).unwrap();
+    let samples_per_second = source.sample_rate() as usize * source.channels() as usize;
+    assert!(total >= 10 * samples_per_second, "{} has {} samples", mime_type, total);
+
+    for seconds in [7, 2] {
+        source.try_seek(Duration::from_secs(seconds as u64)).unwrap();
+        let left = source.by_ref().count();
+        assert_eq!(left, total - seconds * samples_per_second, "{} samples left after seeking to {} s", mime_type, seconds);
+    }
+}
+
+#[test]
+fn test_mp3_seeks_exactly() {
+    assert_seeks_exactly(mp3(10), "audio/mpeg");
+}
+
+#[test]
+fn test_ogg_vorbis_seeks_exactly() {
+    assert_seeks_exactly(ogg_vorbis(10), "audio/ogg");
+}
+
+#[test]
+fn test_m4a_seeks_exactly() {
+    assert_seeks_exactly(m4a(10), "audio/mp4");
+}
+
+#[test]
+fn test_ogg_opus_seeks_exactly() {
+    assert_seeks_exactly(ogg_opus(&[0.5; 10]), "audio/ogg");
+}
+
+#[test]
+fn test_flac_seeks_exactly() {
+    assert_seeks_exactly(flac(&[0.5; 10]), "audio/flac");
+}
+
+/// How loud the next 20 ms are, from 0 to 1.
+fn level(source: &mut impl Source<Item = i16>) -> f32 {
+    let samples: Vec<f32> = source.take(960).map(|s| s as f32 / i16::MAX as f32).collect();
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt() * std::f32::consts::SQRT_2
+}
+
+#[test]
+fn test_ogg_opus_plays_and_seeks() {
+    let mut source = decode(ogg_opus(&[0.0, 0.8, 0.2]), Some("audio/ogg")).unwrap();
+    let duration = source.total_duration().unwrap();
+    assert!(duration.abs_diff(Duration::from_secs(3)) < Duration::from_millis(10), "Duration: {:?}", duration);
+    assert_eq!((source.channels(), source.sample_rate()), (1, OPUS_SAMPLE_RATE));
+    assert!(level(&mut source) < 0.05);
+
+    source.try_seek(Duration::from_millis(1500)).unwrap();
+    let loud = level(&mut source);
+    assert!((loud - 0.8).abs() < 0.15, "Level after seeking into the loud second: {}", loud);
+
+    source.try_seek(Duration::from_millis(2500)).unwrap();
+    let quiet = level(&mut source);
+    assert!((quiet - 0.2).abs() < 0.1, "Level after seeking into the quiet second: {}", quiet);
+
+    source.try_seek(Duration::from_millis(500)).unwrap();
+    assert!(level(&mut source) < 0.05, "Seeking back should reach the silent second");
+}
+
+/// The start of a 5.1 Ogg Opus stream, which needs libopus' multistream decoder.
+fn surround_ogg_opus() -> Vec<u8> {
+    let mut head = b"OpusHead".to_vec();
+    head.push(1); // version
+    head.push(6); // channels
+    head.extend(312u16.to_le_bytes()); // pre-skip
+    head.extend(48000u32.to_le_bytes());
+    head.extend(0u16.to_le_bytes()); // output gain
+    head.push(1); // channel mapping family
+    head.extend([4, 2, 0, 4, 1, 2, 3, 5]); // streams, coupled streams, channel mapping
+
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend(4u32.to_le_bytes());
+    tags.extend(b"test");
+    tags.extend(0u32.to_le_bytes());
+    ogg(&[head, tags], &[(vec![0xFC, 0xFF, 0xFE], 960)])
+}
+
+fn playback_error(bytes: Vec<u8>, mime_type: &str) -> PlaybackError {
+    decode(bytes, Some(mime_type))
+        .err()
+        .expect("decoding should fail")
+        .downcast::<PlaybackError>()
+        .expect("should be a PlaybackError")
+}
+
+#[test]
+fn test_unsupported_codec_is_a_typed_error() {
+    let error = playback_error(surround_ogg_opus(), "audio/opus");
+    assert_eq!(
+        error,
+        PlaybackError::UnsupportedCodec { mime_type: "audio/opus".to_string(), codec: "Opus".to_string() }
+    );
+    assert!(error.to_string().contains("audio/opus"));
+    assert!(error.to_string().contains("Opus codec"));
+}
+
+#[test]
+fn test_unrecognized_audio_is_a_typed_error() {
+    assert_eq!(
+        playback_error(b"definitely not audio".to_vec(), "audio/x-m4a"),
+        PlaybackError::UnrecognizedFormat { mime_type: "audio/x-m4a".to_string() }
+    );
+}
@@ -0,0 +1,61 @@
+use rss_reader::chapters::{chapter_at, parse_id3_chapters};
+use std::time::Duration;
+
+fn frame(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut frame = id.to_vec();
+    frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&[0, 0]);
+    frame.extend_from_slice(data);
+    frame
+}
+
+fn chap(id: &str, start_ms: u32, end_ms: u32, title: Option<&str>) -> Vec<u8> {
+    let mut data = id.as_bytes().to_vec();
+    data.push(0);
+    for value in [start_ms, end_ms, u32::MAX, u32::MAX] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    if let Some(title) = title {
+        let mut text = vec![3];
+        text.extend_from_slice(title.as_bytes());
+        data.extend(frame(b"TIT2", &text));
+    }
+    frame(b"CHAP", &data)
+}
+
+/// An ID3v2.3 tag (plain frame sizes) around `frames`, followed by some padding.
+fn id3v23(frames: &[Vec<u8>]) -> Vec<u8> {
+    let mut body: Vec<u8> = frames.concat();
+    body.extend_from_slice(&[0; 16]);
+    let size = body.len();
+    let mut tag = b"ID3\x03\x00\x00".to_vec();
+    tag.extend((0..4).rev().map(|i| ((size >> (7 * i)) & 0x7F) as u8));
+    tag.extend(body);
+    tag
+}
+
+#[test]
+fn test_parses_chapters_in_start_order() {
+    let tag = id3v23(&[
+        frame(b"TIT2", b"\x03Episode"),
+        chap("ch2", 60_000, 150_000, Some("Interview")),
+        chap("ch1", 0, 60_000, None),
+    ]);
+    let chapters = parse_id3_chapters(&tag);
+
+    assert_eq!(chapters.len(), 2);
+    assert_eq!(chapters[0].start, Duration::ZERO);
+    assert_eq!(chapters[0].title, None);
+    assert_eq!(chapters[1].title.as_deref(), Some("Interview"));
+    assert_eq!(chapters[1].end, Duration::from_secs(150));
+
+    let playing = chapter_at(&chapters, Duration::from_secs(90)).unwrap();
+    assert_eq!(playing.start, Duration::from_secs(60));
+    assert!(chapter_at(&chapters, Duration::from_secs(200)).is_none());
+}
+
+#[test]
+fn test_audio_without_a_tag_has_no_chapters() {
+    assert!(parse_id3_chapters(&[0xFF, 0xFB, 0x90, 0x00]).is_empty());
+    assert!(parse_id3_chapters(b"ID3").is_empty());
+}
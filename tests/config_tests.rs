@@ -0,0 +1,175 @@
+use rss_reader::config::{Config, ConfigOverrides, DirectoryService, Theme};
+use rss_reader::http::HttpSettings;
+use rss_reader::refresh_scheduler::ScheduleSettings;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+#[test]
+fn test_config_from_toml_partial() {
+    let config = Config::from_toml(r#"
+        [paths]
+        subscriptions = "/tmp/feeds.txt"
+
+        [playback]
+        skip_forward_secs = 30
+        volume_step = 0.05
+
+        [ui]
+        theme = "mono"
+    "#).unwrap();
+
+    assert_eq!(config.paths.subscriptions, PathBuf::from("/tmp/feeds.txt"));
+    assert_eq!(config.playback.skip_forward_secs, 30);
+    assert_eq!(config.playback.skip_backward_secs, 10, "Unset values should keep their defaults");
+    assert_eq!(config.playback.volume_step, 0.05);
+    assert_eq!(config.playback.default_speed, 1.0);
+    assert_eq!(config.refresh.interval_mins, 60);
+    assert_eq!(config.ui.theme, Theme::Mono);
+}
+
+#[test]
+fn test_network_config_to_http_settings() {
+    let config = Config::from_toml("[network]\ntimeout_secs = 5\nmax_feed_mb = 2\ncompression = false").unwrap();
+    let settings = config.network.http_settings();
+
+    assert_eq!(settings.read_timeout, Duration::from_secs(5));
+    assert_eq!(settings.max_feed_bytes, 2 * 1024 * 1024);
+    assert!(!settings.compression);
+    assert_eq!(settings.retries, 3, "Unset values should keep their defaults");
+    assert!(settings.user_agent.starts_with("rss_reader/"));
+    assert_eq!(Config::default().network.http_settings(), HttpSettings::default());
+}
+
+#[test]
+fn test_refresh_config_to_schedule_settings() {
+    let config = Config::from_toml("[refresh]\ninterval_mins = 30\njitter_percent = 20").unwrap();
+    let settings = config.refresh.schedule_settings().unwrap();
+
+    assert_eq!(settings.interval, Duration::from_secs(30 * 60));
+    assert_eq!(settings.jitter, 0.2);
+    assert_eq!(settings.max_interval, Duration::from_secs(24 * 60 * 60), "Unset values should keep their defaults");
+    assert_eq!(Config::default().refresh.schedule_settings(), Some(ScheduleSettings::default()));
+    assert!(Config::from_toml("[refresh]\ninterval_mins = 0").unwrap().refresh.schedule_settings().is_none());
+}
+
+#[test]
+fn test_directory_config() {
+    let config = Config::from_toml("[directory]\nprovider = \"podcastindex\"\nlimit = 5").unwrap();
+    assert_eq!(config.directory.provider, DirectoryService::PodcastIndex);
+    assert_eq!(config.directory.limit, 5);
+    assert_eq!(Config::default().directory.provider, DirectoryService::Itunes);
+
+    let vars: HashMap<&str, &str> = HashMap::from([
+        ("RSS_READER_DIRECTORY", "itunes"),
+        ("RSS_READER_PODCASTINDEX_KEY", "KEY"),
+        ("RSS_READER_PODCASTINDEX_SECRET", "SECRET"),
+    ]);
+    let overrides = ConfigOverrides::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+    let mut config = config;
+    config.apply(&overrides);
+    assert_eq!(config.directory.provider, DirectoryService::Itunes);
+    assert_eq!(config.directory.podcastindex_key.as_deref(), Some("KEY"));
+    assert_eq!(config.directory.podcastindex_secret.as_deref(), Some("SECRET"));
+}
+
+#[test]
+fn test_config_from_toml_invalid() {
+    assert!(Config::from_toml("[playback]\nvolume_step = \"loud\"").is_err());
+    assert!(Config::from_toml("[ui]\ntheme = \"neon\"").is_err());
+}
+
+#[test]
+fn test_overrides_from_vars() {
+    let vars: HashMap<&str, &str> = HashMap::from([
+        ("RSS_READER_DATA_DIR", "/data"),
+        ("RSS_READER_SPEED", "1.5"),
+        ("RSS_READER_THEME", "light"),
+        ("RSS_READER_SKIP_BACKWARD", ""),
+        ("RSS_READER_OUTPUT_DEVICE", "USB Headset"),
+        ("RSS_READER_RETRIES", "5"),
+    ]);
+
+    let overrides = ConfigOverrides::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+
+    assert_eq!(overrides.data_dir, Some(PathBuf::from("/data")));
+    assert_eq!(overrides.default_speed, Some(1.5));
+    assert_eq!(overrides.theme, Some(Theme::Light));
+    assert_eq!(overrides.output_device.as_deref(), Some("USB Headset"));
+    assert_eq!(overrides.retries, Some(5));
+    assert_eq!(overrides.skip_backward_secs, None, "Empty variables should be ignored");
+}
+
+#[test]
+fn test_overrides_from_vars_invalid_value() {
+    let result = ConfigOverrides::from_vars(|name| {
+        (name == "RSS_READER_VOLUME_STEP").then(|| "lots".to_string())
+    });
+
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("RSS_READER_VOLUME_STEP"), "Error should name the variable: {}", error);
+}
+
+#[test]
+fn test_load_precedence() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "[playback]\nskip_forward_secs = 30\ndefault_speed = 1.25\nvolume_step = 0.2").unwrap();
+
+    let env = ConfigOverrides {
+        config_file: Some(config_file.path().to_path_buf()),
+        skip_forward_secs: Some(45),
+        default_speed: Some(1.5),
+        ..Default::default()
+    };
+    let cli = ConfigOverrides {
+        default_speed: Some(2.0),
+        ..Default::default()
+    };
+
+    let config = Config::load(&env.merge(cli)).unwrap();
+
+    assert_eq!(config.playback.volume_step, 0.2, "File should override defaults");
+    assert_eq!(config.playback.skip_forward_secs, 45, "Environment should override the file");
+    assert_eq!(config.playback.default_speed, 2.0, "Command line should override the environment");
+}
+
+#[test]
+fn test_load_rejects_out_of_range_values() {
+    let load = |content: &str, overrides: ConfigOverrides| {
+        let mut config_file = NamedTempFile::new().unwrap();
+        writeln!(config_file, "{}", content).unwrap();
+        Config::load(&ConfigOverrides { config_file: Some(config_file.path().to_path_buf()), ..overrides })
+    };
+
+    for content in [
+        "[playback]\nskip_forward_secs = 0",
+        "[playback]\nvolume_step = 1.5",
+        "[playback]\ntarget_lufs = 6.0",
+        "[playback]\nmax_pause_ms = 0",
+        "[playback]\nmax_volume = -1.0",
+        "[refresh]\ninterval_mins = 0",
+        "[refresh]\njitter_percent = 150",
+        "[refresh]\ninterval_mins = 120\nmax_interval_mins = 60",
+        "[network]\ntimeout_secs = 0",
+        "[network]\nretries = 1000",
+        "[network]\nmax_feed_mb = 0",
+        "[network]\nuser_agent = \"\"",
+        "[directory]\nlimit = 0",
+    ] {
+        assert!(load(content, ConfigOverrides::default()).is_err(), "{} should be rejected", content);
+    }
+    let error = load("", ConfigOverrides { default_speed: Some(f32::NAN), ..Default::default() }).unwrap_err();
+    assert!(error.to_string().contains("playback.default_speed"), "Error should name the setting: {}", error);
+    assert!(load("[playback]\ndefault_speed = 2.5", ConfigOverrides::default()).is_ok());
+}
+
+#[test]
+fn test_load_missing_explicit_file() {
+    let overrides = ConfigOverrides {
+        config_file: Some(PathBuf::from("nonexistent_config.toml")),
+        ..Default::default()
+    };
+    assert!(Config::load(&overrides).is_err());
+}
@@ -103,4 +103,105 @@ mod tests {
 
         assert_eq!(result, PlayerCommand::Pause);
     }
+
+    #[test]
+    fn test_translate_with_configured_steps() {
+        let playback = rss_reader::config::PlaybackConfig {
+            skip_forward_secs: 30,
+            skip_backward_secs: 15,
+            volume_step: 0.05,
+            ..Default::default()
+        };
+        let controls = KeyboardControls::from_config(&playback);
+
+        assert_eq!(controls.translate("f"), PlayerCommand::SkipForward(30));
+        assert_eq!(controls.translate("b"), PlayerCommand::SkipBackward(15));
+        assert_eq!(controls.translate("+"), PlayerCommand::VolumeUp(0.05));
+        assert_eq!(controls.translate("p"), PlayerCommand::Pause);
+        assert_eq!(controls.translate("m"), PlayerCommand::ToggleMute);
+    }
+
+    #[test]
+    fn test_parse_parameterized_commands() {
+        let controls = KeyboardControls::new();
+
+        assert_eq!(controls.parse("f 45").unwrap(), PlayerCommand::SkipForward(45));
+        assert_eq!(controls.parse("b 2m").unwrap(), PlayerCommand::SkipBackward(120));
+        assert_eq!(controls.parse("f 1m30s").unwrap(), PlayerCommand::SkipForward(90));
+        assert_eq!(controls.parse("f 1:02:30").unwrap(), PlayerCommand::SkipForward(3750));
+        assert_eq!(controls.parse("vol 0.6").unwrap(), PlayerCommand::SetVolume(0.6));
+        assert_eq!(controls.parse("speed 1.5").unwrap(), PlayerCommand::SetSpeed(1.5));
+        assert_eq!(controls.parse("+ 0.2").unwrap(), PlayerCommand::VolumeUp(0.2));
+        assert_eq!(controls.parse("  p  ").unwrap(), PlayerCommand::Pause);
+    }
+
+    #[test]
+    fn test_parse_errors_are_reported() {
+        let controls = KeyboardControls::new();
+
+        for input in ["x", "f soon", "b 2x", "vol", "vol -1", "speed 0", "speed fast", "speed 9", "p 5"] {
+            assert!(controls.parse(input).is_err(), "Expected a parse error for '{}'", input);
+        }
+
+        let error = controls.parse("vol").unwrap_err().to_string();
+        assert!(error.contains("vol 0.6"), "Error should show an example: {}", error);
+
+        // Times too long to count in seconds are rejected instead of overflowing
+        for input in ["f 9999999999999999999h", "b 9999999999999999999s", "f 9999999999999999999:00:00", "b 307445734561825861:00"] {
+            let error = controls.parse(input).unwrap_err().to_string();
+            assert!(error.contains("Invalid time"), "Unexpected error for '{}': {}", input, error);
+        }
+
+        // The lenient translation still maps invalid input to Ignore
+        assert_eq!(controls.translate("f soon"), PlayerCommand::Ignore);
+    }
+
+    #[test]
+    fn test_parse_seek_commands() {
+        let controls = KeyboardControls::new();
+
+        assert_eq!(controls.parse("seek 1:02:30").unwrap(), PlayerCommand::Seek(Duration::from_secs(3750)));
+        assert_eq!(controls.parse("goto 90s").unwrap(), PlayerCommand::Seek(Duration::from_secs(90)));
+        assert_eq!(controls.parse("seek 40%").unwrap(), PlayerCommand::SeekPercent(40.0));
+
+        for input in ["seek", "seek 140%", "seek soon", "seek 9999999999999999999:00:00"] {
+            assert!(controls.parse(input).is_err(), "Expected a parse error for '{}'", input);
+        }
+
+        let error = controls.parse("seek").unwrap_err().to_string();
+        assert!(error.contains("seek 1:02:30"), "Error should show an example: {}", error);
+    }
+
+    #[test]
+    fn test_parse_sleep_commands() {
+        use rss_reader::sleep_timer::SleepMode;
+        let controls = KeyboardControls::new();
+
+        // A plain number is minutes for the sleep timer
+        assert_eq!(controls.parse("sleep 30").unwrap(), PlayerCommand::Sleep(SleepMode::After(Duration::from_secs(1800))));
+        assert_eq!(controls.parse("sleep 1h").unwrap(), PlayerCommand::Sleep(SleepMode::After(Duration::from_secs(3600))));
+        assert_eq!(controls.parse("sleep end").unwrap(), PlayerCommand::Sleep(SleepMode::EndOfEpisode));
+        assert_eq!(controls.parse("sleep chapter").unwrap(), PlayerCommand::Sleep(SleepMode::EndOfChapter));
+        assert_eq!(controls.parse("sleep +10").unwrap(), PlayerCommand::ExtendSleep(Duration::from_secs(600)));
+        assert_eq!(controls.parse("sleep off").unwrap(), PlayerCommand::CancelSleep);
+        assert_eq!(controls.translate("z"), PlayerCommand::ExtendSleep(Duration::from_secs(900)));
+        assert_eq!(controls.translate("Z"), PlayerCommand::CancelSleep);
+
+        for input in ["sleep", "sleep 0", "sleep soon", "sleep +"] {
+            assert!(controls.parse(input).is_err(), "Expected a parse error for '{}'", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_device_commands() {
+        let controls = KeyboardControls::new();
+
+        assert_eq!(controls.parse("device list").unwrap(), PlayerCommand::ListOutputDevices);
+        assert_eq!(controls.parse("device default").unwrap(), PlayerCommand::SetOutputDevice(None));
+        assert_eq!(
+            controls.parse("device USB Headset").unwrap(),
+            PlayerCommand::SetOutputDevice(Some("USB Headset".to_string()))
+        );
+        assert!(controls.parse("device").is_err());
+    }
 }
@@ -0,0 +1,150 @@
+mod support;
+
+use rss_reader::config::Config;
+use rss_reader::directory::{
+    podcast_index_signature, DirectoryProvider, DirectoryResult, ItunesDirectory, PodcastIndexDirectory,
+};
+use support::{fast_client, Fixture, FixtureServer};
+
+const ITUNES_RESULTS: &str = r#"{
+  "resultCount": 3,
+  "results": [
+    {"wrapperType": "track", "kind": "podcast", "collectionName": "Welcome to Night Vale",
+     "artistName": "Night Vale Presents", "feedUrl": "https://feeds.example.com/nightvale", "trackCount": 250},
+    {"wrapperType": "track", "kind": "podcast", "collectionName": "No Feed Show", "artistName": "Someone"},
+    {"wrapperType": "track", "kind": "podcast", "collectionName": "Night Vale Fans",
+     "feedUrl": "https://fans.example.com/rss"}
+  ]
+}"#;
+
+const PODCAST_INDEX_RESULTS: &str = r#"{
+  "status": "true",
+  "feeds": [
+    {"id": 1, "title": "Welcome to Night Vale", "url": "https://feeds.example.com/nightvale",
+     "author": "Night Vale Presents", "description": "Twice-monthly community updates", "episodeCount": 250},
+    {"id": 2, "title": "Night Vale Fans", "url": "https://fans.example.com/rss", "author": "", "description": ""}
+  ],
+  "count": 2
+}"#;
+
+fn json(body: &str) -> Fixture {
+    Fixture::ok(body).with_header("Content-Type", "application/json")
+}
+
+#[test]
+fn test_itunes_search() {
+    let server = FixtureServer::start();
+    server.serve("/search", json(ITUNES_RESULTS));
+    let directory = ItunesDirectory::with_base_url(fast_client(), server.url(""));
+
+    let results = directory.search("night vale", 10).unwrap();
+
+    assert_eq!(results, vec![
+        DirectoryResult {
+            title: "Welcome to Night Vale".to_string(),
+            author: Some("Night Vale Presents".to_string()),
+            feed_url: "https://feeds.example.com/nightvale".to_string(),
+            description: None,
+            episode_count: Some(250),
+        },
+        DirectoryResult {
+            title: "Night Vale Fans".to_string(),
+            author: None,
+            feed_url: "https://fans.example.com/rss".to_string(),
+            description: None,
+            episode_count: None,
+        },
+    ]);
+    let path = &server.requests()[0].path;
+    assert!(path.starts_with("/search?"), "Unexpected request {}", path);
+    for param in ["media=podcast", "entity=podcast", "term=night+vale", "limit=10"] {
+        assert!(path.contains(param), "{} is missing from {}", param, path);
+    }
+}
+
+#[test]
+fn test_podcast_index_search_signs_requests() {
+    let server = FixtureServer::start();
+    server.serve("/api/1.0/search/byterm", json(PODCAST_INDEX_RESULTS));
+    let directory = PodcastIndexDirectory::with_base_url(fast_client(), server.url("/api/1.0/"), "KEY", "SECRET");
+
+    let results = directory.search("night vale", 1).unwrap();
+
+    assert_eq!(results.len(), 1, "Results beyond the limit should be dropped");
+    assert_eq!(results[0].description.as_deref(), Some("Twice-monthly community updates"));
+    assert_eq!(results[0].episode_count, Some(250));
+
+    let request = &server.requests()[0];
+    assert!(request.path.contains("q=night+vale") && request.path.contains("max=1"), "Unexpected request {}", request.path);
+    let date = request.header("X-Auth-Date").expect("X-Auth-Date should be sent");
+    assert_eq!(request.header("X-Auth-Key"), Some("KEY"));
+    assert_eq!(request.header("Authorization"), Some(podcast_index_signature("KEY", "SECRET", date).as_str()));
+    assert!(request.header("User-Agent").is_some_and(|agent| agent.starts_with("rss_reader/")));
+}
+
+#[test]
+fn test_podcast_index_empty_author_and_description_are_left_out() {
+    let server = FixtureServer::start();
+    server.serve("/search/byterm", json(PODCAST_INDEX_RESULTS));
+    let directory = PodcastIndexDirectory::with_base_url(fast_client(), server.url(""), "KEY", "SECRET");
+
+    let results = directory.search("night vale", 10).unwrap();
+
+    assert_eq!(results[1].author, None);
+    assert_eq!(results[1].description, None);
+}
+
+#[test]
+fn test_podcast_index_signature() {
+    // The SHA-1 of "keysecret1700000000"
+    assert_eq!(podcast_index_signature("key", "secret", "1700000000"), "abaf71c02050c31e4d4e6b08c1625173af0445ba");
+}
+
+#[test]
+fn test_podcast_index_needs_credentials() {
+    let server = FixtureServer::start();
+    let directory = PodcastIndexDirectory::with_base_url(fast_client(), server.url(""), "", "");
+
+    let error = directory.search("night vale", 10).unwrap_err();
+
+    assert!(error.to_string().contains("API key"), "Unexpected error: {}", error);
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn test_search_reports_errors() {
+    let server = FixtureServer::start();
+    server.serve("/search", Fixture::ok("<html>Maintenance</html>"));
+    server.serve("/search/byterm", Fixture::status(401));
+
+    let error = ItunesDirectory::with_base_url(fast_client(), server.url("")).search("news", 5).unwrap_err();
+    assert!(error.to_string().contains("Unexpected answer"), "Unexpected error: {}", error);
+
+    let error = PodcastIndexDirectory::with_base_url(fast_client(), server.url(""), "KEY", "SECRET")
+        .search("news", 5)
+        .unwrap_err();
+    assert!(error.to_string().contains("401"), "Unexpected error: {}", error);
+}
+
+#[test]
+fn test_provider_from_config() {
+    let server = FixtureServer::start();
+    server.serve("/search", json(ITUNES_RESULTS));
+    server.serve("/pi/search/byterm", json(PODCAST_INDEX_RESULTS));
+    let config = Config::from_toml(&format!(
+        "[directory]\nitunes_url = \"{}\"\npodcastindex_url = \"{}\"\npodcastindex_key = \"KEY\"\npodcastindex_secret = \"SECRET\"",
+        server.url(""),
+        server.url("/pi"),
+    ))
+    .unwrap();
+
+    let itunes = config.directory.provider(fast_client());
+    assert_eq!(itunes.name(), "iTunes");
+    assert_eq!(itunes.search("night vale", 10).unwrap().len(), 2);
+
+    let mut config = config;
+    config.directory.provider = "podcastindex".parse().unwrap();
+    let podcast_index = config.directory.provider(fast_client());
+    assert_eq!(podcast_index.name(), "Podcast Index");
+    assert_eq!(podcast_index.search("night vale", 10).unwrap()[0].title, "Welcome to Night Vale");
+}
@@ -0,0 +1,88 @@
+mod support;
+
+use rss_reader::discovery::{discover_feeds, feed_links, FeedCandidate};
+use support::{fast_client, Fixture, FixtureServer};
+
+const FEED: &str = r#"<rss version="2.0"><channel><title>The Show</title>
+<item><title>Episode</title><enclosure url="https://example.com/1.mp3" length="1" type="audio/mpeg"/></item>
+</channel></rss>"#;
+
+fn page(head: &str) -> Fixture {
+    Fixture::ok(format!("<!DOCTYPE html><html><head>{}</head><body>Hi</body></html>", head))
+        .with_header("Content-Type", "text/html")
+}
+
+#[test]
+fn test_feed_links_finds_alternate_rss_links() {
+    let html = r#"
+        <link rel="stylesheet" href="/style.css">
+        <LINK REL="alternate" TYPE="application/rss+xml" TITLE="Main feed" HREF="/feed.xml">
+        <link rel='alternate' type='application/atom+xml' href='/atom.xml'>
+        <link type=application/rss+xml rel=alternate href=https://cdn.example.com/rss?a=1&amp;b=2 />
+        <link rel="alternate nofollow" type="application/rss+xml" href="comments.xml" title="">
+    "#;
+
+    let links = feed_links(html, "https://example.com/shows/one");
+
+    assert_eq!(links, vec![
+        FeedCandidate { url: "https://example.com/feed.xml".to_string(), title: Some("Main feed".to_string()) },
+        FeedCandidate { url: "https://cdn.example.com/rss?a=1&b=2".to_string(), title: None },
+        FeedCandidate { url: "https://example.com/shows/comments.xml".to_string(), title: None },
+    ]);
+}
+
+#[test]
+fn test_feed_links_ignores_pages_without_feeds() {
+    assert!(feed_links("<html><head><title>No feed</title></head></html>", "https://example.com").is_empty());
+    assert!(feed_links("<link rel=\"alternate\" hreflang=\"de\" href=\"/de\">", "https://example.com").is_empty());
+    assert!(feed_links("<link", "https://example.com").is_empty());
+}
+
+#[test]
+fn test_discover_accepts_a_feed_url() {
+    let server = FixtureServer::start();
+    server.serve("/feed.rss", Fixture::feed(FEED));
+
+    let candidates = discover_feeds(&fast_client(), &server.url("/feed.rss")).unwrap();
+
+    assert_eq!(candidates, vec![FeedCandidate { url: server.url("/feed.rss"), title: Some("The Show".to_string()) }]);
+}
+
+#[test]
+fn test_discover_follows_links_on_a_homepage() {
+    let server = FixtureServer::start();
+    server.serve("/", page(r#"
+        <link rel="alternate" type="application/rss+xml" title="MP3" href="/mp3.rss">
+        <link rel="alternate" type="application/rss+xml" title="AAC" href="/aac.rss">
+        <link rel="alternate" type="application/rss+xml" title="MP3 again" href="/mp3.rss">
+    "#));
+
+    let candidates = discover_feeds(&fast_client(), &server.url("/")).unwrap();
+
+    let urls: Vec<&str> = candidates.iter().map(|candidate| candidate.url.as_str()).collect();
+    assert_eq!(urls, vec![server.url("/mp3.rss"), server.url("/aac.rss")]);
+    assert_eq!(candidates[0].title.as_deref(), Some("MP3"));
+}
+
+#[test]
+fn test_discover_tries_common_feed_paths() {
+    let server = FixtureServer::start();
+    server.serve("/show", page("<title>No links here</title>"));
+    server.serve("/rss", Fixture::feed(FEED));
+
+    let candidates = discover_feeds(&fast_client(), &server.url("/show")).unwrap();
+
+    assert_eq!(candidates, vec![FeedCandidate { url: server.url("/rss"), title: Some("The Show".to_string()) }]);
+    assert_eq!(server.requests_to("/feed").len(), 1, "Earlier paths should have been tried first");
+}
+
+#[test]
+fn test_discover_fails_when_there_is_no_feed() {
+    let server = FixtureServer::start();
+    server.serve("/", page("<title>Nothing</title>"));
+
+    let error = discover_feeds(&fast_client(), &server.url("/")).unwrap_err();
+
+    assert!(error.to_string().contains("No feed found"), "Unexpected error: {}", error);
+    assert!(discover_feeds(&fast_client(), &server.url("/missing")).is_err());
+}
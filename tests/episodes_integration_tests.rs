@@ -1,5 +1,10 @@
-use rss_reader::episodes::{read_rss_feeds, fetch_episodes};
+mod support;
+
+use rss_reader::episodes::{add_rss_feed, read_rss_feeds, fetch_episodes, move_rss_feed};
+use rss_reader::feed_source::{FeedSource, HttpSource};
+use rss_reader::podcast_manager::{load_podcasts, PodcastStore};
 use std::io::Write;
+use support::{fast_client, Fixture, FixtureServer};
 use tempfile::NamedTempFile;
 
 #[test]
@@ -47,7 +52,7 @@ fn test_read_rss_feeds_file_not_found() {
     let result = read_rss_feeds("nonexistent_file.txt");
     assert!(result.is_err());
     if let Err(e) = result {
-        assert!(e.downcast_ref::<std::io::Error>().map_or(false, |io_err| io_err.kind() == std::io::ErrorKind::NotFound), 
+        assert!(e.downcast_ref::<std::io::Error>().is_some_and(|io_err| io_err.kind() == std::io::ErrorKind::NotFound), 
                 "Expected a 'file not found' error, got: {}", e);
     }
 }
@@ -73,10 +78,25 @@ fn test_read_rss_feeds_with_comments() {
     assert_eq!(feeds[3], "https://feed4.com/rss");
 }
 
+#[test]
+fn test_add_rss_feed_appends_new_feeds_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("feeds").join("rss-db.txt");
+    let path = path.to_str().unwrap();
+
+    assert!(add_rss_feed(path, "https://example.com/a.rss").unwrap(), "A missing file should be created");
+    std::fs::write(path, "# My feeds\nhttps://example.com/a.rss").unwrap();
+    assert!(add_rss_feed(path, "https://example.com/b.rss").unwrap());
+    assert!(!add_rss_feed(path, "https://example.com/a.rss").unwrap());
+
+    assert_eq!(std::fs::read_to_string(path).unwrap(), "# My feeds\nhttps://example.com/a.rss\nhttps://example.com/b.rss\n");
+    assert_eq!(read_rss_feeds(path).unwrap().len(), 2);
+}
+
 #[test]
 fn test_fetch_episodes_real_feed() {
     let feed_url = "https://feeds.zencastr.com/f/oSn1i316.rss";
-    match fetch_episodes(feed_url) {
+    match fetch_episodes(&HttpSource::new(), feed_url) {
         Ok(episodes) => {
             println!("\nFound {} episodes in feed {}", episodes.len(), feed_url);
             
@@ -130,6 +150,155 @@ fn test_fetch_episodes_real_feed() {
 
 #[test]
 fn test_fetch_episodes_invalid_url() {
-    let result = fetch_episodes("https://invalid.url/feed.rss");
+    let result = fetch_episodes(&HttpSource::with_client(fast_client()), "https://invalid.url/feed.rss");
     assert!(result.is_err());
 }
+
+#[test]
+fn test_fetch_episodes_from_fixture_server() {
+    let server = FixtureServer::start();
+    server.serve("/feed.rss", Fixture::feed_file("test-feed.rss"));
+
+    let episodes = fetch_episodes(&HttpSource::with_client(fast_client()), &server.url("/feed.rss")).unwrap();
+
+    assert_eq!(episodes.len(), 1);
+    assert_eq!(episodes[0].title, "Programming As An Expressive Instrument (with Sam Aaron)");
+    assert!(episodes[0].audio_url.is_some());
+    let requests = server.requests_to("/feed.rss");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "GET");
+}
+
+#[test]
+fn test_fetch_episodes_follows_redirects() {
+    let server = FixtureServer::start();
+    server.serve("/old.rss", Fixture::redirect(301, &server.url("/new.rss")));
+    server.serve("/new.rss", Fixture::feed_file("test-feed.rss"));
+
+    let episodes = fetch_episodes(&HttpSource::with_client(fast_client()), &server.url("/old.rss")).unwrap();
+
+    assert_eq!(episodes.len(), 1);
+    assert_eq!(server.requests_to("/new.rss").len(), 1);
+}
+
+#[test]
+fn test_fetch_episodes_rejects_a_non_feed_response() {
+    let server = FixtureServer::start();
+    server.serve("/error.rss", Fixture::status(403));
+
+    assert!(fetch_episodes(&HttpSource::with_client(fast_client()), &server.url("/error.rss")).is_err());
+    assert!(fetch_episodes(&HttpSource::with_client(fast_client()), &server.url("/missing.rss")).is_err());
+}
+
+/// A feed that stays where it is, unlike `test-feed.rss`, which announces a new URL.
+const STAYING_FEED: &str = r#"<rss version="2.0"><channel><title>Stays</title>
+<item><title>Episode</title><enclosure url="https://example.com/1.mp3" length="1" type="audio/mpeg"/></item>
+</channel></rss>"#;
+
+#[test]
+fn test_load_podcasts_from_fixture_server() {
+    let server = FixtureServer::start();
+    server.serve("/a.rss", Fixture::feed(STAYING_FEED));
+    server.serve("/b.rss", Fixture::feed(STAYING_FEED));
+    let mut feeds = NamedTempFile::new().unwrap();
+    writeln!(feeds, "{}\n{}", server.url("/a.rss"), server.url("/b.rss")).unwrap();
+
+    let mut store = PodcastStore::new();
+    load_podcasts(feeds.path().to_str().unwrap(), &mut store, &HttpSource::with_client(fast_client())).unwrap();
+
+    assert_eq!(store.list_podcast_urls().len(), 2);
+    assert_eq!(store.get_episodes(&server.url("/a.rss")).unwrap().len(), 1);
+}
+
+#[test]
+fn test_feed_follows_permanent_redirects_only() {
+    let server = FixtureServer::start();
+    server.serve("/moved.rss", Fixture::redirect(301, &server.url("/hop.rss")));
+    server.serve("/hop.rss", Fixture::redirect(308, &server.url("/new.rss")));
+    server.serve("/temporary.rss", Fixture::redirect(302, &server.url("/hop.rss")));
+    server.serve("/new.rss", Fixture::feed(STAYING_FEED));
+    let source = HttpSource::new();
+
+    assert_eq!(source.feed(&server.url("/moved.rss")).unwrap().moved_to, Some(server.url("/new.rss")));
+    assert_eq!(source.feed(&server.url("/temporary.rss")).unwrap().moved_to, None);
+    assert_eq!(source.feed(&server.url("/new.rss")).unwrap().moved_to, None);
+}
+
+/// A feed that announces it moved to `new_url`.
+fn announcing_feed(new_url: &str) -> String {
+    format!(
+        r#"<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"><channel><title>Old</title>
+<itunes:new-feed-url>{}</itunes:new-feed-url>
+<item><title>Old episode</title><enclosure url="https://example.com/old.mp3" length="1" type="audio/mpeg"/></item>
+</channel></rss>"#,
+        new_url
+    )
+}
+
+#[test]
+fn test_feed_follows_itunes_new_feed_url_that_works() {
+    let server = FixtureServer::start();
+    server.serve("/feed.rss", Fixture::feed(announcing_feed(&server.url("/new.rss"))));
+    server.serve("/new.rss", Fixture::feed(STAYING_FEED));
+    server.serve("/broken.rss", Fixture::feed(announcing_feed(&server.url("/missing.rss"))));
+    let source = HttpSource::with_client(fast_client());
+
+    let feed = source.feed(&server.url("/feed.rss")).unwrap();
+    assert_eq!(feed.moved_to, Some(server.url("/new.rss")));
+    assert_eq!(feed.episodes[0].title, "Episode");
+
+    let feed = source.feed(&server.url("/broken.rss")).unwrap();
+    assert_eq!(feed.moved_to, None, "A new URL that does not work should not count as a move");
+    assert_eq!(feed.episodes[0].title, "Old episode");
+}
+
+#[test]
+fn test_feed_ignores_redirects_to_the_same_feed() {
+    let server = FixtureServer::start();
+    server.serve("/feed", Fixture::redirect(301, &server.url("/feed/")));
+    server.serve("/feed/", Fixture::feed(STAYING_FEED));
+    let mut feeds = NamedTempFile::new().unwrap();
+    writeln!(feeds, "{}", server.url("/feed")).unwrap();
+
+    let mut store = PodcastStore::new();
+    let moves = load_podcasts(feeds.path().to_str().unwrap(), &mut store, &HttpSource::with_client(fast_client())).unwrap();
+
+    assert!(moves.is_empty());
+    assert_eq!(std::fs::read_to_string(feeds.path()).unwrap(), format!("{}\n", server.url("/feed")));
+}
+
+#[test]
+fn test_load_podcasts_files_moved_feeds_under_their_new_url() {
+    let server = FixtureServer::start();
+    server.serve("/old.rss", Fixture::redirect(301, &server.url("/new.rss")));
+    server.serve("/new.rss", Fixture::feed(STAYING_FEED));
+    let mut feeds = NamedTempFile::new().unwrap();
+    writeln!(feeds, "{}", server.url("/old.rss")).unwrap();
+
+    let mut store = PodcastStore::new();
+    let moves = load_podcasts(feeds.path().to_str().unwrap(), &mut store, &HttpSource::new()).unwrap();
+
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].from, server.url("/old.rss"));
+    assert_eq!(store.list_podcast_urls(), vec![server.url("/new.rss")]);
+    assert_eq!(std::fs::read_to_string(feeds.path()).unwrap(), format!("{}\n", server.url("/new.rss")));
+}
+
+#[test]
+fn test_move_rss_feed_keeps_its_place() {
+    let mut feeds = NamedTempFile::new().unwrap();
+    write!(feeds, "# Favourites\nhttp://example.com/a\nhttps://example.com/b\n").unwrap();
+    let filename = feeds.path().to_str().unwrap();
+
+    assert!(!move_rss_feed(filename, "http://example.com/a", "https://example.com/a/").unwrap());
+    assert!(!move_rss_feed(filename, "https://example.com/gone", "https://example.com/c").unwrap());
+    assert!(move_rss_feed(filename, "http://example.com/a", "https://new.example.com/a").unwrap());
+    assert_eq!(
+        std::fs::read_to_string(feeds.path()).unwrap(),
+        "# Favourites\nhttps://new.example.com/a\nhttps://example.com/b\n"
+    );
+
+    // Moving onto a feed that is already listed leaves just that one
+    assert!(move_rss_feed(filename, "https://new.example.com/a", "http://example.com/b").unwrap());
+    assert_eq!(std::fs::read_to_string(feeds.path()).unwrap(), "# Favourites\nhttps://example.com/b\n");
+}
@@ -30,6 +30,8 @@ mod tests {
         println!("Episode audio URL: {:?}", first_episode.audio_url);
         assert!(first_episode.title.contains("Sam"), "Episode title should contain 'Sam'");
         assert!(first_episode.audio_url.is_some(), "Episode should have an audio URL");
+        assert_eq!(first_episode.mime_type.as_deref(), Some("audio/mpeg"));
+        assert_eq!(first_episode.enclosure_length, Some(158427784));
     }
 
     #[test]
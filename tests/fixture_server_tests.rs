@@ -0,0 +1,55 @@
+mod support;
+
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use std::time::Duration;
+use support::{Fixture, FixtureServer};
+
+#[test]
+fn test_fixture_server_answers_with_the_etag_and_not_modified() {
+    let server = FixtureServer::start();
+    server.serve("/feed.rss", Fixture::feed("<rss/>").with_etag("\"v1\""));
+    let client = Client::new();
+
+    let first = client.get(server.url("/feed.rss")).send().unwrap();
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(first.headers()["etag"], "\"v1\"");
+
+    let second = client.get(server.url("/feed.rss")).header("If-None-Match", "\"v1\"").send().unwrap();
+    assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+    assert!(second.bytes().unwrap().is_empty());
+    assert_eq!(server.requests()[1].header("if-none-match"), Some("\"v1\""));
+}
+
+#[test]
+fn test_fixture_server_serves_byte_ranges() {
+    let server = FixtureServer::start();
+    server.serve("/audio.mp3", Fixture::audio(b"0123456789".to_vec(), "audio/mpeg"));
+    let client = Client::new();
+    let get = |range: &str| client.get(server.url("/audio.mp3")).header("Range", range).send().unwrap();
+
+    let partial = get("bytes=2-4");
+    assert_eq!(partial.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(partial.headers()["content-range"], "bytes 2-4/10");
+    assert_eq!(&partial.bytes().unwrap()[..], b"234");
+    assert_eq!(&get("bytes=7-").bytes().unwrap()[..], b"789");
+    assert_eq!(&get("bytes=-2").bytes().unwrap()[..], b"89");
+    assert_eq!(get("bytes=20-30").status(), StatusCode::RANGE_NOT_SATISFIABLE);
+}
+
+#[test]
+fn test_fixture_server_plays_sequences_and_delays() {
+    let server = FixtureServer::start();
+    server.serve_sequence("/flaky.rss", vec![Fixture::status(503), Fixture::feed("<rss/>")]);
+    server.serve("/slow.rss", Fixture::feed("<rss/>").with_delay(Duration::from_millis(500)));
+
+    let client = Client::new();
+    let status = || client.get(server.url("/flaky.rss")).send().unwrap().status();
+    assert_eq!(status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(status(), StatusCode::OK);
+    assert_eq!(status(), StatusCode::OK);
+
+    let impatient = Client::builder().timeout(Duration::from_millis(100)).build().unwrap();
+    assert!(impatient.get(server.url("/slow.rss")).send().unwrap_err().is_timeout());
+    assert_eq!(client.get(server.url("/missing")).send().unwrap().status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,144 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use rss_reader::config::{Config, KeyList};
+use rss_reader::key_bindings::{
+    Bindings, ChordMatcher, KeyBindings, KeyChord, KeyPress, PlayerAction, TuiAction,
+};
+use rss_reader::keyboard_controls::KeyboardControls;
+use rss_reader::PlayerCommand;
+use std::collections::HashMap;
+
+fn key(c: char) -> KeyPress {
+    KeyPress::new(KeyCode::Char(c), KeyModifiers::NONE)
+}
+
+fn overrides(entries: &[(&str, &[&str])]) -> HashMap<String, KeyList> {
+    entries
+        .iter()
+        .map(|(action, keys)| {
+            (action.to_string(), KeyList::Many(keys.iter().map(|k| k.to_string()).collect()))
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_key_press() {
+    assert_eq!(KeyPress::parse("p").unwrap(), key('p'));
+    assert_eq!(KeyPress::parse("+").unwrap(), key('+'));
+    assert_eq!(KeyPress::parse("space").unwrap(), key(' '));
+    assert_eq!(
+        KeyPress::parse("ctrl+c").unwrap(),
+        KeyPress::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+    );
+    assert_eq!(
+        KeyPress::parse("alt++").unwrap(),
+        KeyPress::new(KeyCode::Char('+'), KeyModifiers::ALT)
+    );
+    assert_eq!(KeyPress::parse("shift+g").unwrap(), KeyPress::parse("G").unwrap());
+    assert_eq!(KeyPress::parse("left").unwrap(), KeyPress::new(KeyCode::Left, KeyModifiers::NONE));
+    assert_eq!(KeyPress::parse("F5").unwrap(), KeyPress::new(KeyCode::F(5), KeyModifiers::NONE));
+
+    assert!(KeyPress::parse("hyper+x").is_err());
+    assert!(KeyPress::parse("nope").is_err());
+}
+
+#[test]
+fn test_key_chord_display_round_trip() {
+    for spec in ["g g", "ctrl+x s", "space", "alt+left"] {
+        let chord = KeyChord::parse(spec).unwrap();
+        assert_eq!(chord.to_string(), spec);
+        assert_eq!(KeyChord::parse(&chord.to_string()).unwrap(), chord);
+    }
+}
+
+#[test]
+fn test_line_input() {
+    assert_eq!(KeyChord::parse("g g").unwrap().as_line_input().as_deref(), Some("gg"));
+    assert_eq!(KeyChord::parse("ctrl+c").unwrap().as_line_input(), None);
+    assert_eq!(KeyChord::parse("left").unwrap().as_line_input(), None);
+}
+
+#[test]
+fn test_override_replaces_default_keys() {
+    let bindings: Bindings<PlayerAction> =
+        Bindings::from_config(&overrides(&[("pause", &["space", "x"])])).unwrap();
+
+    assert_eq!(bindings.action_for_line("x"), Some(PlayerAction::Pause));
+    assert_eq!(bindings.action_for_line("p"), None, "Default key should be replaced");
+    assert_eq!(bindings.action_for_line("q"), Some(PlayerAction::Quit));
+}
+
+#[test]
+fn test_conflicting_bindings_are_rejected() {
+    let result = Bindings::<PlayerAction>::from_config(&overrides(&[("pause", &["q"])]));
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("pause") && error.contains("quit"), "Unexpected error: {}", error);
+
+    let result = Bindings::<TuiAction>::from_config(&overrides(&[("help", &["g"])]));
+    assert!(result.unwrap_err().to_string().contains("shadows"));
+}
+
+#[test]
+fn test_unknown_action_is_rejected() {
+    let result = Bindings::<PlayerAction>::from_config(&overrides(&[("dance", &["d"])]));
+    assert!(result.unwrap_err().to_string().contains("dance"));
+}
+
+#[test]
+fn test_chord_matcher() {
+    let bindings = Bindings::<TuiAction>::default();
+    let mut matcher = ChordMatcher::new();
+
+    assert_eq!(matcher.feed(key('g'), &bindings), None);
+    assert_eq!(matcher.pending().len(), 1);
+    assert_eq!(matcher.feed(key('g'), &bindings), Some(TuiAction::Top));
+
+    // An abandoned chord does not swallow the next key
+    assert_eq!(matcher.feed(key('g'), &bindings), None);
+    assert_eq!(matcher.feed(key('j'), &bindings), Some(TuiAction::Down));
+    assert!(matcher.pending().is_empty());
+
+    let ctrl_c = KeyPress::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+    assert_eq!(matcher.feed(ctrl_c, &bindings), Some(TuiAction::Quit));
+    assert_eq!(matcher.feed(key('x'), &bindings), None);
+}
+
+#[test]
+fn test_help_lists_descriptions() {
+    let help = Bindings::<PlayerAction>::default().help_text();
+    assert!(help.contains("Pause or resume playback"));
+    assert!(help.contains("h, ?"));
+    assert_eq!(help.lines().count(), 10);
+}
+
+#[test]
+fn test_line_help_leaves_out_chords_that_cannot_be_typed() {
+    let bindings = Bindings::<PlayerAction>::from_config(&overrides(&[("pause", &["p", "ctrl+p"]), ("quit", &["esc"])])).unwrap();
+    let help = bindings.line_help_text();
+
+    assert!(help.contains("  p "), "Unexpected help:\n{}", help);
+    assert!(!help.contains("ctrl+p"));
+    assert!(!help.contains("and quit"), "Quit has no key that can be typed:\n{}", help);
+    assert_eq!(bindings.action_for_line("ctrl+p"), None);
+}
+
+#[test]
+fn test_key_bindings_from_config_file() {
+    let config = Config::from_toml(r#"
+        [keys.player]
+        pause = "space"
+        skip_forward = ["f", "l"]
+
+        [keys.tui]
+        quit = "ctrl+q"
+    "#).unwrap();
+
+    let bindings = KeyBindings::from_config(&config.keys).unwrap();
+    let controls = KeyboardControls::with_bindings(bindings.player, &config.playback);
+
+    assert_eq!(controls.translate("l"), PlayerCommand::SkipForward(10));
+    assert_eq!(
+        bindings.tui.action_for(&KeyChord::parse("ctrl+q").unwrap()),
+        Some(TuiAction::Quit)
+    );
+    assert_eq!(bindings.tui.action_for(&KeyChord::parse("q").unwrap()), None);
+}
@@ -0,0 +1,41 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use rss_reader::key_input::KeyEventInput;
+use rss_reader::keyboard_controls::KeyboardControls;
+use rss_reader::PlayerCommand;
+
+fn press(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+#[test]
+fn test_arrow_keys_and_space() {
+    let controls = KeyboardControls::new();
+    let mut input = KeyEventInput::new(&controls);
+
+    assert_eq!(input.handle_key(press(KeyCode::Right)), PlayerCommand::SkipForward(10));
+    assert_eq!(input.handle_key(press(KeyCode::Left)), PlayerCommand::SkipBackward(10));
+    assert_eq!(input.handle_key(press(KeyCode::Up)), PlayerCommand::VolumeUp(0.1));
+    assert_eq!(input.handle_key(press(KeyCode::Down)), PlayerCommand::VolumeDown(0.1));
+    assert_eq!(input.handle_key(press(KeyCode::Char(' '))), PlayerCommand::Pause);
+    assert_eq!(input.handle_key(press(KeyCode::Char('q'))), PlayerCommand::Quit);
+}
+
+#[test]
+fn test_ctrl_c_quits_in_raw_mode() {
+    let controls = KeyboardControls::new();
+    let mut input = KeyEventInput::new(&controls);
+
+    let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+    assert_eq!(input.handle_key(ctrl_c), PlayerCommand::Quit);
+}
+
+#[test]
+fn test_release_and_unbound_keys_are_ignored() {
+    let controls = KeyboardControls::new();
+    let mut input = KeyEventInput::new(&controls);
+
+    let release = KeyEvent::new_with_kind(KeyCode::Char('p'), KeyModifiers::NONE, KeyEventKind::Release);
+    assert_eq!(input.handle_key(release), PlayerCommand::Ignore);
+    assert_eq!(input.handle_key(press(KeyCode::Char('x'))), PlayerCommand::Ignore);
+    assert_eq!(input.handle_key(press(KeyCode::Char('p'))), PlayerCommand::Pause);
+}
@@ -0,0 +1,86 @@
+use rodio::buffer::SamplesBuffer;
+use rss_reader::loudness::{
+    measure, normalization_gain, LoudnessCache, LoudnessMeter, Normalized, SharedGain,
+};
+use std::f64::consts::PI;
+
+const RATE: u32 = 48000;
+
+/// A 997 Hz sine with the given peak level, the reference signal of EBU R128 test material.
+fn sine(dbfs: f64, seconds: f64) -> impl Iterator<Item = f32> {
+    let amplitude = 10f64.powf(dbfs / 20.0);
+    (0..(seconds * RATE as f64) as usize)
+        .map(move |i| (amplitude * (2.0 * PI * 997.0 * i as f64 / RATE as f64).sin()) as f32)
+}
+
+fn integrated(channels: u16, samples: impl Iterator<Item = f32>) -> Option<f64> {
+    let mut meter = LoudnessMeter::new(channels, RATE);
+    for sample in samples {
+        for _ in 0..channels {
+            meter.push(sample);
+        }
+    }
+    meter.integrated()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 0.1, "expected {} LUFS, got {}", expected, actual);
+}
+
+#[test]
+fn test_reference_sine() {
+    // EBU Tech 3341: a stereo 997 Hz sine at -23 dBFS reads -23 LUFS
+    assert_close(integrated(2, sine(-23.0, 5.0)).unwrap(), -23.0);
+    // A single channel carries half the power
+    assert_close(integrated(1, sine(-23.0, 5.0)).unwrap(), -26.0);
+}
+
+#[test]
+fn test_quiet_passages_are_gated() {
+    // Without gating the quiet half would pull this down to about -26 LUFS; only the few
+    // blocks straddling the transition count slightly below -23
+    let samples = sine(-23.0, 5.0).chain(sine(-60.0, 5.0));
+    let lufs = integrated(2, samples).unwrap();
+    assert!((-23.2..=-23.0).contains(&lufs), "got {} LUFS", lufs);
+}
+
+#[test]
+fn test_silence_has_no_loudness() {
+    assert_eq!(integrated(2, std::iter::repeat_n(0.0, RATE as usize * 2)), None);
+}
+
+#[test]
+fn test_measure_source() {
+    let samples: Vec<i16> = sine(-20.0, 3.0).map(|s| (s * i16::MAX as f32) as i16).collect();
+    assert_close(measure(SamplesBuffer::new(1, RATE, samples)).unwrap(), -23.0);
+}
+
+#[test]
+fn test_normalization_gain() {
+    assert!((normalization_gain(-26.0, -16.0) - 3.1623).abs() < 0.001);
+    assert!((normalization_gain(-16.0, -16.0) - 1.0).abs() < f32::EPSILON);
+    assert!(normalization_gain(-10.0, -16.0) < 1.0);
+}
+
+#[test]
+fn test_limiter_prevents_clipping() {
+    let loud: Vec<i16> = sine(-3.0, 1.0).map(|s| (s * i16::MAX as f32) as i16).collect();
+    let normalized = Normalized::new(SamplesBuffer::new(1, RATE, loud), SharedGain::new(4.0));
+
+    let peak = normalized.fold(0.0f32, |peak, s| peak.max(s.abs()));
+    assert!(peak <= 0.892, "peak {} exceeds the limiter ceiling", peak);
+    assert!(peak > 0.85, "limiter should not squash the signal, peak {}", peak);
+}
+
+#[test]
+fn test_cache_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = LoudnessCache::default_path(dir.path());
+
+    let mut cache = LoudnessCache::load(&path).unwrap();
+    assert_eq!(cache.get("https://example.com/ep1.mp3"), None);
+    cache.insert("https://example.com/ep1.mp3", -19.5).unwrap();
+
+    let reloaded = LoudnessCache::load(&path).unwrap();
+    assert_eq!(reloaded.get("https://example.com/ep1.mp3"), Some(-19.5));
+}
@@ -0,0 +1,158 @@
+use rss_reader::episodes::Episode;
+use rss_reader::feed_source::{FeedDocument, FeedSource, MemorySource};
+use rss_reader::podcast_manager::{Podcast, PodcastStore};
+use rss_reader::refresh_scheduler::{BackgroundRefresh, RefreshEvent, RefreshSchedule, ScheduleSettings};
+use rss_reader::subscriptions::read_subscriptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+fn settings() -> ScheduleSettings {
+    ScheduleSettings { interval: HOUR, jitter: 0.0, max_interval: 24 * HOUR }
+}
+
+fn feed(episodes: &[&str]) -> String {
+    let items: String = episodes
+        .iter()
+        .map(|title| format!(r#"<item><title>{0}</title><enclosure url="https://example.com/{0}.mp3" length="1" type="audio/mpeg"/></item>"#, title))
+        .collect();
+    format!("<rss version=\"2.0\"><channel><title>Feed</title>{}</channel></rss>", items)
+}
+
+/// A memory source the test can still change while the background thread reads it.
+#[derive(Clone, Default)]
+struct SharedSource(Arc<Mutex<MemorySource>>);
+
+impl FeedSource for SharedSource {
+    fn fetch(&self, url: &str) -> anyhow::Result<FeedDocument> {
+        self.0.lock().unwrap().fetch(url)
+    }
+}
+
+/// The first event `pick` takes, skipping the others.
+fn wait_for<T>(refresh: &BackgroundRefresh, mut pick: impl FnMut(RefreshEvent) -> Option<T>) -> T {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if let Some(found) = refresh.events().into_iter().find_map(&mut pick) {
+            return found;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("No such refresh happened");
+}
+
+#[test]
+fn test_feeds_come_due_after_the_interval_or_their_longer_hint() {
+    let now = Instant::now();
+    let mut schedule = RefreshSchedule::new(settings());
+    schedule.add("plain", None, now);
+    schedule.add("ttl", Some(3 * HOUR), now);
+    schedule.add("eager", Some(Duration::from_secs(60)), now);
+    schedule.add("yearly", Some(365 * 24 * HOUR), now);
+
+    assert_eq!(schedule.due_at("plain"), Some(now + HOUR));
+    assert_eq!(schedule.due_at("ttl"), Some(now + 3 * HOUR));
+    assert_eq!(schedule.due_at("eager"), Some(now + HOUR), "Hints should never refresh more often than the interval");
+    assert_eq!(schedule.due_at("yearly"), Some(now + 24 * HOUR), "Hints should be capped");
+    assert_eq!(schedule.next_due(), Some(now + HOUR));
+
+    assert!(schedule.due(now).is_empty());
+    assert_eq!(schedule.due(now + HOUR), vec!["eager", "plain"]);
+    assert_eq!(schedule.due(now + 24 * HOUR), vec!["eager", "plain", "ttl", "yearly"]);
+}
+
+#[test]
+fn test_failing_feeds_back_off_until_they_work_again() {
+    let now = Instant::now();
+    let mut schedule = RefreshSchedule::new(settings());
+    schedule.add("feed", None, now);
+
+    assert_eq!(schedule.failed("feed", now), 2 * HOUR);
+    assert_eq!(schedule.failed("feed", now), 4 * HOUR);
+    assert_eq!(schedule.failures("feed"), 2);
+    for _ in 0..40 {
+        schedule.failed("feed", now);
+    }
+    assert_eq!(schedule.failed("feed", now), 24 * HOUR, "Backoff should be capped");
+
+    assert_eq!(schedule.succeeded("feed", Some(2 * HOUR), now), 2 * HOUR);
+    assert_eq!(schedule.failures("feed"), 0);
+    assert_eq!(schedule.due_at("feed"), Some(now + 2 * HOUR));
+}
+
+#[test]
+fn test_jitter_spreads_feeds_around_the_interval() {
+    let now = Instant::now();
+    let mut schedule = RefreshSchedule::new(ScheduleSettings { jitter: 0.1, ..settings() });
+    let waits: Vec<Duration> = (0..50).map(|_| schedule.succeeded("feed", None, now)).collect();
+
+    assert!(waits.iter().all(|wait| *wait >= HOUR.mul_f64(0.9) && *wait <= HOUR.mul_f64(1.1)), "Waits out of range: {:?}", waits);
+    assert!(waits.iter().any(|wait| *wait != waits[0]), "Waits should vary");
+}
+
+#[test]
+fn test_schedules_follow_moved_and_removed_feeds() {
+    let now = Instant::now();
+    let mut schedule = RefreshSchedule::new(settings());
+    schedule.add("old", None, now);
+    schedule.failed("old", now);
+
+    schedule.rename("old", "new");
+    assert_eq!(schedule.due_at("old"), None);
+    assert_eq!(schedule.failures("new"), 1);
+    assert!(schedule.remove("new"));
+    assert!(!schedule.remove("new"));
+    assert_eq!(schedule.next_due(), None);
+}
+
+#[test]
+fn test_background_refresh_reports_new_episodes_and_failures() {
+    let source = SharedSource::default();
+    let mut store = PodcastStore::new();
+    let episodes = vec![Episode { title: "one".to_string(), audio_url: Some("https://example.com/one.mp3".to_string()), ..Default::default() }];
+    store.add_podcast(Podcast::new("mem://feed".to_string(), "Feed".to_string(), episodes));
+    source.0.lock().unwrap().insert("mem://feed", feed(&["two", "one"]));
+
+    let interval = Duration::from_millis(20);
+    let refresh = BackgroundRefresh::spawn(store, source.clone(), ScheduleSettings { interval, jitter: 0.0, max_interval: interval * 4 });
+
+    let (podcast, new_episodes) = wait_for(&refresh, |event| match event {
+        RefreshEvent::Refreshed { podcast, refresh } => Some((podcast, refresh.new_episodes)),
+        RefreshEvent::Failed { error, .. } => panic!("Refresh failed: {}", error),
+    });
+    assert_eq!(new_episodes, 1);
+    assert_eq!(podcast.episodes()[0].title, "two");
+
+    refresh.add(Podcast::new("mem://gone".to_string(), "Gone".to_string(), Vec::new()));
+    let (feed_url, retry_in) = wait_for(&refresh, |event| match event {
+        RefreshEvent::Failed { feed_url, retry_in, .. } => Some((feed_url, retry_in)),
+        RefreshEvent::Refreshed { .. } => None,
+    });
+    assert_eq!(feed_url, "mem://gone");
+    assert_eq!(retry_in, interval * 2, "The first retry should wait twice the interval");
+}
+
+#[test]
+fn test_background_refresh_saves_moved_feeds() {
+    let (old, new) = ("https://old.example.com/feed", "https://new.example.com/feed");
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "{}\nhttps://other.example.com/feed", old).unwrap();
+    let path = file.path().to_string_lossy().to_string();
+
+    let source = MemorySource::new().with_feed(new, feed(&["one"])).with_move(old, new);
+    let mut store = PodcastStore::with_subscription_file(file.path());
+    store.add_podcast(Podcast::new(old.to_string(), "Feed".to_string(), Vec::new()));
+
+    let interval = Duration::from_millis(20);
+    let refresh = BackgroundRefresh::spawn(store, source, ScheduleSettings { interval, jitter: 0.0, max_interval: interval * 4 });
+    let moved = wait_for(&refresh, |event| match event {
+        RefreshEvent::Refreshed { refresh, .. } => refresh.moved,
+        RefreshEvent::Failed { error, .. } => panic!("Refresh failed: {}", error),
+    });
+    assert_eq!(moved.to, new);
+
+    let urls: Vec<String> = read_subscriptions(&path).unwrap().into_iter().map(|subscription| subscription.url).collect();
+    assert_eq!(urls, vec![new.to_string(), "https://other.example.com/feed".to_string()]);
+}
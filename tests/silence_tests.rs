@@ -0,0 +1,75 @@
+use rodio::buffer::SamplesBuffer;
+use rodio::Source;
+use rss_reader::silence::{TimeSavedLog, TrimCounter, TrimSettings, TrimSilence};
+use std::time::Duration;
+
+const RATE: u32 = 1000;
+
+fn settings() -> TrimSettings {
+    TrimSettings { threshold_db: -40.0, max_pause: Duration::from_millis(200) }
+}
+
+/// `loud` ms of signal, `quiet` ms of silence, then `loud` ms of signal again, in stereo.
+fn speech_with_pause(loud: usize, quiet: usize) -> SamplesBuffer<f32> {
+    let samples: Vec<f32> = std::iter::repeat_n(0.5, loud * 2)
+        .chain(std::iter::repeat_n(0.001, quiet * 2))
+        .chain(std::iter::repeat_n(-0.5, loud * 2))
+        .collect();
+    SamplesBuffer::new(2, RATE, samples)
+}
+
+#[test]
+fn test_long_pause_is_shortened() {
+    let counter = TrimCounter::new();
+    let trimmed: Vec<f32> = TrimSilence::new(speech_with_pause(100, 1000), Some(settings()), counter.clone()).collect();
+
+    // 100 ms + 200 ms kept of the pause + 100 ms, two channels each
+    assert_eq!(trimmed.len(), (100 + 200 + 100) * 2);
+    assert_eq!(counter.episode(), Duration::from_millis(800));
+    assert_eq!(counter.since_seek(), Duration::from_millis(800));
+    // Frames stay intact: the signal after the pause starts on the left channel
+    assert_eq!(&trimmed[600..602], &[-0.5, -0.5]);
+}
+
+#[test]
+fn test_short_pause_is_kept() {
+    let counter = TrimCounter::new();
+    let trimmed = TrimSilence::new(speech_with_pause(100, 150), Some(settings()), counter.clone()).count();
+
+    assert_eq!(trimmed, (100 + 150 + 100) * 2);
+    assert_eq!(counter.episode(), Duration::ZERO);
+}
+
+#[test]
+fn test_disabled_passes_through() {
+    let counter = TrimCounter::new();
+    let trimmed = TrimSilence::new(speech_with_pause(100, 1000), None, counter.clone()).count();
+
+    assert_eq!(trimmed, (100 + 1000 + 100) * 2);
+    assert_eq!(counter.episode(), Duration::ZERO);
+}
+
+#[test]
+fn test_seek_resets_position_offset_only() {
+    let counter = TrimCounter::new();
+    let mut trim = TrimSilence::new(speech_with_pause(100, 1000), Some(settings()), counter.clone());
+    trim.by_ref().take(700).for_each(drop);
+    assert_eq!(counter.since_seek(), Duration::from_millis(800));
+
+    trim.try_seek(Duration::ZERO).unwrap();
+    assert_eq!(counter.since_seek(), Duration::ZERO);
+    assert_eq!(counter.episode(), Duration::from_millis(800));
+}
+
+#[test]
+fn test_time_saved_log_persists_total() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = TimeSavedLog::default_path(dir.path());
+
+    let mut log = TimeSavedLog::load(&path).unwrap();
+    assert_eq!(log.total(), Duration::ZERO);
+    log.add(Duration::from_secs(90)).unwrap();
+    log.add(Duration::from_secs(30)).unwrap();
+
+    assert_eq!(TimeSavedLog::load(&path).unwrap().total(), Duration::from_secs(120));
+}
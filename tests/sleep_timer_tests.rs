@@ -0,0 +1,72 @@
+use rss_reader::chapters::Chapter;
+use rss_reader::sleep_timer::{fade_gain, SleepMode, SleepTimer, FADE_DURATION};
+use std::time::Duration;
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+fn chapter(start: u64, end: u64) -> Chapter {
+    Chapter { title: None, start: secs(start), end: secs(end) }
+}
+
+#[test]
+fn test_countdown_counts_listening_time() {
+    let mut timer = SleepTimer::new(SleepMode::After(secs(600)), secs(0), None, &[]).unwrap();
+    timer.elapse(secs(100));
+
+    // The position and speed do not matter for a countdown
+    assert_eq!(timer.remaining(secs(3000), 2.0), secs(500));
+    assert!(!timer.is_episode_bound());
+
+    timer.elapse(secs(1000));
+    assert_eq!(timer.remaining(secs(0), 1.0), Duration::ZERO);
+}
+
+#[test]
+fn test_end_of_episode_depends_on_position_and_speed() {
+    let timer = SleepTimer::new(SleepMode::EndOfEpisode, secs(0), Some(secs(1200)), &[]).unwrap();
+
+    assert!(timer.is_episode_bound());
+    assert_eq!(timer.remaining(secs(600), 1.0), secs(600));
+    assert_eq!(timer.remaining(secs(600), 2.0), secs(300));
+    assert_eq!(timer.remaining(secs(1300), 1.0), Duration::ZERO);
+
+    // Without a duration there is no end to wait for
+    assert!(SleepTimer::new(SleepMode::EndOfEpisode, secs(0), None, &[]).is_err());
+}
+
+#[test]
+fn test_end_of_chapter_uses_the_playing_chapter() {
+    let chapters = [chapter(0, 300), chapter(300, 900)];
+    let timer = SleepTimer::new(SleepMode::EndOfChapter, secs(400), Some(secs(900)), &chapters).unwrap();
+    assert_eq!(timer.remaining(secs(400), 1.0), secs(500));
+
+    let error = SleepTimer::new(SleepMode::EndOfChapter, secs(400), Some(secs(900)), &[]).unwrap_err();
+    assert!(error.to_string().contains("no chapters"), "{}", error);
+}
+
+#[test]
+fn test_extend_turns_a_position_into_a_countdown() {
+    let mut timer = SleepTimer::new(SleepMode::EndOfEpisode, secs(0), Some(secs(1000)), &[]).unwrap();
+    timer.extend(secs(300), secs(800), 1.0);
+
+    assert!(!timer.is_episode_bound());
+    assert_eq!(timer.remaining(secs(0), 1.0), secs(500));
+}
+
+#[test]
+fn test_zero_minutes_is_rejected() {
+    assert!(SleepTimer::new(SleepMode::After(Duration::ZERO), secs(0), None, &[]).is_err());
+}
+
+#[test]
+fn test_fade_covers_the_last_seconds() {
+    assert_eq!(fade_gain(secs(600)), 1.0);
+    assert_eq!(fade_gain(FADE_DURATION), 1.0);
+    assert_eq!(fade_gain(Duration::ZERO), 0.0);
+
+    let halfway = fade_gain(FADE_DURATION / 2);
+    assert!(halfway > 0.0 && halfway < 0.5, "Fade should be perceptual, got {}", halfway);
+    assert!(fade_gain(secs(20)) > fade_gain(secs(10)));
+}
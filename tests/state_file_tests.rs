@@ -0,0 +1,29 @@
+use rss_reader::state_file::{load_json, save_json};
+use std::collections::HashMap;
+
+#[test]
+fn test_state_round_trips_and_survives_corruption() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("state.json");
+
+    let missing: HashMap<String, f64> = load_json(&path, "state").unwrap();
+    assert!(missing.is_empty());
+
+    let state = HashMap::from([("volume".to_string(), 0.5)]);
+    save_json(&path, &state).unwrap();
+    assert_eq!(load_json::<HashMap<String, f64>>(&path, "state").unwrap(), state);
+    // Nothing is left behind next to the file
+    assert_eq!(std::fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        save_json(&path, &state).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    std::fs::write(&path, "{\"volume\": 0.5,").unwrap();
+    let corrupt: HashMap<String, f64> = load_json(&path, "state").unwrap();
+    assert!(corrupt.is_empty());
+}
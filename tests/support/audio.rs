@@ -0,0 +1,337 @@
+//! Audio files built in code, since there are no encoders to run in tests.
+
+use audiopus::coder::Encoder;
+use audiopus::{Application, Bitrate, Channels, SampleRate};
+
+/// CRC-32 as used by Ogg: polynomial 0x04c11db7, no reflection, zero initial value.
+fn ogg_crc(data: &[u8]) -> u32 {
+    data.iter().fold(0u32, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u32) << 24), |crc, _| {
+            if crc & 0x8000_0000 != 0 { (crc << 1) ^ 0x04c1_1db7 } else { crc << 1 }
+        })
+    })
+}
+
+/// An Ogg page of stream 1 holding one whole packet.
+pub fn ogg_page(header_type: u8, sequence: u32, granule: u64, packet: &[u8]) -> Vec<u8> {
+    let mut lacing = vec![255u8; packet.len() / 255];
+    lacing.push((packet.len() % 255) as u8);
+    assert!(lacing.len() <= 255, "Packet too long for one page");
+
+    let mut page = Vec::new();
+    page.extend(b"OggS");
+    page.push(0);
+    page.push(header_type);
+    page.extend(granule.to_le_bytes());
+    page.extend(1u32.to_le_bytes()); // stream serial
+    page.extend(sequence.to_le_bytes());
+    page.extend(0u32.to_le_bytes()); // checksum, filled in below
+    page.push(lacing.len() as u8);
+    page.extend(lacing);
+    page.extend(packet);
+    let crc = ogg_crc(&page);
+    page[22..26].copy_from_slice(&crc.to_le_bytes());
+    page
+}
+
+/// Packs header packets and audio packets into Ogg pages, one packet per page. Each audio
+/// packet comes with the granule position at its end.
+pub fn ogg(headers: &[Vec<u8>], packets: &[(Vec<u8>, u64)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut sequence = 0;
+    for (i, header) in headers.iter().enumerate() {
+        bytes.extend(ogg_page(if i == 0 { 0x02 } else { 0x00 }, sequence, 0, header));
+        sequence += 1;
+    }
+    for (i, (packet, granule)) in packets.iter().enumerate() {
+        let last = i + 1 == packets.len();
+        bytes.extend(ogg_page(if last { 0x04 } else { 0x00 }, sequence, *granule, packet));
+        sequence += 1;
+    }
+    bytes
+}
+
+pub const OPUS_SAMPLE_RATE: u32 = 48000;
+
+/// The samples of a 440 Hz tone that is as loud as `levels[n]` (0 to 1) in second `n`.
+pub fn tone_levels(levels: &[f32], sample_rate: u32) -> Vec<i16> {
+    levels
+        .iter()
+        .flat_map(|level| (0..sample_rate).map(move |i| (i, level)))
+        .map(|(i, level)| {
+            let t = i as f32 / sample_rate as f32;
+            ((t * 440.0 * std::f32::consts::TAU).sin() * level * i16::MAX as f32) as i16
+        })
+        .collect()
+}
+
+/// A mono Ogg Opus file of a 440 Hz tone, as loud as `levels[n]` in second `n`.
+pub fn ogg_opus(levels: &[f32]) -> Vec<u8> {
+    const FRAME: usize = 960; // 20 ms
+
+    let mut encoder = Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Audio).unwrap();
+    encoder.set_bitrate(Bitrate::BitsPerSecond(32000)).unwrap();
+    let pre_skip = encoder.lookahead().unwrap() as u64;
+
+    let mut head = b"OpusHead".to_vec();
+    head.push(1); // version
+    head.push(1); // channels
+    head.extend((pre_skip as u16).to_le_bytes());
+    head.extend(OPUS_SAMPLE_RATE.to_le_bytes());
+    head.extend(0u16.to_le_bytes()); // output gain
+    head.push(0); // channel mapping family
+
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend(4u32.to_le_bytes());
+    tags.extend(b"test");
+    tags.extend(0u32.to_le_bytes());
+
+    // The encoder lags by the pre-skip, so it gets that much silence more to flush the tone out
+    let mut samples = tone_levels(levels, OPUS_SAMPLE_RATE);
+    let length = samples.len() as u64;
+    samples.resize((samples.len() + pre_skip as usize).div_ceil(FRAME) * FRAME, 0);
+
+    let mut packets = Vec::new();
+    let mut output = [0u8; 1500];
+    for (i, frame) in samples.chunks(FRAME).enumerate() {
+        let size = encoder.encode(frame, &mut output).unwrap();
+        let granule = ((i + 1) * FRAME) as u64;
+        packets.push((output[..size].to_vec(), granule.min(pre_skip + length)));
+    }
+    ogg(&[head, tags], &packets)
+}
+
+/// A mono 16-bit FLAC file of a 440 Hz tone, as loud as `levels[n]` in second `n`. The frames
+/// store their samples verbatim: valid FLAC, just not compressed.
+pub fn flac(levels: &[f32]) -> Vec<u8> {
+    const SAMPLE_RATE: u32 = 8000;
+    const BLOCK: usize = 4096;
+    let samples = tone_levels(levels, SAMPLE_RATE);
+
+    let mut bytes = b"fLaC".to_vec();
+    bytes.push(0x80); // the last metadata block, STREAMINFO
+    bytes.extend(&34u32.to_be_bytes()[1..]);
+    bytes.extend((BLOCK as u16).to_be_bytes()); // smallest block
+    bytes.extend((BLOCK as u16).to_be_bytes()); // largest block
+    bytes.extend([0; 6]); // frame sizes unknown
+    // Sample rate (20 bits), channels - 1 (3), bits per sample - 1 (5), total samples (36)
+    bytes.extend(((SAMPLE_RATE as u64) << 44 | 15 << 36 | samples.len() as u64).to_be_bytes());
+    bytes.extend([0; 16]); // MD5 unknown
+
+    for (number, block) in samples.chunks(BLOCK).enumerate() {
+        assert!(number < 128, "Frame numbers past 127 take more than a byte");
+        // Sync code, block size after the header, 8 kHz, mono, 16 bits
+        let mut frame = vec![0xFF, 0xF8, 0x74, 0x08, number as u8];
+        frame.extend((block.len() as u16 - 1).to_be_bytes());
+        frame.push(crc(&frame, 0x07) as u8);
+        frame.push(0x02); // verbatim subframe
+        frame.extend(block.iter().flat_map(|sample| sample.to_be_bytes()));
+        let frame_crc = crc(&frame, 0x8005) as u16;
+        frame.extend(frame_crc.to_be_bytes());
+        bytes.extend(frame);
+    }
+    bytes
+}
+
+/// The FLAC header and frame checksums: CRC-8 (polynomial 0x07) or CRC-16 (0x8005), no
+/// reflection, zero initial value.
+fn crc(data: &[u8], polynomial: u32) -> u32 {
+    let width = if polynomial > 0xFF { 16 } else { 8 };
+    let top = 1 << (width - 1);
+    let mask = (1 << width) - 1;
+    data.iter().fold(0u32, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u32) << (width - 8)), |crc, _| {
+            if crc & top != 0 { ((crc << 1) ^ polynomial) & mask } else { (crc << 1) & mask }
+        })
+    })
+}
+
+/// A silent mono MP3 file at 48 kHz and 64 kbit/s: frames whose side information, and so
+/// their audio, is all zero.
+pub fn mp3(seconds: u32) -> Vec<u8> {
+    const FRAME_SAMPLES: u32 = 1152;
+    const FRAME_BYTES: usize = 192;
+    let mut frame = vec![0xFF, 0xFB, 0x54, 0xC0]; // MPEG-1 Layer III, 64 kbit/s, 48 kHz, mono
+    frame.resize(FRAME_BYTES, 0);
+    frame.repeat((seconds * 48000).div_ceil(FRAME_SAMPLES) as usize)
+}
+
+fn atom(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut atom = (8 + body.len() as u32).to_be_bytes().to_vec();
+    atom.extend(kind);
+    atom.extend(body);
+    atom
+}
+
+fn full_atom(kind: &[u8; 4], flags: u32, body: &[u8]) -> Vec<u8> {
+    let mut full = flags.to_be_bytes().to_vec(); // version 0
+    full.extend(body);
+    atom(kind, &full)
+}
+
+fn unity_matrix() -> Vec<u8> {
+    [0x0001_0000u32, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000].iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+/// A silent mono AAC-LC file at 48 kHz in an MP4 container, as podcasts ship `.m4a`.
+pub fn m4a(seconds: u32) -> Vec<u8> {
+    const SAMPLE_RATE: u32 = 48000;
+    const FRAME_SAMPLES: u32 = 1024;
+    // A single channel element with no scale factor bands, then the end element
+    const SILENT_FRAME: [u8; 4] = [0x00, 0x00, 0x00, 0x07];
+    let frames = (seconds * SAMPLE_RATE).div_ceil(FRAME_SAMPLES);
+    let duration = frames * FRAME_SAMPLES;
+
+    let moov = |data_offset: u32| {
+        let mut mvhd = [0u32, 0, SAMPLE_RATE, duration, 0x0001_0000].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+        mvhd.extend(0x0100u16.to_be_bytes()); // volume
+        mvhd.extend([0; 10]);
+        mvhd.extend(unity_matrix());
+        mvhd.extend([0; 24]);
+        mvhd.extend(2u32.to_be_bytes()); // next track
+
+        let mut tkhd = [0u32, 0, 1, 0, duration, 0, 0, 0].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+        tkhd.extend(0x0100u16.to_be_bytes()); // volume
+        tkhd.extend([0; 2]);
+        tkhd.extend(unity_matrix());
+        tkhd.extend([0; 8]); // width and height
+
+        let mut mdhd = [0u32, 0, SAMPLE_RATE, duration].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+        mdhd.extend([0x55, 0xC4, 0, 0]); // language "und"
+
+        let mut hdlr = vec![0; 4];
+        hdlr.extend(b"soun");
+        hdlr.extend([0; 12]);
+        hdlr.extend(b"Sound\0");
+
+        // ES descriptor > decoder config (MPEG-4 audio) > AudioSpecificConfig (AAC-LC, 48 kHz, mono)
+        let mut esds = vec![0x03, 25, 0, 0, 0];
+        esds.extend([0x04, 17, 0x40, 0x15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        esds.extend([0x05, 2, 0x11, 0x88]);
+        esds.extend([0x06, 1, 0x02]);
+
+        let mut mp4a = vec![0, 0, 0, 0, 0, 0, 0, 1]; // reserved, data reference 1
+        mp4a.extend([0; 8]); // version, revision, vendor
+        mp4a.extend([0, 1, 0, 16, 0, 0, 0, 0]); // mono, 16 bits
+        mp4a.extend((SAMPLE_RATE << 16).to_be_bytes());
+        mp4a.extend(full_atom(b"esds", 0, &esds));
+
+        let mut stsd = 1u32.to_be_bytes().to_vec();
+        stsd.extend(atom(b"mp4a", &mp4a));
+        let stts = [1, frames, FRAME_SAMPLES].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+        let stsc = [1, 1, frames, 1].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+        let stsz = [SILENT_FRAME.len() as u32, frames].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+        let stco = [1, data_offset].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+        let stbl = [
+            full_atom(b"stsd", 0, &stsd),
+            full_atom(b"stts", 0, &stts),
+            full_atom(b"stsc", 0, &stsc),
+            full_atom(b"stsz", 0, &stsz),
+            full_atom(b"stco", 0, &stco),
+        ]
+        .concat();
+
+        let mut dref = 1u32.to_be_bytes().to_vec();
+        dref.extend(full_atom(b"url ", 1, &[])); // the media is in this file
+        let minf = [full_atom(b"smhd", 0, &[0; 4]), atom(b"dinf", &full_atom(b"dref", 0, &dref)), atom(b"stbl", &stbl)].concat();
+        let mdia = [full_atom(b"mdhd", 0, &mdhd), full_atom(b"hdlr", 0, &hdlr), atom(b"minf", &minf)].concat();
+        let trak = [full_atom(b"tkhd", 7, &tkhd), atom(b"mdia", &mdia)].concat();
+        atom(b"moov", &[full_atom(b"mvhd", 0, &mvhd), atom(b"trak", &trak)].concat())
+    };
+
+    let ftyp = atom(b"ftyp", b"M4A \0\0\0\0M4A mp42isom");
+    let data_offset = (ftyp.len() + moov(0).len() + 8) as u32;
+    [ftyp, moov(data_offset), atom(b"mdat", &SILENT_FRAME.repeat(frames as usize))].concat()
+}
+
+/// Packs values least significant bit first, as Vorbis headers are.
+#[derive(Default)]
+struct VorbisBits {
+    bytes: Vec<u8>,
+    used: usize,
+}
+
+impl VorbisBits {
+    fn put(&mut self, value: u32, bits: usize) -> &mut Self {
+        for bit in 0..bits {
+            if self.used.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if value >> bit & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 1 << (self.used % 8);
+            }
+            self.used += 1;
+        }
+        self
+    }
+}
+
+/// A silent mono Ogg Vorbis file at 8 kHz. The setup header has the least a stream can: one
+/// two-entry codebook, a floor, a residue, a mapping and a mode, and every audio packet marks
+/// its floor unused, which decodes to silence.
+pub fn ogg_vorbis(seconds: u32) -> Vec<u8> {
+    const SAMPLE_RATE: u32 = 8000;
+    const BLOCK_EXPONENT: u8 = 11;
+    const PACKET_SAMPLES: u64 = 1 << (BLOCK_EXPONENT - 1); // blocks overlap by half
+
+    let mut ident = b"\x01vorbis".to_vec();
+    ident.extend(0u32.to_le_bytes()); // version
+    ident.push(1); // channels
+    ident.extend(SAMPLE_RATE.to_le_bytes());
+    ident.extend([0; 12]); // bitrates
+    ident.push(BLOCK_EXPONENT << 4 | BLOCK_EXPONENT); // both block sizes 2048
+    ident.push(1); // framing
+
+    let mut comments = b"\x03vorbis".to_vec();
+    comments.extend(4u32.to_le_bytes());
+    comments.extend(b"test");
+    comments.extend(0u32.to_le_bytes());
+    comments.push(1); // framing
+
+    let mut setup = VorbisBits::default();
+    setup
+        .put(0, 8) // one codebook
+        .put(0x564342, 24)
+        .put(1, 16) // dimensions
+        .put(2, 24) // entries
+        .put(0, 2) // not ordered, not sparse
+        .put(0, 5)
+        .put(0, 5) // both codewords one bit long
+        .put(0, 4) // no lookup table
+        .put(0, 6)
+        .put(0, 16) // one time domain transform, a placeholder
+        .put(0, 6)
+        .put(1, 16) // one floor, type 1
+        .put(0, 5) // no partitions
+        .put(0, 2) // multiplier 1
+        .put(10, 4) // range bits
+        .put(0, 6)
+        .put(0, 16) // one residue, type 0
+        .put(0, 24)
+        .put(0, 24) // empty
+        .put(0, 24) // partition size 1
+        .put(0, 6) // one classification
+        .put(0, 8) // classbook 0
+        .put(0, 4) // no cascade
+        .put(0, 6)
+        .put(0, 16) // one mapping, type 0
+        .put(0, 4) // one submap, no coupling, reserved
+        .put(0, 8)
+        .put(0, 8)
+        .put(0, 8) // submap: time, floor 0, residue 0
+        .put(0, 6) // one mode
+        .put(0, 1) // short blocks
+        .put(0, 16)
+        .put(0, 16) // window and transform types
+        .put(0, 8) // mapping 0
+        .put(1, 1); // framing
+    let mut setup_packet = b"\x05vorbis".to_vec();
+    setup_packet.extend(&setup.bytes);
+
+    // The first block only primes the overlap, so one more packet than the samples need
+    let length = (seconds * SAMPLE_RATE) as u64;
+    let packets: Vec<(Vec<u8>, u64)> = (0..=length.div_ceil(PACKET_SAMPLES))
+        .map(|i| (vec![0x00], (i * PACKET_SAMPLES).min(length))) // audio packet, mode 0, floor unused
+        .collect();
+    ogg(&[ident, comments, setup_packet], &packets)
+}
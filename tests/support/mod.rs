@@ -0,0 +1,293 @@
+//! Test support: a local HTTP server serving canned feeds and audio, and audio files to serve.
+//!
+//! Every test file that needs it declares `mod support;`, so parts it does not use are expected.
+#![allow(dead_code)]
+
+pub mod audio;
+
+use rss_reader::http::{HttpClient, HttpSettings};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// HTTP settings that keep failing tests fast: retries wait milliseconds, not seconds.
+pub fn fast_settings() -> HttpSettings {
+    HttpSettings { retry_backoff: Duration::from_millis(10), ..Default::default() }
+}
+
+/// A client with `fast_settings`, for tests that do not tune the settings further.
+pub fn fast_client() -> HttpClient {
+    HttpClient::new(fast_settings()).unwrap()
+}
+
+/// A canned response.
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Waits this long before answering, e.g. to trigger client timeouts.
+    pub delay: Duration,
+    /// Answers `If-None-Match` with this tag by 304 Not Modified.
+    pub etag: Option<String>,
+    /// Answers `Range` requests with 206 Partial Content.
+    pub ranges: bool,
+}
+
+impl Fixture {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self { status: 200, headers: Vec::new(), body: body.into(), delay: Duration::ZERO, etag: None, ranges: false }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self { status, ..Self::ok(Vec::new()) }
+    }
+
+    /// An RSS document.
+    pub fn feed(xml: impl Into<Vec<u8>>) -> Self {
+        Self::ok(xml).with_header("Content-Type", "application/rss+xml")
+    }
+
+    /// An RSS document read from the `tests` directory, e.g. `test-feed.rss`.
+    pub fn feed_file(name: &str) -> Self {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join(name);
+        Self::feed(std::fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e)))
+    }
+
+    /// Audio that supports range requests, like most podcast hosts.
+    pub fn audio(bytes: impl Into<Vec<u8>>, mime_type: &str) -> Self {
+        Self { ranges: true, ..Self::ok(bytes).with_header("Content-Type", mime_type) }
+    }
+
+    pub fn redirect(status: u16, location: &str) -> Self {
+        Self::status(status).with_header("Location", location)
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_etag(mut self, etag: &str) -> Self {
+        self.etag = Some(etag.to_string());
+        self
+    }
+
+    pub fn with_ranges(mut self) -> Self {
+        self.ranges = true;
+        self
+    }
+}
+
+/// A request as the server received it.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// The path including any query string.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl RecordedRequest {
+    /// The value of a header, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Responses per path; the last one repeats once the others are used up.
+    routes: HashMap<String, VecDeque<Fixture>>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// An HTTP/1.1 server on a free local port, serving until it is dropped.
+///
+/// Unknown paths get 404. Each connection carries one request and is then closed.
+pub struct FixtureServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl FixtureServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind the fixture server");
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(State::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let (accept_state, accept_shutdown) = (state.clone(), shutdown.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let state = accept_state.clone();
+                thread::spawn(move || {
+                    let _ = handle(stream, &state);
+                });
+            }
+        });
+
+        Self { addr, state, shutdown }
+    }
+
+    /// The absolute URL of `path` on this server.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// Serves `fixture` at `path` from now on.
+    pub fn serve(&self, path: &str, fixture: Fixture) -> &Self {
+        self.serve_sequence(path, vec![fixture])
+    }
+
+    /// Serves the fixtures at `path` one request at a time, repeating the last one.
+    pub fn serve_sequence(&self, path: &str, fixtures: Vec<Fixture>) -> &Self {
+        assert!(!fixtures.is_empty(), "A route needs at least one fixture");
+        self.state.lock().unwrap().routes.insert(path.to_string(), fixtures.into());
+        self
+    }
+
+    /// Every request received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests().into_iter().filter(|request| request.path == path).collect()
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+fn handle(stream: TcpStream, state: &Mutex<State>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(());
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let request = RecordedRequest { method: method.to_string(), path: path.to_string(), headers };
+    if let Some(length) = request.header("Content-Length").and_then(|l| l.parse::<u64>().ok()) {
+        std::io::copy(&mut reader.by_ref().take(length), &mut std::io::sink())?;
+    }
+
+    let fixture = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(request.clone());
+        let route = if state.routes.contains_key(path) { path } else { path.split('?').next().unwrap_or(path) };
+        state.routes.get_mut(route).map(|fixtures| match fixtures.len() {
+            1 => fixtures[0].clone(),
+            _ => fixtures.pop_front().unwrap(),
+        })
+    };
+    let fixture = fixture.unwrap_or_else(|| Fixture::status(404));
+    thread::sleep(fixture.delay);
+    respond(stream, &request, fixture)
+}
+
+fn respond(mut stream: TcpStream, request: &RecordedRequest, fixture: Fixture) -> std::io::Result<()> {
+    let mut status = fixture.status;
+    let mut headers = fixture.headers.clone();
+    let mut body = fixture.body.clone();
+
+    if let Some(etag) = &fixture.etag {
+        headers.push(("ETag".to_string(), etag.clone()));
+        if request.header("If-None-Match") == Some(etag.as_str()) {
+            status = 304;
+            body.clear();
+        }
+    }
+
+    if fixture.ranges && status == 200 {
+        headers.push(("Accept-Ranges".to_string(), "bytes".to_string()));
+        if let Some(range) = request.header("Range") {
+            let total = body.len();
+            match parse_range(range, total) {
+                Some((start, end)) => {
+                    status = 206;
+                    headers.push(("Content-Range".to_string(), format!("bytes {}-{}/{}", start, end, total)));
+                    body = body[start..=end].to_vec();
+                }
+                None => {
+                    status = 416;
+                    headers.push(("Content-Range".to_string(), format!("bytes */{}", total)));
+                    body.clear();
+                }
+            }
+        }
+    }
+
+    let mut head = format!("HTTP/1.1 {} {}\r\n", status, reason(status));
+    for (name, value) in &headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", body.len()));
+    stream.write_all(head.as_bytes())?;
+    if request.method != "HEAD" {
+        stream.write_all(&body)?;
+    }
+    stream.flush()
+}
+
+/// The inclusive byte range of a `bytes=` header, or `None` when it cannot be satisfied.
+fn parse_range(header: &str, total: usize) -> Option<(usize, usize)> {
+    let (start, end) = header.strip_prefix("bytes=")?.split_once('-')?;
+    let last = total.checked_sub(1)?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => (total.saturating_sub(suffix.parse().ok()?), last),
+        (start, "") => (start.parse().ok()?, last),
+        (start, end) => (start.parse().ok()?, end.parse::<usize>().ok()?.min(last)),
+    };
+    (start <= end).then_some((start, end))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        404 => "Not Found",
+        416 => "Range Not Satisfiable",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
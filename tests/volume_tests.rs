@@ -0,0 +1,92 @@
+use rodio::buffer::SamplesBuffer;
+use rss_reader::loudness::SharedGain;
+use rss_reader::silence::{TrimCounter, TrimSettings, TrimSilence};
+use rss_reader::volume::{amplitude, Volume, VolumeControl};
+use std::time::Duration;
+
+#[test]
+fn test_curve_is_logarithmic() {
+    assert_eq!(amplitude(0.0), 0.0);
+    assert!((amplitude(1.0) - 1.0).abs() < f32::EPSILON);
+    // Equal steps in level are equal ratios in amplitude
+    let ratio_low = amplitude(0.3) / amplitude(0.2);
+    let ratio_high = amplitude(0.9) / amplitude(0.8);
+    assert!((ratio_low - ratio_high).abs() < 0.001);
+    assert!((amplitude(0.5) - 0.1).abs() < 0.001, "0.5 should be -20 dB");
+}
+
+#[test]
+fn test_level_is_clamped_to_maximum() {
+    let mut volume = VolumeControl::new(1.0);
+    for _ in 0..20 {
+        volume.adjust(0.1);
+    }
+    assert_eq!(volume.level(), 1.0);
+
+    volume.set(-3.0);
+    assert_eq!(volume.level(), 0.0);
+
+    let mut boosted = VolumeControl::new(1.5);
+    boosted.set(1.4);
+    assert_eq!(boosted.level(), 1.4);
+    assert!(boosted.amplitude() > 1.0);
+}
+
+#[test]
+fn test_mute_keeps_level() {
+    let mut volume = VolumeControl::new(1.0);
+    volume.set(0.7);
+    volume.toggle_mute();
+    assert!(volume.is_muted());
+    assert_eq!(volume.amplitude(), 0.0);
+    assert_eq!(volume.level(), 0.7);
+
+    volume.toggle_mute();
+    assert!((volume.amplitude() - amplitude(0.7)).abs() < f32::EPSILON);
+
+    // Changing the volume while muted unmutes
+    volume.toggle_mute();
+    volume.adjust(-0.1);
+    assert!(!volume.is_muted());
+}
+
+#[test]
+fn test_volume_persists_across_sessions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = VolumeControl::default_path(dir.path());
+
+    let mut volume = VolumeControl::load(&path, 1.0).unwrap();
+    assert_eq!(volume.level(), 1.0);
+    volume.set(0.4);
+    volume.toggle_mute();
+    assert!(!path.exists(), "Changes are only written when saved");
+    volume.save().unwrap();
+
+    let restored = VolumeControl::load(&path, 1.0).unwrap();
+    assert_eq!(restored.level(), 0.4);
+    assert!(restored.is_muted());
+
+    // A lower maximum than last time still applies
+    assert_eq!(VolumeControl::load(&path, 0.3).unwrap().level(), 0.3);
+
+    // Saving a volume that ends up where it started does not write
+    std::fs::remove_file(&path).unwrap();
+    let mut unchanged = VolumeControl::load(&path, 1.0).unwrap();
+    unchanged.toggle_mute();
+    unchanged.toggle_mute();
+    unchanged.save().unwrap();
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_muting_does_not_trim_the_episode_as_silence() {
+    // Two seconds of speech-level audio at 1 kHz, muted from the start
+    let speech = SamplesBuffer::new(1, 1000, vec![0.5f32; 2000]);
+    let settings = TrimSettings { threshold_db: -40.0, max_pause: Duration::from_millis(200) };
+    let counter = TrimCounter::new();
+    let muted: Vec<f32> = Volume::new(TrimSilence::new(speech, Some(settings), counter.clone()), SharedGain::new(0.0)).collect();
+
+    assert_eq!(muted.len(), 2000, "Muted audio still plays for its full length");
+    assert!(muted.iter().all(|&sample| sample == 0.0));
+    assert_eq!(counter.episode(), Duration::ZERO);
+}